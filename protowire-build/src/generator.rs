@@ -0,0 +1,948 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use protowire::{
+    DescriptorPool, EnumDescriptor, FieldDescriptor, Kind, Label, MessageDescriptor,
+    OneofDescriptor,
+};
+
+use crate::names::{ident, relative_path, snake_case, upper_camel_case};
+
+/// Generated source, indented by the braces which open and close its lines
+#[derive(Default)]
+struct Code {
+    text: String,
+    indent: usize,
+}
+
+impl Code {
+    fn line(&mut self, line: &str) {
+        if line.starts_with(['}', ')', ']']) {
+            self.indent -= 1;
+        }
+        if !line.is_empty() {
+            for _ in 0..self.indent {
+                self.text.push_str("    ");
+            }
+        }
+        self.text.push_str(line);
+        self.text.push('\n');
+        if line.ends_with(['{', '(', '[']) {
+            self.indent += 1;
+        }
+    }
+
+    /// a blank line between two items, unless this is the first item of a block
+    fn separate(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with("{\n") {
+            self.text.push('\n');
+        }
+    }
+}
+
+macro_rules! emit {
+    ($code:expr, $($arg:tt)*) => {
+        $code.line(&format!($($arg)*))
+    };
+}
+
+/// the code for every file in `pool`, one output file per package, named by the package
+/// like `foo.bar.rs` or `_.rs` for files without one
+pub(crate) fn generate(pool: &DescriptorPool) -> Vec<(String, String)> {
+    let mut files: BTreeMap<String, Code> = BTreeMap::new();
+    for file in pool.files() {
+        let name = match file.package() {
+            "" => "_.rs".to_string(),
+            package => format!("{package}.rs"),
+        };
+        let code = files.entry(name).or_default();
+        let module = package_module(file.package());
+        for descriptor in file.enums() {
+            enum_code(code, descriptor);
+        }
+        for descriptor in file.messages() {
+            message_code(code, descriptor, &module);
+        }
+    }
+    files
+        .into_iter()
+        .map(|(name, code)| (name, code.text))
+        .collect()
+}
+
+fn package_module(package: &str) -> Vec<String> {
+    package
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(ident)
+        .collect()
+}
+
+/// the module holding the types nested in `message`
+fn nested_module(message: MessageDescriptor<'_>) -> Vec<String> {
+    let mut module = match message.parent_message() {
+        Some(parent) => nested_module(parent),
+        None => package_module(message.file().package()),
+    };
+    module.push(ident(&snake_case(message.name())));
+    module
+}
+
+fn message_path(message: MessageDescriptor<'_>) -> Vec<String> {
+    let mut path = match message.parent_message() {
+        Some(parent) => nested_module(parent),
+        None => package_module(message.file().package()),
+    };
+    path.push(ident(message.name()));
+    path
+}
+
+fn enum_path(descriptor: EnumDescriptor<'_>) -> Vec<String> {
+    let mut path = match descriptor.parent_message() {
+        Some(parent) => nested_module(parent),
+        None => package_module(descriptor.file().package()),
+    };
+    path.push(ident(descriptor.name()));
+    path
+}
+
+fn enum_code(code: &mut Code, descriptor: EnumDescriptor<'_>) {
+    let name = ident(descriptor.name());
+    code.separate();
+    emit!(code, "/// `{}`", descriptor.full_name());
+    code.line("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]");
+    emit!(code, "pub struct {name}(pub i32);");
+    code.line("");
+    let lower_case = descriptor
+        .values()
+        .any(|value| value.name().chars().any(char::is_lowercase));
+    if lower_case {
+        code.line("#[allow(non_upper_case_globals)]");
+    }
+    emit!(code, "impl {name} {{");
+    for value in descriptor.values() {
+        emit!(
+            code,
+            "pub const {}: Self = Self({});",
+            ident(value.name()),
+            value.number()
+        );
+    }
+    code.line("");
+    code.line("/// the name of the value in the schema, `None` for numbers it doesn't declare");
+    code.line("pub fn name(self) -> Option<&'static str> {");
+    code.line("match self.0 {");
+    let mut numbers = Vec::new();
+    for value in descriptor.values() {
+        // aliases share a number, the first name declared wins
+        if !numbers.contains(&value.number()) {
+            numbers.push(value.number());
+            emit!(code, "{} => Some(\"{}\"),", value.number(), value.name());
+        }
+    }
+    code.line("_ => None,");
+    code.line("}");
+    code.line("}");
+    code.line("");
+    code.line("/// the value declared with `name` in the schema");
+    code.line("pub fn from_name(name: &str) -> Option<Self> {");
+    code.line("match name {");
+    for value in descriptor.values() {
+        emit!(
+            code,
+            "\"{}\" => Some(Self::{}),",
+            value.name(),
+            ident(value.name())
+        );
+    }
+    code.line("_ => None,");
+    code.line("}");
+    code.line("}");
+    code.line("}");
+}
+
+/// How a scalar is read from and written to the wire
+struct Scalar {
+    /// the Rust type
+    ty: String,
+    /// the name shared by `Varint`, `I32` and `I64` and their packed counterparts
+    wire: &'static str,
+    /// reads `v`, a `VarintRef`, `I32Ref` or `I64Ref`
+    read: String,
+    /// reads `v`, a `u64`, `i32` or `i64` from `get_all_varints` and the like
+    read_value: String,
+    /// writes `value` as a `Varint`, `I32` or `I64`
+    write: String,
+}
+
+fn scalar(kind: Kind<'_>, from: &[String]) -> Option<Scalar> {
+    let (ty, wire, read, read_value, write) = match kind {
+        Kind::Double => (
+            "f64",
+            "I64",
+            "v.get_double()",
+            "f64::from_bits(v as u64)",
+            "I64::new_double(value)",
+        ),
+        Kind::Float => (
+            "f32",
+            "I32",
+            "v.get_float()",
+            "f32::from_bits(v as u32)",
+            "I32::new_float(value)",
+        ),
+        Kind::Int64 => (
+            "i64",
+            "Varint",
+            "v.as_proto_int64()",
+            "v as i64",
+            "Varint::new_proto_int64(value)",
+        ),
+        Kind::Uint64 => ("u64", "Varint", "v.get()", "v", "Varint::new(value)"),
+        Kind::Int32 => (
+            "i32",
+            "Varint",
+            "v.as_proto_int32()",
+            "v as i32",
+            "Varint::new_proto_int32(value)",
+        ),
+        Kind::Fixed64 => (
+            "u64",
+            "I64",
+            "v.get() as u64",
+            "v as u64",
+            "I64::new(value as i64)",
+        ),
+        Kind::Fixed32 => (
+            "u32",
+            "I32",
+            "v.get() as u32",
+            "v as u32",
+            "I32::new(value as i32)",
+        ),
+        Kind::Bool => (
+            "bool",
+            "Varint",
+            "v.get() != 0",
+            "v != 0",
+            "Varint::new(value as u64)",
+        ),
+        Kind::Uint32 => (
+            "u32",
+            "Varint",
+            "v.get() as u32",
+            "v as u32",
+            "Varint::new(value as u64)",
+        ),
+        Kind::Sfixed32 => ("i32", "I32", "v.get()", "v", "I32::new(value)"),
+        Kind::Sfixed64 => ("i64", "I64", "v.get()", "v", "I64::new(value)"),
+        Kind::Sint32 => (
+            "i32",
+            "Varint",
+            "v.as_proto_sint32()",
+            "((v as u32) >> 1) as i32 ^ -((v & 1) as i32)",
+            "Varint::new_proto_sint32(value)",
+        ),
+        Kind::Sint64 => (
+            "i64",
+            "Varint",
+            "v.as_proto_sint64()",
+            "(v >> 1) as i64 ^ -((v & 1) as i64)",
+            "Varint::new_proto_sint64(value)",
+        ),
+        Kind::Enum(descriptor) => {
+            let ty = relative_path(from, &enum_path(descriptor));
+            return Some(Scalar {
+                read: format!("{ty}(v.as_proto_int32())"),
+                read_value: format!("{ty}(v as i32)"),
+                ty,
+                wire: "Varint",
+                write: "::protowire::Varint::new_proto_int32(value.0)".to_string(),
+            });
+        }
+        Kind::String | Kind::Bytes | Kind::Message(_) | Kind::Group(_) => return None,
+    };
+    Some(Scalar {
+        ty: ty.to_string(),
+        wire,
+        read: read.to_string(),
+        read_value: read_value.to_string(),
+        write: format!("::protowire::{write}"),
+    })
+}
+
+fn wire_lower(wire: &str) -> &'static str {
+    match wire {
+        "Varint" => "varint",
+        "I32" => "i32",
+        _ => "i64",
+    }
+}
+
+/// the type a field is declared with, as written in a `.proto` file
+fn proto_type(field: FieldDescriptor<'_>) -> String {
+    let name = match field.kind() {
+        Kind::Double => "double",
+        Kind::Float => "float",
+        Kind::Int64 => "int64",
+        Kind::Uint64 => "uint64",
+        Kind::Int32 => "int32",
+        Kind::Fixed64 => "fixed64",
+        Kind::Fixed32 => "fixed32",
+        Kind::Bool => "bool",
+        Kind::String => "string",
+        Kind::Bytes => "bytes",
+        Kind::Uint32 => "uint32",
+        Kind::Sfixed32 => "sfixed32",
+        Kind::Sfixed64 => "sfixed64",
+        Kind::Sint32 => "sint32",
+        Kind::Sint64 => "sint64",
+        Kind::Enum(descriptor) => descriptor.full_name(),
+        Kind::Message(descriptor) | Kind::Group(descriptor) => descriptor.full_name(),
+    };
+    let label = match field.label() {
+        Label::Repeated => "repeated ",
+        _ if field.is_proto3_optional() => "optional ",
+        Label::Required => "required ",
+        _ => "",
+    };
+    format!("{label}{name} {} = {}", field.name(), field.number())
+}
+
+/// the expression building a `MessageObject` for a single `value` of `kind`, which is a
+/// `&str` for strings, a `&[u8]` for bytes and the generated type for messages. Groups
+/// are fallible, so the expression uses `?`
+fn object(kind: Kind<'_>, number: u32, value: &str, from: &[String]) -> String {
+    match kind {
+        Kind::String => format!("::protowire::MessageObject::Len(::protowire::Len::new_string({value}))"),
+        Kind::Bytes => format!("::protowire::MessageObject::Len(::protowire::Len::new_bytes({value}))"),
+        Kind::Message(_) => format!(
+            "::protowire::MessageObject::Len(::protowire::Len::new_message({value}.into()))"
+        ),
+        Kind::Group(_) => format!(
+            "::protowire::MessageObject::Group(::protowire::Group::from_message({number}, {value}.into())?)"
+        ),
+        kind => {
+            let scalar = scalar(kind, from).expect("every other kind is a scalar");
+            format!(
+                "::protowire::MessageObject::{}({})",
+                scalar.wire,
+                scalar.write.replace("value", value)
+            )
+        }
+    }
+}
+
+/// the type a singular field is set with
+fn param_type(kind: Kind<'_>, from: &[String]) -> String {
+    match kind {
+        Kind::String => "&str".to_string(),
+        Kind::Bytes => "&[u8]".to_string(),
+        Kind::Message(descriptor) | Kind::Group(descriptor) => {
+            relative_path(from, &message_path(descriptor))
+        }
+        kind => scalar(kind, from).expect("every other kind is a scalar").ty,
+    }
+}
+
+/// the item type a repeated field is set with, and how each item is passed to `object`
+fn item_type(kind: Kind<'_>, from: &[String]) -> (String, &'static str) {
+    match kind {
+        Kind::String => ("impl AsRef<str>".to_string(), "value.as_ref()"),
+        Kind::Bytes => ("impl AsRef<[u8]>".to_string(), "value.as_ref()"),
+        kind => (param_type(kind, from), "value"),
+    }
+}
+
+/// pushes every item of `values` onto `target`, packed if the field is
+fn push_repeated(code: &mut Code, target: &str, field: FieldDescriptor<'_>, from: &[String]) {
+    let number = field.number();
+    match scalar(field.kind(), from) {
+        Some(scalar) if field.is_packed() => {
+            emit!(
+                code,
+                "let mut packed = ::protowire::PackedRepeated{}::new();",
+                scalar.wire
+            );
+            code.line("for value in values {");
+            emit!(code, "packed.push({});", scalar.write);
+            code.line("}");
+            code.line("if !packed.is_empty() {");
+            code.line("let mut len = ::protowire::Len::new();");
+            emit!(
+                code,
+                "len.set_packed_repeated_{}(packed);",
+                wire_lower(scalar.wire)
+            );
+            emit!(
+                code,
+                "{target}.push(::protowire::Field::new({number}, ::protowire::MessageObject::Len(len)));"
+            );
+            code.line("}");
+        }
+        _ => {
+            let (_, value) = item_type(field.kind(), from);
+            code.line("for value in values {");
+            emit!(
+                code,
+                "{target}.push(::protowire::Field::new({number}, {}));",
+                object(field.kind(), number, value, from)
+            );
+            code.line("}");
+        }
+    }
+}
+
+fn oneof_enum_name(oneof: OneofDescriptor<'_>) -> String {
+    ident(&upper_camel_case(oneof.name()))
+}
+
+/// the methods of every message view and builder, which accessors mustn't collide with
+const GENERATED_METHODS: [&str; 7] = [
+    "FULL_NAME",
+    "new",
+    "from_wire_data",
+    "builder",
+    "as_message",
+    "into_wire_data",
+    "build",
+];
+
+/// the accessor names of the fields and oneofs of `message`, with `_` appended to those which
+/// would collide with a generated method, like `new_`, or with the setters of another field
+fn accessor_names(message: MessageDescriptor<'_>) -> HashMap<&str, String> {
+    let names: Vec<&str> = message
+        .fields()
+        .map(|field| field.name())
+        .chain(
+            message
+                .oneofs()
+                .filter(|oneof| !oneof.is_synthetic())
+                .map(|oneof| oneof.name()),
+        )
+        .collect();
+    let mut taken: HashSet<String> = GENERATED_METHODS
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    for name in &names {
+        taken.insert(format!("set_{name}"));
+        taken.insert(format!("clear_{name}"));
+    }
+    // names which are free keep them, before any others are renamed
+    let mut accessors = HashMap::new();
+    for name in &names {
+        if ident(name) == *name && !taken.contains(*name) {
+            accessors.insert(*name, name.to_string());
+        }
+    }
+    taken.extend(accessors.values().cloned());
+    for name in names {
+        if !accessors.contains_key(name) {
+            let mut accessor = ident(name);
+            while taken.contains(&accessor) {
+                accessor.push('_');
+            }
+            taken.insert(accessor.clone());
+            accessors.insert(name, accessor);
+        }
+    }
+    accessors
+}
+
+fn accessor_name(message: MessageDescriptor<'_>, name: &str) -> String {
+    accessor_names(message).remove(name).unwrap()
+}
+
+/// `{Name}Builder`, with `_` appended while another type in the same module has that name
+fn builder_name(message: MessageDescriptor<'_>) -> String {
+    let siblings: HashSet<String> = match message.parent_message() {
+        Some(parent) => parent
+            .nested_messages()
+            .map(|nested| ident(nested.name()))
+            .chain(parent.nested_enums().map(|nested| ident(nested.name())))
+            .chain(
+                parent
+                    .oneofs()
+                    .filter(|oneof| !oneof.is_synthetic())
+                    .map(oneof_enum_name),
+            )
+            .collect(),
+        None => message
+            .parent_pool()
+            .files()
+            .filter(|file| file.package() == message.file().package())
+            .flat_map(|file| {
+                file.messages()
+                    .map(|sibling| ident(sibling.name()))
+                    .chain(file.enums().map(|sibling| ident(sibling.name())))
+            })
+            .collect(),
+    };
+    let mut builder = format!("{}Builder", message.name());
+    while siblings.contains(&builder) {
+        builder.push('_');
+    }
+    builder
+}
+
+/// allows an accessor named after a field to take `self` differently from what clippy expects
+/// of its prefix, like a getter for a field called `into_data`
+fn allow_self_convention(code: &mut Code, name: &str, by_value: bool) {
+    let unexpected = match by_value {
+        true => ["as_", "from_", "is_", "to_"]
+            .iter()
+            .any(|p| name.starts_with(p)),
+        false => {
+            name.starts_with("from_")
+                || name.starts_with("into_")
+                || (name.starts_with("to_") && name.ends_with("_mut"))
+        }
+    };
+    if unexpected {
+        code.line("#[allow(clippy::wrong_self_convention)]");
+    }
+}
+
+fn message_code(code: &mut Code, message: MessageDescriptor<'_>, module: &[String]) {
+    let name = ident(message.name());
+    let builder = builder_name(message);
+    let oneofs: Vec<_> = message
+        .oneofs()
+        .filter(|oneof| !oneof.is_synthetic())
+        .collect();
+
+    code.separate();
+    emit!(code, "/// `{}`", message.full_name());
+    code.line("#[derive(Debug, Clone, Default)]");
+    emit!(code, "pub struct {name}(::protowire::Message);");
+    code.line("");
+    emit!(code, "impl From<::protowire::Message> for {name} {{");
+    code.line("fn from(message: ::protowire::Message) -> Self {");
+    code.line("Self(message)");
+    code.line("}");
+    code.line("}");
+    code.line("");
+    emit!(code, "impl From<{name}> for ::protowire::Message {{");
+    emit!(code, "fn from(message: {name}) -> Self {{");
+    code.line("message.0");
+    code.line("}");
+    code.line("}");
+    code.line("");
+    emit!(code, "impl {name} {{");
+    emit!(
+        code,
+        "pub const FULL_NAME: &'static str = \"{}\";",
+        message.full_name()
+    );
+    code.line("");
+    code.line("pub fn new() -> Self {");
+    code.line("Self::default()");
+    code.line("}");
+    code.line("");
+    code.line("pub fn from_wire_data(data: ::protowire::WireData) -> Self {");
+    code.line("Self(data.into())");
+    code.line("}");
+    code.line("");
+    emit!(code, "pub fn builder() -> {builder} {{");
+    emit!(code, "{builder}::default()");
+    code.line("}");
+    code.line("");
+    code.line("pub fn as_message(&self) -> &::protowire::Message {");
+    code.line("&self.0");
+    code.line("}");
+    code.line("");
+    code.line("pub fn into_wire_data(self) -> ::protowire::WireData {");
+    code.line("self.0.serialize()");
+    code.line("}");
+    for field in message.fields() {
+        field_accessors(code, field, module);
+    }
+    for oneof in &oneofs {
+        oneof_accessors(code, *oneof, module);
+    }
+    code.line("}");
+
+    builder_code(code, message, &builder, module);
+
+    let nested = nested_module(message);
+    let has_nested = message.nested_messages().len() > 0
+        || message.nested_enums().len() > 0
+        || !oneofs.is_empty();
+    if has_nested {
+        code.separate();
+        emit!(code, "/// the types nested in `{}`", message.full_name());
+        if module.last() == nested.last() {
+            // like `Legacy` in package `test.legacy`, which can't be helped
+            code.line("#[allow(clippy::module_inception)]");
+        }
+        emit!(code, "pub mod {} {{", nested.last().unwrap());
+        for descriptor in message.nested_enums() {
+            enum_code(code, descriptor);
+        }
+        for oneof in &oneofs {
+            oneof_enum(code, *oneof, &nested);
+        }
+        for descriptor in message.nested_messages() {
+            message_code(code, descriptor, &nested);
+        }
+        code.line("}");
+    }
+}
+
+fn field_accessors(code: &mut Code, field: FieldDescriptor<'_>, from: &[String]) {
+    let name = accessor_name(field.containing_message(), field.name());
+    let number = field.number();
+    let kind = field.kind();
+    let result = |ty: &str| format!("Result<{ty}, ::protowire::DecodeError>");
+
+    // getter
+    code.separate();
+    emit!(code, "/// `{}`", proto_type(field));
+    allow_self_convention(code, &name, false);
+    if field.label() == Label::Repeated {
+        match (kind, scalar(kind, from)) {
+            (_, Some(scalar)) => {
+                let ty = result(&format!("Vec<{}>", scalar.ty));
+                emit!(code, "pub fn {name}(&self) -> {ty} {{");
+                let get_all = format!("self.0.get_all_{}s({number})", wire_lower(scalar.wire));
+                match scalar.read_value.as_str() {
+                    "v" => code.line(&get_all),
+                    read => emit!(code, "Ok({get_all}?.into_iter().map(|v| {read}).collect())"),
+                }
+            }
+            (Kind::String, _) => {
+                emit!(code, "pub fn {name}(&self) -> {} {{", result("Vec<&str>"));
+                emit!(code, "self.0.get_all_strs({number})");
+            }
+            (Kind::Bytes, _) => {
+                emit!(code, "pub fn {name}(&self) -> {} {{", result("Vec<&[u8]>"));
+                emit!(
+                    code,
+                    "Ok(self.0.get_all_lens({number})?.into_iter().map(|len| len.get_data()).collect())"
+                );
+            }
+            (_, None) => {
+                let ty = param_type(kind, from);
+                emit!(
+                    code,
+                    "pub fn {name}(&self) -> {} {{",
+                    result(&format!("Vec<{ty}>"))
+                );
+                emit!(
+                    code,
+                    "Ok(self.0.get_all_messages({number})?.into_iter().map({ty}::from).collect())"
+                );
+            }
+        }
+    } else {
+        let presence = field.has_presence();
+        let (ty, value) = match (kind, scalar(kind, from)) {
+            (_, Some(scalar)) => (
+                scalar.ty,
+                format!(
+                    "self.0.get_{}({number})?.map(|v| {})",
+                    wire_lower(scalar.wire),
+                    scalar.read
+                ),
+            ),
+            (Kind::String, _) if presence => {
+                emit!(
+                    code,
+                    "pub fn {name}(&self) -> {} {{",
+                    result("Option<&str>")
+                );
+                emit!(code, "self.0.get_str({number})");
+                code.line("}");
+                return setters(code, field, from);
+            }
+            (Kind::String, _) => ("&str".to_string(), format!("self.0.get_str({number})?")),
+            (Kind::Bytes, _) => (
+                "&[u8]".to_string(),
+                format!("self.0.get_len({number})?.map(|len| len.get_data())"),
+            ),
+            (_, None) => {
+                let ty = param_type(kind, from);
+                let value = format!("self.0.get_message({number})?.map({ty}::from)");
+                (ty, value)
+            }
+        };
+        match presence {
+            true => {
+                emit!(
+                    code,
+                    "pub fn {name}(&self) -> {} {{",
+                    result(&format!("Option<{ty}>"))
+                );
+                emit!(code, "Ok({value})");
+            }
+            false => {
+                emit!(code, "pub fn {name}(&self) -> {} {{", result(&ty));
+                emit!(code, "Ok({value}.unwrap_or_default())");
+            }
+        }
+    }
+    code.line("}");
+    setters(code, field, from);
+}
+
+/// the setter, which clears the other fields of a oneof first, and `clear_` method
+fn setters(code: &mut Code, field: FieldDescriptor<'_>, from: &[String]) {
+    let number = field.number();
+    let kind = field.kind();
+    let result = |ty: &str| format!("Result<{ty}, ::protowire::DecodeError>");
+    let siblings: Vec<u32> = field
+        .containing_oneof()
+        .filter(|oneof| !oneof.is_synthetic())
+        .map(|oneof| {
+            oneof
+                .fields()
+                .map(|sibling| sibling.number())
+                .filter(|sibling| *sibling != number)
+                .collect()
+        })
+        .unwrap_or_default();
+    code.line("");
+    if field.label() == Label::Repeated {
+        let (item, _) = item_type(kind, from);
+        emit!(
+            code,
+            "pub fn set_{}(&mut self, values: impl IntoIterator<Item = {item}>) -> {} {{",
+            field.name(),
+            result("()")
+        );
+        emit!(code, "self.0.remove({number})?;");
+        push_repeated(code, "self.0", field, from);
+        code.line("Ok(())");
+    } else {
+        emit!(
+            code,
+            "pub fn set_{}(&mut self, value: {}) -> {} {{",
+            field.name(),
+            param_type(kind, from),
+            result("()")
+        );
+        for sibling in siblings {
+            emit!(code, "self.0.remove({sibling})?;");
+        }
+        emit!(
+            code,
+            "self.0.set({number}, {})",
+            object(kind, number, "value", from)
+        );
+    }
+    code.line("}");
+
+    code.line("");
+    emit!(
+        code,
+        "pub fn clear_{}(&mut self) -> {} {{",
+        field.name(),
+        result("()")
+    );
+    emit!(code, "self.0.remove({number}).map(|_| ())");
+    code.line("}");
+}
+
+/// whether the value held by a oneof variant has to be borrowed to be set
+fn borrowed(kind: Kind<'_>) -> bool {
+    matches!(kind, Kind::String | Kind::Bytes)
+}
+
+fn oneof_accessors(code: &mut Code, oneof: OneofDescriptor<'_>, from: &[String]) {
+    let accessors = accessor_names(oneof.containing_message());
+    let name = &accessors[oneof.name()];
+    let nested = nested_module(oneof.containing_message());
+    let ty = format!(
+        "{}::{}",
+        relative_path(from, &nested),
+        oneof_enum_name(oneof)
+    );
+    let numbers: Vec<String> = oneof
+        .fields()
+        .map(|field| field.number().to_string())
+        .collect();
+
+    code.separate();
+    emit!(
+        code,
+        "/// whichever field of the `{}` oneof occurs last",
+        oneof.name()
+    );
+    allow_self_convention(code, name, false);
+    emit!(
+        code,
+        "pub fn {name}(&self) -> Result<Option<{ty}>, ::protowire::DecodeError> {{"
+    );
+    code.line("let mut last = None;");
+    code.line("for field in self.0.view().into_try_iter() {");
+    code.line("let field_id = field?.get_field_id();");
+    emit!(code, "if matches!(field_id, {}) {{", numbers.join(" | "));
+    code.line("last = Some(field_id);");
+    code.line("}");
+    code.line("}");
+    code.line("Ok(match last {");
+    for field in oneof.fields() {
+        let variant = format!("{ty}::{}", ident(&upper_camel_case(field.name())));
+        let getter = format!("self.{}()?", accessors[field.name()]);
+        match field.kind() {
+            Kind::String => emit!(
+                code,
+                "Some({}) => {getter}.map(|value| {variant}(value.into())),",
+                field.number()
+            ),
+            Kind::Bytes => emit!(
+                code,
+                "Some({}) => {getter}.map(|value| {variant}(value.to_vec())),",
+                field.number()
+            ),
+            _ => emit!(code, "Some({}) => {getter}.map({variant}),", field.number()),
+        }
+    }
+    code.line("_ => None,");
+    code.line("})");
+    code.line("}");
+
+    code.line("");
+    emit!(
+        code,
+        "pub fn set_{}(&mut self, value: Option<{ty}>) -> Result<(), ::protowire::DecodeError> {{",
+        oneof.name()
+    );
+    code.line("match value {");
+    for field in oneof.fields() {
+        let variant = format!("{ty}::{}", ident(&upper_camel_case(field.name())));
+        let value = match borrowed(field.kind()) {
+            true => "&value",
+            false => "value",
+        };
+        emit!(
+            code,
+            "Some({variant}(value)) => self.set_{}({value}),",
+            field.name()
+        );
+    }
+    code.line("None => {");
+    for number in &numbers {
+        emit!(code, "self.0.remove({number})?;");
+    }
+    code.line("Ok(())");
+    code.line("}");
+    code.line("}");
+    code.line("}");
+}
+
+fn oneof_enum(code: &mut Code, oneof: OneofDescriptor<'_>, from: &[String]) {
+    code.separate();
+    emit!(
+        code,
+        "/// the `{}` oneof of `{}`",
+        oneof.name(),
+        oneof.containing_message().full_name()
+    );
+    code.line("#[derive(Debug, Clone)]");
+    emit!(code, "pub enum {} {{", oneof_enum_name(oneof));
+    for field in oneof.fields() {
+        let ty = match field.kind() {
+            Kind::String => "String".to_string(),
+            Kind::Bytes => "Vec<u8>".to_string(),
+            kind => param_type(kind, from),
+        };
+        emit!(code, "{}({ty}),", ident(&upper_camel_case(field.name())));
+    }
+    code.line("}");
+}
+
+fn builder_code(code: &mut Code, message: MessageDescriptor<'_>, builder: &str, from: &[String]) {
+    let name = ident(message.name());
+    let accessors = accessor_names(message);
+    let result = "Result<Self, ::protowire::DecodeError>";
+
+    code.separate();
+    emit!(
+        code,
+        "/// Builds a `{name}`, appending fields in the order they are given"
+    );
+    code.line("#[derive(Debug, Default)]");
+    emit!(code, "pub struct {builder}(::protowire::Message);");
+    code.line("");
+    emit!(code, "impl {builder} {{");
+    for field in message.fields() {
+        let number = field.number();
+        let kind = field.kind();
+        let group = matches!(kind, Kind::Group(_));
+        let ret = match group {
+            true => result,
+            false => "Self",
+        };
+        code.separate();
+        emit!(code, "/// `{}`", proto_type(field));
+        allow_self_convention(code, &accessors[field.name()], true);
+        if field.label() == Label::Repeated {
+            let (item, _) = item_type(kind, from);
+            emit!(
+                code,
+                "pub fn {}(mut self, values: impl IntoIterator<Item = {item}>) -> {ret} {{",
+                accessors[field.name()]
+            );
+            push_repeated(code, "self.0", field, from);
+        } else {
+            emit!(
+                code,
+                "pub fn {}(mut self, value: {}) -> {ret} {{",
+                accessors[field.name()],
+                param_type(kind, from)
+            );
+            emit!(
+                code,
+                "self.0.push(::protowire::Field::new({number}, {}));",
+                object(kind, number, "value", from)
+            );
+        }
+        match group {
+            true => code.line("Ok(self)"),
+            false => code.line("self"),
+        }
+        code.line("}");
+    }
+    for oneof in message.oneofs().filter(|oneof| !oneof.is_synthetic()) {
+        let nested = nested_module(message);
+        let ty = format!(
+            "{}::{}",
+            relative_path(from, &nested),
+            oneof_enum_name(oneof)
+        );
+        let fallible = oneof
+            .fields()
+            .any(|field| matches!(field.kind(), Kind::Group(_)));
+        code.separate();
+        emit!(code, "/// one field of the `{}` oneof", oneof.name());
+        allow_self_convention(code, &accessors[oneof.name()], true);
+        emit!(
+            code,
+            "pub fn {}(self, value: {ty}) -> {} {{",
+            accessors[oneof.name()],
+            if fallible { result } else { "Self" }
+        );
+        code.line("match value {");
+        for field in oneof.fields() {
+            let variant = format!("{ty}::{}", ident(&upper_camel_case(field.name())));
+            let value = match borrowed(field.kind()) {
+                true => "&value",
+                false => "value",
+            };
+            let call = format!("self.{}({value})", accessors[field.name()]);
+            let call = match fallible && !matches!(field.kind(), Kind::Group(_)) {
+                true => format!("Ok({call})"),
+                false => call,
+            };
+            emit!(code, "{variant}(value) => {call},");
+        }
+        code.line("}");
+        code.line("}");
+    }
+    code.separate();
+    emit!(code, "pub fn build(self) -> {name} {{");
+    emit!(code, "{name}(self.0)");
+    code.line("}");
+    code.line("}");
+}
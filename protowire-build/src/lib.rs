@@ -0,0 +1,333 @@
+//! Generates typed views over protowire messages from a `FileDescriptorSet`
+//!
+//! Every message becomes a struct wrapping a `protowire::Message`, decoded lazily: getters
+//! read the wire data each time they are called, borrowing strings and bytes from it, and
+//! setters edit it in place. Every message also gets a builder, which appends fields
+//! without scanning the message. Enums become a newtype over `i32` with a constant per
+//! value, so values unknown to the schema survive, and each oneof becomes an enum.
+//!
+//! The code for each package is written to its own file, `foo.bar.rs` for package
+//! `foo.bar`, which refers to other packages by relative paths. Include each file in a
+//! module tree mirroring the packages:
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     protowire_build::compile_descriptor_set("src/descriptors.pb").unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! pub mod foo {
+//!     pub mod bar {
+//!         include!(concat!(env!("OUT_DIR"), "/foo.bar.rs"));
+//!     }
+//! }
+//! ```
+//!
+//! Extensions and services are not generated.
+
+mod generator;
+mod names;
+
+use std::path::{Path, PathBuf};
+
+use protowire::{DescriptorError, DescriptorPool};
+
+/// An error raised while generating code
+#[derive(Debug)]
+pub enum BuildError {
+    /// the descriptor set could not be read or the code could not be written
+    Io(std::io::Error),
+    /// the descriptor set is not valid
+    Descriptor(DescriptorError),
+    /// no output directory was configured and `OUT_DIR` is not set
+    NoOutDir,
+}
+
+impl From<std::io::Error> for BuildError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<DescriptorError> for BuildError {
+    fn from(e: DescriptorError) -> Self {
+        Self::Descriptor(e)
+    }
+}
+
+impl core::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Descriptor(e) => write!(f, "invalid descriptor set: {e}"),
+            Self::NoOutDir => write!(f, "no output directory, and OUT_DIR is not set"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Options for generating code
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    out_dir: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// where to write the generated files, `OUT_DIR` by default as set for `build.rs`
+    pub fn out_dir(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.out_dir = Some(path.into());
+        self
+    }
+
+    /// the generated code for every package in the serialized `FileDescriptorSet`, as
+    /// pairs of file name and contents
+    pub fn generate(&self, descriptor_set: &[u8]) -> Result<Vec<(String, String)>, BuildError> {
+        let pool = DescriptorPool::decode(descriptor_set)?;
+        Ok(generator::generate(&pool))
+    }
+
+    /// reads a serialized `FileDescriptorSet`, as written by `protoc --descriptor_set_out`,
+    /// and writes the generated code for each package into the output directory
+    pub fn compile_descriptor_set(&self, path: impl AsRef<Path>) -> Result<(), BuildError> {
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(BuildError::NoOutDir)?,
+        };
+        let descriptor_set = std::fs::read(path)?;
+        for (name, code) in self.generate(&descriptor_set)? {
+            std::fs::write(out_dir.join(name), code)?;
+        }
+        Ok(())
+    }
+}
+
+/// see `Config::compile_descriptor_set`, with the default options
+pub fn compile_descriptor_set(path: impl AsRef<Path>) -> Result<(), BuildError> {
+    Config::new().compile_descriptor_set(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protowire::Message;
+
+    fn descriptor_set() -> Vec<u8> {
+        let text = r#"
+            1: {
+              1: {"shop.proto"} 2: {"test.shop"} 12: {"proto3"}
+              4: {
+                1: {"Order"}
+                2: {1: {"id"} 3: 1 4: 1 5: 9}
+                2: {1: {"total"} 3: 2 4: 1 5: 3}
+                2: {1: {"quantities"} 3: 3 4: 3 5: 5}
+                2: {1: {"tags"} 3: 4 4: 3 5: 9}
+                2: {1: {"status"} 3: 5 4: 1 5: 14 6: {".test.shop.Status"}}
+                2: {1: {"item"} 3: 6 4: 1 5: 11 6: {".test.shop.Order.Item"}}
+                2: {1: {"items"} 3: 7 4: 3 5: 11 6: {".test.shop.Order.Item"}}
+                2: {1: {"card"} 3: 8 4: 1 5: 9 9: 0}
+                2: {1: {"voucher"} 3: 9 4: 1 5: 11 6: {".test.shop.Order.Item"} 9: 0}
+                2: {1: {"discount"} 3: 10 4: 1 5: 17 9: 1 17: 1}
+                2: {1: {"blob"} 3: 11 4: 1 5: 12}
+                2: {1: {"ratio"} 3: 12 4: 1 5: 1}
+                2: {1: {"counts"} 3: 13 4: 3 5: 11 6: {".test.shop.Order.CountsEntry"}}
+                2: {1: {"type"} 3: 14 4: 1 5: 8}
+                3: {
+                  1: {"Item"}
+                  2: {1: {"name"} 3: 1 4: 1 5: 9}
+                  2: {1: {"price"} 3: 2 4: 1 5: 7}
+                }
+                3: {
+                  1: {"CountsEntry"}
+                  2: {1: {"key"} 3: 1 4: 1 5: 9}
+                  2: {1: {"value"} 3: 2 4: 1 5: 5}
+                  7: {7: 1}
+                }
+                8: {1: {"payment"}}
+                8: {1: {"_discount"}}
+              }
+              5: {1: {"Status"} 2: {1: {"PENDING"} 2: 0} 2: {1: {"SHIPPED"} 2: 1}}
+            }
+            1: {
+              1: {"legacy.proto"} 2: {"test.legacy"} 3: {"shop.proto"}
+              4: {
+                1: {"Legacy"}
+                2: {1: {"data"} 3: 1 4: 1 5: 10 6: {".test.legacy.Legacy.Data"}}
+                2: {1: {"ids"} 3: 2 4: 3 5: 4}
+                2: {1: {"item"} 3: 3 4: 1 5: 11 6: {".test.shop.Order.Item"}}
+                2: {1: {"offsets"} 3: 4 4: 3 5: 18 8: {2: 1}}
+                3: {1: {"Data"} 2: {1: {"x"} 3: 1 4: 2 5: 5}}
+              }
+            }
+            1: {
+              1: {"clash.proto"} 2: {"test.clash"} 12: {"proto3"}
+              4: {
+                1: {"Job"}
+                2: {1: {"new"} 3: 1 4: 1 5: 5}
+                2: {1: {"new_"} 3: 2 4: 1 5: 8}
+                2: {1: {"build"} 3: 3 4: 3 5: 9}
+                2: {1: {"x"} 3: 4 4: 1 5: 5}
+                2: {1: {"set_x"} 3: 5 4: 1 5: 5}
+                2: {1: {"as_message"} 3: 6 4: 1 5: 9 9: 0}
+                2: {1: {"into_wire_data"} 3: 7 4: 1 5: 12 9: 0}
+                8: {1: {"builder"}}
+              }
+              4: {
+                1: {"JobBuilder"}
+                2: {1: {"job"} 3: 1 4: 1 5: 11 6: {".test.clash.Job"}}
+              }
+            }
+        "#;
+        Message::from_protoscope(text)
+            .unwrap()
+            .serialize()
+            .as_ref()
+            .to_vec()
+    }
+
+    /// the generated code, compiled into the module tree `generate` expects
+    mod test {
+        pub mod shop {
+            include!("testdata/test.shop.rs");
+        }
+        pub mod legacy {
+            include!("testdata/test.legacy.rs");
+        }
+        pub mod clash {
+            include!("testdata/test.clash.rs");
+        }
+    }
+
+    use test::legacy::{legacy::Data, Legacy};
+    use test::shop::{order, Order, Status};
+
+    #[test]
+    fn test_generate() {
+        let files = Config::new().generate(&descriptor_set()).unwrap();
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["test.clash.rs", "test.legacy.rs", "test.shop.rs"]);
+        assert_eq!(files[0].1, include_str!("testdata/test.clash.rs"));
+        assert_eq!(files[1].1, include_str!("testdata/test.legacy.rs"));
+        assert_eq!(files[2].1, include_str!("testdata/test.shop.rs"));
+    }
+
+    #[test]
+    fn test_generated_code() {
+        let item = |name: &str, price| order::Item::builder().name(name).price(price).build();
+        let order = Order::builder()
+            .id("A-1")
+            .total(-5)
+            .quantities([1, 2, 300])
+            .tags(["x", "y"])
+            .status(Status::SHIPPED)
+            .items([item("pen", 3), item("ink", 4)])
+            .payment(order::Payment::Card("4111".to_string()))
+            .discount(-2)
+            .r#type(true)
+            .build();
+        let mut order = Order::from_wire_data(order.into_wire_data());
+        assert_eq!(order.id().unwrap(), "A-1");
+        assert_eq!(order.total().unwrap(), -5);
+        assert_eq!(order.quantities().unwrap(), [1, 2, 300]);
+        assert_eq!(order.tags().unwrap(), ["x", "y"]);
+        assert_eq!(order.status().unwrap(), Status::SHIPPED);
+        assert_eq!(order.status().unwrap().name(), Some("SHIPPED"));
+        assert_eq!(Status::from_name("PENDING"), Some(Status::PENDING));
+        assert!(order.item().unwrap().is_none());
+        let items = order.items().unwrap();
+        assert_eq!(items[1].name().unwrap(), "ink");
+        assert_eq!(items[1].price().unwrap(), 4);
+        assert_eq!(order.card().unwrap(), Some("4111"));
+        assert_eq!(order.discount().unwrap(), Some(-2));
+        assert!(order.r#type().unwrap());
+        // proto3 defaults for fields which are absent
+        assert_eq!(order.blob().unwrap(), b"");
+        assert_eq!(order.ratio().unwrap(), 0.0);
+
+        // setting one member of a oneof clears the others
+        order.set_voucher(item("gift", 10)).unwrap();
+        assert_eq!(order.card().unwrap(), None);
+        match order.payment().unwrap() {
+            Some(order::Payment::Voucher(voucher)) => assert_eq!(voucher.name().unwrap(), "gift"),
+            other => panic!("unexpected payment {other:?}"),
+        }
+        order.set_payment(None).unwrap();
+        assert!(order.payment().unwrap().is_none());
+
+        // the last occurrence of a singular field wins, values outside the enum survive
+        order.set_total(7).unwrap();
+        order.set_status(Status(9)).unwrap();
+        order.set_quantities([]).unwrap();
+        order.clear_discount().unwrap();
+        assert_eq!(order.total().unwrap(), 7);
+        assert_eq!(order.status().unwrap(), Status(9));
+        assert_eq!(order.status().unwrap().name(), None);
+        assert!(order.quantities().unwrap().is_empty());
+        assert_eq!(order.discount().unwrap(), None);
+
+        let legacy = Legacy::builder()
+            .data(Data::builder().x(7).build())
+            .unwrap()
+            .ids([1, 2])
+            .item(item("cap", 1))
+            .offsets([-1, 1])
+            .build();
+        let expected =
+            Message::from_protoscope("1: !{1: 7} 2: 1 2: 2 3: {1: {\"cap\"} 2: 1i32} 4: {`0102`}")
+                .unwrap();
+        assert_eq!(
+            legacy.clone().into_wire_data().as_ref(),
+            expected.serialize().as_ref()
+        );
+        assert_eq!(legacy.data().unwrap().unwrap().x().unwrap(), Some(7));
+        assert_eq!(legacy.ids().unwrap(), [1, 2]);
+        assert_eq!(legacy.item().unwrap().unwrap().name().unwrap(), "cap");
+        assert_eq!(legacy.offsets().unwrap(), [-1, 1]);
+
+        // repeated scalars are read whether or not they are packed
+        let legacy =
+            Legacy::from(Message::from_protoscope("2: {`0304`} 2: 5 4: 2 4: {`03`}").unwrap());
+        assert_eq!(legacy.ids().unwrap(), [3, 4, 5]);
+        assert_eq!(legacy.offsets().unwrap(), [1, -2]);
+    }
+
+    #[test]
+    fn test_name_clashes() {
+        use test::clash::{job, Job, JobBuilder};
+
+        // accessors named like generated methods, or like another field's setter, get a `_`,
+        // and so does a builder named like another message
+        let job = Job::builder()
+            .new__(1)
+            .new_(true)
+            .build_(["a"])
+            .x(2)
+            .set_x_(3)
+            .builder_(job::Builder::AsMessage("m".to_string()))
+            .build();
+        let mut job = Job::from_wire_data(job.into_wire_data());
+        assert_eq!(job.new__().unwrap(), 1);
+        assert!(job.new_().unwrap());
+        assert_eq!(job.build_().unwrap(), ["a"]);
+        assert_eq!(job.x().unwrap(), 2);
+        assert_eq!(job.set_x_().unwrap(), 3);
+        assert_eq!(job.as_message_().unwrap(), Some("m"));
+        job.set_new(4).unwrap();
+        job.set_into_wire_data(b"w").unwrap();
+        assert_eq!(job.new__().unwrap(), 4);
+        assert!(matches!(
+            job.builder_().unwrap(),
+            Some(job::Builder::IntoWireData(value)) if value == b"w"
+        ));
+        let wrapper = JobBuilder::builder().job(job).build();
+        assert_eq!(wrapper.job().unwrap().unwrap().x().unwrap(), 2);
+    }
+}
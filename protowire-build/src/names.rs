@@ -0,0 +1,65 @@
+/// Words which can't be used as identifiers as they are
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Keywords which can't be raw identifiers either
+const RESERVED: &[&str] = &["crate", "self", "Self", "super"];
+
+/// `name` as an identifier, as a raw identifier if it is a keyword
+pub(crate) fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else if KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+/// `FooBar` or `HTTPRequest` as `foo_bar` or `http_request`
+pub(crate) fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// `foo_bar` as `FooBar`
+pub(crate) fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect()
+}
+
+/// the path to `target` as seen from inside the module `from`, both given as segments
+/// from the root of the generated code
+pub(crate) fn relative_path(from: &[String], target: &[String]) -> String {
+    let common = from.iter().zip(target).take_while(|(a, b)| a == b).count();
+    let mut segments: Vec<&str> = vec!["super"; from.len() - common];
+    segments.extend(target[common..].iter().map(String::as_str));
+    segments.join("::")
+}
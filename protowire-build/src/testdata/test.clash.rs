@@ -0,0 +1,307 @@
+/// `test.clash.Job`
+#[derive(Debug, Clone, Default)]
+pub struct Job(::protowire::Message);
+
+impl From<::protowire::Message> for Job {
+    fn from(message: ::protowire::Message) -> Self {
+        Self(message)
+    }
+}
+
+impl From<Job> for ::protowire::Message {
+    fn from(message: Job) -> Self {
+        message.0
+    }
+}
+
+impl Job {
+    pub const FULL_NAME: &'static str = "test.clash.Job";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_wire_data(data: ::protowire::WireData) -> Self {
+        Self(data.into())
+    }
+
+    pub fn builder() -> JobBuilder_ {
+        JobBuilder_::default()
+    }
+
+    pub fn as_message(&self) -> &::protowire::Message {
+        &self.0
+    }
+
+    pub fn into_wire_data(self) -> ::protowire::WireData {
+        self.0.serialize()
+    }
+
+    /// `int32 new = 1`
+    pub fn new__(&self) -> Result<i32, ::protowire::DecodeError> {
+        Ok(self.0.get_varint(1)?.map(|v| v.as_proto_int32()).unwrap_or_default())
+    }
+
+    pub fn set_new(&mut self, value: i32) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(1, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value)))
+    }
+
+    pub fn clear_new(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(1).map(|_| ())
+    }
+
+    /// `bool new_ = 2`
+    pub fn new_(&self) -> Result<bool, ::protowire::DecodeError> {
+        Ok(self.0.get_varint(2)?.map(|v| v.get() != 0).unwrap_or_default())
+    }
+
+    pub fn set_new_(&mut self, value: bool) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(2, ::protowire::MessageObject::Varint(::protowire::Varint::new(value as u64)))
+    }
+
+    pub fn clear_new_(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(2).map(|_| ())
+    }
+
+    /// `repeated string build = 3`
+    pub fn build_(&self) -> Result<Vec<&str>, ::protowire::DecodeError> {
+        self.0.get_all_strs(3)
+    }
+
+    pub fn set_build(&mut self, values: impl IntoIterator<Item = impl AsRef<str>>) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(3)?;
+        for value in values {
+            self.0.push(::protowire::Field::new(3, ::protowire::MessageObject::Len(::protowire::Len::new_string(value.as_ref()))));
+        }
+        Ok(())
+    }
+
+    pub fn clear_build(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(3).map(|_| ())
+    }
+
+    /// `int32 x = 4`
+    pub fn x(&self) -> Result<i32, ::protowire::DecodeError> {
+        Ok(self.0.get_varint(4)?.map(|v| v.as_proto_int32()).unwrap_or_default())
+    }
+
+    pub fn set_x(&mut self, value: i32) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(4, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value)))
+    }
+
+    pub fn clear_x(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(4).map(|_| ())
+    }
+
+    /// `int32 set_x = 5`
+    pub fn set_x_(&self) -> Result<i32, ::protowire::DecodeError> {
+        Ok(self.0.get_varint(5)?.map(|v| v.as_proto_int32()).unwrap_or_default())
+    }
+
+    pub fn set_set_x(&mut self, value: i32) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(5, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value)))
+    }
+
+    pub fn clear_set_x(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(5).map(|_| ())
+    }
+
+    /// `string as_message = 6`
+    pub fn as_message_(&self) -> Result<Option<&str>, ::protowire::DecodeError> {
+        self.0.get_str(6)
+    }
+
+    pub fn set_as_message(&mut self, value: &str) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(7)?;
+        self.0.set(6, ::protowire::MessageObject::Len(::protowire::Len::new_string(value)))
+    }
+
+    pub fn clear_as_message(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(6).map(|_| ())
+    }
+
+    /// `bytes into_wire_data = 7`
+    #[allow(clippy::wrong_self_convention)]
+    pub fn into_wire_data_(&self) -> Result<Option<&[u8]>, ::protowire::DecodeError> {
+        Ok(self.0.get_len(7)?.map(|len| len.get_data()))
+    }
+
+    pub fn set_into_wire_data(&mut self, value: &[u8]) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(6)?;
+        self.0.set(7, ::protowire::MessageObject::Len(::protowire::Len::new_bytes(value)))
+    }
+
+    pub fn clear_into_wire_data(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(7).map(|_| ())
+    }
+
+    /// whichever field of the `builder` oneof occurs last
+    pub fn builder_(&self) -> Result<Option<job::Builder>, ::protowire::DecodeError> {
+        let mut last = None;
+        for field in self.0.view().into_try_iter() {
+            let field_id = field?.get_field_id();
+            if matches!(field_id, 6 | 7) {
+                last = Some(field_id);
+            }
+        }
+        Ok(match last {
+            Some(6) => self.as_message_()?.map(|value| job::Builder::AsMessage(value.into())),
+            Some(7) => self.into_wire_data_()?.map(|value| job::Builder::IntoWireData(value.to_vec())),
+            _ => None,
+        })
+    }
+
+    pub fn set_builder(&mut self, value: Option<job::Builder>) -> Result<(), ::protowire::DecodeError> {
+        match value {
+            Some(job::Builder::AsMessage(value)) => self.set_as_message(&value),
+            Some(job::Builder::IntoWireData(value)) => self.set_into_wire_data(&value),
+            None => {
+                self.0.remove(6)?;
+                self.0.remove(7)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Builds a `Job`, appending fields in the order they are given
+#[derive(Debug, Default)]
+pub struct JobBuilder_(::protowire::Message);
+
+impl JobBuilder_ {
+    /// `int32 new = 1`
+    pub fn new__(mut self, value: i32) -> Self {
+        self.0.push(::protowire::Field::new(1, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value))));
+        self
+    }
+
+    /// `bool new_ = 2`
+    pub fn new_(mut self, value: bool) -> Self {
+        self.0.push(::protowire::Field::new(2, ::protowire::MessageObject::Varint(::protowire::Varint::new(value as u64))));
+        self
+    }
+
+    /// `repeated string build = 3`
+    pub fn build_(mut self, values: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        for value in values {
+            self.0.push(::protowire::Field::new(3, ::protowire::MessageObject::Len(::protowire::Len::new_string(value.as_ref()))));
+        }
+        self
+    }
+
+    /// `int32 x = 4`
+    pub fn x(mut self, value: i32) -> Self {
+        self.0.push(::protowire::Field::new(4, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value))));
+        self
+    }
+
+    /// `int32 set_x = 5`
+    pub fn set_x_(mut self, value: i32) -> Self {
+        self.0.push(::protowire::Field::new(5, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value))));
+        self
+    }
+
+    /// `string as_message = 6`
+    #[allow(clippy::wrong_self_convention)]
+    pub fn as_message_(mut self, value: &str) -> Self {
+        self.0.push(::protowire::Field::new(6, ::protowire::MessageObject::Len(::protowire::Len::new_string(value))));
+        self
+    }
+
+    /// `bytes into_wire_data = 7`
+    pub fn into_wire_data_(mut self, value: &[u8]) -> Self {
+        self.0.push(::protowire::Field::new(7, ::protowire::MessageObject::Len(::protowire::Len::new_bytes(value))));
+        self
+    }
+
+    /// one field of the `builder` oneof
+    pub fn builder_(self, value: job::Builder) -> Self {
+        match value {
+            job::Builder::AsMessage(value) => self.as_message_(&value),
+            job::Builder::IntoWireData(value) => self.into_wire_data_(&value),
+        }
+    }
+
+    pub fn build(self) -> Job {
+        Job(self.0)
+    }
+}
+
+/// the types nested in `test.clash.Job`
+pub mod job {
+    /// the `builder` oneof of `test.clash.Job`
+    #[derive(Debug, Clone)]
+    pub enum Builder {
+        AsMessage(String),
+        IntoWireData(Vec<u8>),
+    }
+}
+
+/// `test.clash.JobBuilder`
+#[derive(Debug, Clone, Default)]
+pub struct JobBuilder(::protowire::Message);
+
+impl From<::protowire::Message> for JobBuilder {
+    fn from(message: ::protowire::Message) -> Self {
+        Self(message)
+    }
+}
+
+impl From<JobBuilder> for ::protowire::Message {
+    fn from(message: JobBuilder) -> Self {
+        message.0
+    }
+}
+
+impl JobBuilder {
+    pub const FULL_NAME: &'static str = "test.clash.JobBuilder";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_wire_data(data: ::protowire::WireData) -> Self {
+        Self(data.into())
+    }
+
+    pub fn builder() -> JobBuilderBuilder {
+        JobBuilderBuilder::default()
+    }
+
+    pub fn as_message(&self) -> &::protowire::Message {
+        &self.0
+    }
+
+    pub fn into_wire_data(self) -> ::protowire::WireData {
+        self.0.serialize()
+    }
+
+    /// `test.clash.Job job = 1`
+    pub fn job(&self) -> Result<Option<Job>, ::protowire::DecodeError> {
+        Ok(self.0.get_message(1)?.map(Job::from))
+    }
+
+    pub fn set_job(&mut self, value: Job) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(1, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into())))
+    }
+
+    pub fn clear_job(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(1).map(|_| ())
+    }
+}
+
+/// Builds a `JobBuilder`, appending fields in the order they are given
+#[derive(Debug, Default)]
+pub struct JobBuilderBuilder(::protowire::Message);
+
+impl JobBuilderBuilder {
+    /// `test.clash.Job job = 1`
+    pub fn job(mut self, value: Job) -> Self {
+        self.0.push(::protowire::Field::new(1, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into()))));
+        self
+    }
+
+    pub fn build(self) -> JobBuilder {
+        JobBuilder(self.0)
+    }
+}
@@ -0,0 +1,222 @@
+/// `test.legacy.Legacy`
+#[derive(Debug, Clone, Default)]
+pub struct Legacy(::protowire::Message);
+
+impl From<::protowire::Message> for Legacy {
+    fn from(message: ::protowire::Message) -> Self {
+        Self(message)
+    }
+}
+
+impl From<Legacy> for ::protowire::Message {
+    fn from(message: Legacy) -> Self {
+        message.0
+    }
+}
+
+impl Legacy {
+    pub const FULL_NAME: &'static str = "test.legacy.Legacy";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_wire_data(data: ::protowire::WireData) -> Self {
+        Self(data.into())
+    }
+
+    pub fn builder() -> LegacyBuilder {
+        LegacyBuilder::default()
+    }
+
+    pub fn as_message(&self) -> &::protowire::Message {
+        &self.0
+    }
+
+    pub fn into_wire_data(self) -> ::protowire::WireData {
+        self.0.serialize()
+    }
+
+    /// `test.legacy.Legacy.Data data = 1`
+    pub fn data(&self) -> Result<Option<legacy::Data>, ::protowire::DecodeError> {
+        Ok(self.0.get_message(1)?.map(legacy::Data::from))
+    }
+
+    pub fn set_data(&mut self, value: legacy::Data) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(1, ::protowire::MessageObject::Group(::protowire::Group::from_message(1, value.into())?))
+    }
+
+    pub fn clear_data(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(1).map(|_| ())
+    }
+
+    /// `repeated uint64 ids = 2`
+    pub fn ids(&self) -> Result<Vec<u64>, ::protowire::DecodeError> {
+        self.0.get_all_varints(2)
+    }
+
+    pub fn set_ids(&mut self, values: impl IntoIterator<Item = u64>) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(2)?;
+        for value in values {
+            self.0.push(::protowire::Field::new(2, ::protowire::MessageObject::Varint(::protowire::Varint::new(value))));
+        }
+        Ok(())
+    }
+
+    pub fn clear_ids(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(2).map(|_| ())
+    }
+
+    /// `test.shop.Order.Item item = 3`
+    pub fn item(&self) -> Result<Option<super::shop::order::Item>, ::protowire::DecodeError> {
+        Ok(self.0.get_message(3)?.map(super::shop::order::Item::from))
+    }
+
+    pub fn set_item(&mut self, value: super::shop::order::Item) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(3, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into())))
+    }
+
+    pub fn clear_item(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(3).map(|_| ())
+    }
+
+    /// `repeated sint64 offsets = 4`
+    pub fn offsets(&self) -> Result<Vec<i64>, ::protowire::DecodeError> {
+        Ok(self.0.get_all_varints(4)?.into_iter().map(|v| (v >> 1) as i64 ^ -((v & 1) as i64)).collect())
+    }
+
+    pub fn set_offsets(&mut self, values: impl IntoIterator<Item = i64>) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(4)?;
+        let mut packed = ::protowire::PackedRepeatedVarint::new();
+        for value in values {
+            packed.push(::protowire::Varint::new_proto_sint64(value));
+        }
+        if !packed.is_empty() {
+            let mut len = ::protowire::Len::new();
+            len.set_packed_repeated_varint(packed);
+            self.0.push(::protowire::Field::new(4, ::protowire::MessageObject::Len(len)));
+        }
+        Ok(())
+    }
+
+    pub fn clear_offsets(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(4).map(|_| ())
+    }
+}
+
+/// Builds a `Legacy`, appending fields in the order they are given
+#[derive(Debug, Default)]
+pub struct LegacyBuilder(::protowire::Message);
+
+impl LegacyBuilder {
+    /// `test.legacy.Legacy.Data data = 1`
+    pub fn data(mut self, value: legacy::Data) -> Result<Self, ::protowire::DecodeError> {
+        self.0.push(::protowire::Field::new(1, ::protowire::MessageObject::Group(::protowire::Group::from_message(1, value.into())?)));
+        Ok(self)
+    }
+
+    /// `repeated uint64 ids = 2`
+    pub fn ids(mut self, values: impl IntoIterator<Item = u64>) -> Self {
+        for value in values {
+            self.0.push(::protowire::Field::new(2, ::protowire::MessageObject::Varint(::protowire::Varint::new(value))));
+        }
+        self
+    }
+
+    /// `test.shop.Order.Item item = 3`
+    pub fn item(mut self, value: super::shop::order::Item) -> Self {
+        self.0.push(::protowire::Field::new(3, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into()))));
+        self
+    }
+
+    /// `repeated sint64 offsets = 4`
+    pub fn offsets(mut self, values: impl IntoIterator<Item = i64>) -> Self {
+        let mut packed = ::protowire::PackedRepeatedVarint::new();
+        for value in values {
+            packed.push(::protowire::Varint::new_proto_sint64(value));
+        }
+        if !packed.is_empty() {
+            let mut len = ::protowire::Len::new();
+            len.set_packed_repeated_varint(packed);
+            self.0.push(::protowire::Field::new(4, ::protowire::MessageObject::Len(len)));
+        }
+        self
+    }
+
+    pub fn build(self) -> Legacy {
+        Legacy(self.0)
+    }
+}
+
+/// the types nested in `test.legacy.Legacy`
+#[allow(clippy::module_inception)]
+pub mod legacy {
+    /// `test.legacy.Legacy.Data`
+    #[derive(Debug, Clone, Default)]
+    pub struct Data(::protowire::Message);
+
+    impl From<::protowire::Message> for Data {
+        fn from(message: ::protowire::Message) -> Self {
+            Self(message)
+        }
+    }
+
+    impl From<Data> for ::protowire::Message {
+        fn from(message: Data) -> Self {
+            message.0
+        }
+    }
+
+    impl Data {
+        pub const FULL_NAME: &'static str = "test.legacy.Legacy.Data";
+
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn from_wire_data(data: ::protowire::WireData) -> Self {
+            Self(data.into())
+        }
+
+        pub fn builder() -> DataBuilder {
+            DataBuilder::default()
+        }
+
+        pub fn as_message(&self) -> &::protowire::Message {
+            &self.0
+        }
+
+        pub fn into_wire_data(self) -> ::protowire::WireData {
+            self.0.serialize()
+        }
+
+        /// `required int32 x = 1`
+        pub fn x(&self) -> Result<Option<i32>, ::protowire::DecodeError> {
+            Ok(self.0.get_varint(1)?.map(|v| v.as_proto_int32()))
+        }
+
+        pub fn set_x(&mut self, value: i32) -> Result<(), ::protowire::DecodeError> {
+            self.0.set(1, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value)))
+        }
+
+        pub fn clear_x(&mut self) -> Result<(), ::protowire::DecodeError> {
+            self.0.remove(1).map(|_| ())
+        }
+    }
+
+    /// Builds a `Data`, appending fields in the order they are given
+    #[derive(Debug, Default)]
+    pub struct DataBuilder(::protowire::Message);
+
+    impl DataBuilder {
+        /// `required int32 x = 1`
+        pub fn x(mut self, value: i32) -> Self {
+            self.0.push(::protowire::Field::new(1, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value))));
+            self
+        }
+
+        pub fn build(self) -> Data {
+            Data(self.0)
+        }
+    }
+}
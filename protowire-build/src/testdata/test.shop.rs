@@ -0,0 +1,602 @@
+/// `test.shop.Status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Status(pub i32);
+
+impl Status {
+    pub const PENDING: Self = Self(0);
+    pub const SHIPPED: Self = Self(1);
+
+    /// the name of the value in the schema, `None` for numbers it doesn't declare
+    pub fn name(self) -> Option<&'static str> {
+        match self.0 {
+            0 => Some("PENDING"),
+            1 => Some("SHIPPED"),
+            _ => None,
+        }
+    }
+
+    /// the value declared with `name` in the schema
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "PENDING" => Some(Self::PENDING),
+            "SHIPPED" => Some(Self::SHIPPED),
+            _ => None,
+        }
+    }
+}
+
+/// `test.shop.Order`
+#[derive(Debug, Clone, Default)]
+pub struct Order(::protowire::Message);
+
+impl From<::protowire::Message> for Order {
+    fn from(message: ::protowire::Message) -> Self {
+        Self(message)
+    }
+}
+
+impl From<Order> for ::protowire::Message {
+    fn from(message: Order) -> Self {
+        message.0
+    }
+}
+
+impl Order {
+    pub const FULL_NAME: &'static str = "test.shop.Order";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_wire_data(data: ::protowire::WireData) -> Self {
+        Self(data.into())
+    }
+
+    pub fn builder() -> OrderBuilder {
+        OrderBuilder::default()
+    }
+
+    pub fn as_message(&self) -> &::protowire::Message {
+        &self.0
+    }
+
+    pub fn into_wire_data(self) -> ::protowire::WireData {
+        self.0.serialize()
+    }
+
+    /// `string id = 1`
+    pub fn id(&self) -> Result<&str, ::protowire::DecodeError> {
+        Ok(self.0.get_str(1)?.unwrap_or_default())
+    }
+
+    pub fn set_id(&mut self, value: &str) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(1, ::protowire::MessageObject::Len(::protowire::Len::new_string(value)))
+    }
+
+    pub fn clear_id(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(1).map(|_| ())
+    }
+
+    /// `int64 total = 2`
+    pub fn total(&self) -> Result<i64, ::protowire::DecodeError> {
+        Ok(self.0.get_varint(2)?.map(|v| v.as_proto_int64()).unwrap_or_default())
+    }
+
+    pub fn set_total(&mut self, value: i64) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(2, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int64(value)))
+    }
+
+    pub fn clear_total(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(2).map(|_| ())
+    }
+
+    /// `repeated int32 quantities = 3`
+    pub fn quantities(&self) -> Result<Vec<i32>, ::protowire::DecodeError> {
+        Ok(self.0.get_all_varints(3)?.into_iter().map(|v| v as i32).collect())
+    }
+
+    pub fn set_quantities(&mut self, values: impl IntoIterator<Item = i32>) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(3)?;
+        let mut packed = ::protowire::PackedRepeatedVarint::new();
+        for value in values {
+            packed.push(::protowire::Varint::new_proto_int32(value));
+        }
+        if !packed.is_empty() {
+            let mut len = ::protowire::Len::new();
+            len.set_packed_repeated_varint(packed);
+            self.0.push(::protowire::Field::new(3, ::protowire::MessageObject::Len(len)));
+        }
+        Ok(())
+    }
+
+    pub fn clear_quantities(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(3).map(|_| ())
+    }
+
+    /// `repeated string tags = 4`
+    pub fn tags(&self) -> Result<Vec<&str>, ::protowire::DecodeError> {
+        self.0.get_all_strs(4)
+    }
+
+    pub fn set_tags(&mut self, values: impl IntoIterator<Item = impl AsRef<str>>) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(4)?;
+        for value in values {
+            self.0.push(::protowire::Field::new(4, ::protowire::MessageObject::Len(::protowire::Len::new_string(value.as_ref()))));
+        }
+        Ok(())
+    }
+
+    pub fn clear_tags(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(4).map(|_| ())
+    }
+
+    /// `test.shop.Status status = 5`
+    pub fn status(&self) -> Result<Status, ::protowire::DecodeError> {
+        Ok(self.0.get_varint(5)?.map(|v| Status(v.as_proto_int32())).unwrap_or_default())
+    }
+
+    pub fn set_status(&mut self, value: Status) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(5, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value.0)))
+    }
+
+    pub fn clear_status(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(5).map(|_| ())
+    }
+
+    /// `test.shop.Order.Item item = 6`
+    pub fn item(&self) -> Result<Option<order::Item>, ::protowire::DecodeError> {
+        Ok(self.0.get_message(6)?.map(order::Item::from))
+    }
+
+    pub fn set_item(&mut self, value: order::Item) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(6, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into())))
+    }
+
+    pub fn clear_item(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(6).map(|_| ())
+    }
+
+    /// `repeated test.shop.Order.Item items = 7`
+    pub fn items(&self) -> Result<Vec<order::Item>, ::protowire::DecodeError> {
+        Ok(self.0.get_all_messages(7)?.into_iter().map(order::Item::from).collect())
+    }
+
+    pub fn set_items(&mut self, values: impl IntoIterator<Item = order::Item>) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(7)?;
+        for value in values {
+            self.0.push(::protowire::Field::new(7, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into()))));
+        }
+        Ok(())
+    }
+
+    pub fn clear_items(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(7).map(|_| ())
+    }
+
+    /// `string card = 8`
+    pub fn card(&self) -> Result<Option<&str>, ::protowire::DecodeError> {
+        self.0.get_str(8)
+    }
+
+    pub fn set_card(&mut self, value: &str) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(9)?;
+        self.0.set(8, ::protowire::MessageObject::Len(::protowire::Len::new_string(value)))
+    }
+
+    pub fn clear_card(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(8).map(|_| ())
+    }
+
+    /// `test.shop.Order.Item voucher = 9`
+    pub fn voucher(&self) -> Result<Option<order::Item>, ::protowire::DecodeError> {
+        Ok(self.0.get_message(9)?.map(order::Item::from))
+    }
+
+    pub fn set_voucher(&mut self, value: order::Item) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(8)?;
+        self.0.set(9, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into())))
+    }
+
+    pub fn clear_voucher(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(9).map(|_| ())
+    }
+
+    /// `optional sint32 discount = 10`
+    pub fn discount(&self) -> Result<Option<i32>, ::protowire::DecodeError> {
+        Ok(self.0.get_varint(10)?.map(|v| v.as_proto_sint32()))
+    }
+
+    pub fn set_discount(&mut self, value: i32) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(10, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_sint32(value)))
+    }
+
+    pub fn clear_discount(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(10).map(|_| ())
+    }
+
+    /// `bytes blob = 11`
+    pub fn blob(&self) -> Result<&[u8], ::protowire::DecodeError> {
+        Ok(self.0.get_len(11)?.map(|len| len.get_data()).unwrap_or_default())
+    }
+
+    pub fn set_blob(&mut self, value: &[u8]) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(11, ::protowire::MessageObject::Len(::protowire::Len::new_bytes(value)))
+    }
+
+    pub fn clear_blob(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(11).map(|_| ())
+    }
+
+    /// `double ratio = 12`
+    pub fn ratio(&self) -> Result<f64, ::protowire::DecodeError> {
+        Ok(self.0.get_i64(12)?.map(|v| v.get_double()).unwrap_or_default())
+    }
+
+    pub fn set_ratio(&mut self, value: f64) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(12, ::protowire::MessageObject::I64(::protowire::I64::new_double(value)))
+    }
+
+    pub fn clear_ratio(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(12).map(|_| ())
+    }
+
+    /// `repeated test.shop.Order.CountsEntry counts = 13`
+    pub fn counts(&self) -> Result<Vec<order::CountsEntry>, ::protowire::DecodeError> {
+        Ok(self.0.get_all_messages(13)?.into_iter().map(order::CountsEntry::from).collect())
+    }
+
+    pub fn set_counts(&mut self, values: impl IntoIterator<Item = order::CountsEntry>) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(13)?;
+        for value in values {
+            self.0.push(::protowire::Field::new(13, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into()))));
+        }
+        Ok(())
+    }
+
+    pub fn clear_counts(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(13).map(|_| ())
+    }
+
+    /// `bool type = 14`
+    pub fn r#type(&self) -> Result<bool, ::protowire::DecodeError> {
+        Ok(self.0.get_varint(14)?.map(|v| v.get() != 0).unwrap_or_default())
+    }
+
+    pub fn set_type(&mut self, value: bool) -> Result<(), ::protowire::DecodeError> {
+        self.0.set(14, ::protowire::MessageObject::Varint(::protowire::Varint::new(value as u64)))
+    }
+
+    pub fn clear_type(&mut self) -> Result<(), ::protowire::DecodeError> {
+        self.0.remove(14).map(|_| ())
+    }
+
+    /// whichever field of the `payment` oneof occurs last
+    pub fn payment(&self) -> Result<Option<order::Payment>, ::protowire::DecodeError> {
+        let mut last = None;
+        for field in self.0.view().into_try_iter() {
+            let field_id = field?.get_field_id();
+            if matches!(field_id, 8 | 9) {
+                last = Some(field_id);
+            }
+        }
+        Ok(match last {
+            Some(8) => self.card()?.map(|value| order::Payment::Card(value.into())),
+            Some(9) => self.voucher()?.map(order::Payment::Voucher),
+            _ => None,
+        })
+    }
+
+    pub fn set_payment(&mut self, value: Option<order::Payment>) -> Result<(), ::protowire::DecodeError> {
+        match value {
+            Some(order::Payment::Card(value)) => self.set_card(&value),
+            Some(order::Payment::Voucher(value)) => self.set_voucher(value),
+            None => {
+                self.0.remove(8)?;
+                self.0.remove(9)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Builds a `Order`, appending fields in the order they are given
+#[derive(Debug, Default)]
+pub struct OrderBuilder(::protowire::Message);
+
+impl OrderBuilder {
+    /// `string id = 1`
+    pub fn id(mut self, value: &str) -> Self {
+        self.0.push(::protowire::Field::new(1, ::protowire::MessageObject::Len(::protowire::Len::new_string(value))));
+        self
+    }
+
+    /// `int64 total = 2`
+    pub fn total(mut self, value: i64) -> Self {
+        self.0.push(::protowire::Field::new(2, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int64(value))));
+        self
+    }
+
+    /// `repeated int32 quantities = 3`
+    pub fn quantities(mut self, values: impl IntoIterator<Item = i32>) -> Self {
+        let mut packed = ::protowire::PackedRepeatedVarint::new();
+        for value in values {
+            packed.push(::protowire::Varint::new_proto_int32(value));
+        }
+        if !packed.is_empty() {
+            let mut len = ::protowire::Len::new();
+            len.set_packed_repeated_varint(packed);
+            self.0.push(::protowire::Field::new(3, ::protowire::MessageObject::Len(len)));
+        }
+        self
+    }
+
+    /// `repeated string tags = 4`
+    pub fn tags(mut self, values: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        for value in values {
+            self.0.push(::protowire::Field::new(4, ::protowire::MessageObject::Len(::protowire::Len::new_string(value.as_ref()))));
+        }
+        self
+    }
+
+    /// `test.shop.Status status = 5`
+    pub fn status(mut self, value: Status) -> Self {
+        self.0.push(::protowire::Field::new(5, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value.0))));
+        self
+    }
+
+    /// `test.shop.Order.Item item = 6`
+    pub fn item(mut self, value: order::Item) -> Self {
+        self.0.push(::protowire::Field::new(6, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into()))));
+        self
+    }
+
+    /// `repeated test.shop.Order.Item items = 7`
+    pub fn items(mut self, values: impl IntoIterator<Item = order::Item>) -> Self {
+        for value in values {
+            self.0.push(::protowire::Field::new(7, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into()))));
+        }
+        self
+    }
+
+    /// `string card = 8`
+    pub fn card(mut self, value: &str) -> Self {
+        self.0.push(::protowire::Field::new(8, ::protowire::MessageObject::Len(::protowire::Len::new_string(value))));
+        self
+    }
+
+    /// `test.shop.Order.Item voucher = 9`
+    pub fn voucher(mut self, value: order::Item) -> Self {
+        self.0.push(::protowire::Field::new(9, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into()))));
+        self
+    }
+
+    /// `optional sint32 discount = 10`
+    pub fn discount(mut self, value: i32) -> Self {
+        self.0.push(::protowire::Field::new(10, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_sint32(value))));
+        self
+    }
+
+    /// `bytes blob = 11`
+    pub fn blob(mut self, value: &[u8]) -> Self {
+        self.0.push(::protowire::Field::new(11, ::protowire::MessageObject::Len(::protowire::Len::new_bytes(value))));
+        self
+    }
+
+    /// `double ratio = 12`
+    pub fn ratio(mut self, value: f64) -> Self {
+        self.0.push(::protowire::Field::new(12, ::protowire::MessageObject::I64(::protowire::I64::new_double(value))));
+        self
+    }
+
+    /// `repeated test.shop.Order.CountsEntry counts = 13`
+    pub fn counts(mut self, values: impl IntoIterator<Item = order::CountsEntry>) -> Self {
+        for value in values {
+            self.0.push(::protowire::Field::new(13, ::protowire::MessageObject::Len(::protowire::Len::new_message(value.into()))));
+        }
+        self
+    }
+
+    /// `bool type = 14`
+    pub fn r#type(mut self, value: bool) -> Self {
+        self.0.push(::protowire::Field::new(14, ::protowire::MessageObject::Varint(::protowire::Varint::new(value as u64))));
+        self
+    }
+
+    /// one field of the `payment` oneof
+    pub fn payment(self, value: order::Payment) -> Self {
+        match value {
+            order::Payment::Card(value) => self.card(&value),
+            order::Payment::Voucher(value) => self.voucher(value),
+        }
+    }
+
+    pub fn build(self) -> Order {
+        Order(self.0)
+    }
+}
+
+/// the types nested in `test.shop.Order`
+pub mod order {
+    /// the `payment` oneof of `test.shop.Order`
+    #[derive(Debug, Clone)]
+    pub enum Payment {
+        Card(String),
+        Voucher(Item),
+    }
+
+    /// `test.shop.Order.Item`
+    #[derive(Debug, Clone, Default)]
+    pub struct Item(::protowire::Message);
+
+    impl From<::protowire::Message> for Item {
+        fn from(message: ::protowire::Message) -> Self {
+            Self(message)
+        }
+    }
+
+    impl From<Item> for ::protowire::Message {
+        fn from(message: Item) -> Self {
+            message.0
+        }
+    }
+
+    impl Item {
+        pub const FULL_NAME: &'static str = "test.shop.Order.Item";
+
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn from_wire_data(data: ::protowire::WireData) -> Self {
+            Self(data.into())
+        }
+
+        pub fn builder() -> ItemBuilder {
+            ItemBuilder::default()
+        }
+
+        pub fn as_message(&self) -> &::protowire::Message {
+            &self.0
+        }
+
+        pub fn into_wire_data(self) -> ::protowire::WireData {
+            self.0.serialize()
+        }
+
+        /// `string name = 1`
+        pub fn name(&self) -> Result<&str, ::protowire::DecodeError> {
+            Ok(self.0.get_str(1)?.unwrap_or_default())
+        }
+
+        pub fn set_name(&mut self, value: &str) -> Result<(), ::protowire::DecodeError> {
+            self.0.set(1, ::protowire::MessageObject::Len(::protowire::Len::new_string(value)))
+        }
+
+        pub fn clear_name(&mut self) -> Result<(), ::protowire::DecodeError> {
+            self.0.remove(1).map(|_| ())
+        }
+
+        /// `fixed32 price = 2`
+        pub fn price(&self) -> Result<u32, ::protowire::DecodeError> {
+            Ok(self.0.get_i32(2)?.map(|v| v.get() as u32).unwrap_or_default())
+        }
+
+        pub fn set_price(&mut self, value: u32) -> Result<(), ::protowire::DecodeError> {
+            self.0.set(2, ::protowire::MessageObject::I32(::protowire::I32::new(value as i32)))
+        }
+
+        pub fn clear_price(&mut self) -> Result<(), ::protowire::DecodeError> {
+            self.0.remove(2).map(|_| ())
+        }
+    }
+
+    /// Builds a `Item`, appending fields in the order they are given
+    #[derive(Debug, Default)]
+    pub struct ItemBuilder(::protowire::Message);
+
+    impl ItemBuilder {
+        /// `string name = 1`
+        pub fn name(mut self, value: &str) -> Self {
+            self.0.push(::protowire::Field::new(1, ::protowire::MessageObject::Len(::protowire::Len::new_string(value))));
+            self
+        }
+
+        /// `fixed32 price = 2`
+        pub fn price(mut self, value: u32) -> Self {
+            self.0.push(::protowire::Field::new(2, ::protowire::MessageObject::I32(::protowire::I32::new(value as i32))));
+            self
+        }
+
+        pub fn build(self) -> Item {
+            Item(self.0)
+        }
+    }
+
+    /// `test.shop.Order.CountsEntry`
+    #[derive(Debug, Clone, Default)]
+    pub struct CountsEntry(::protowire::Message);
+
+    impl From<::protowire::Message> for CountsEntry {
+        fn from(message: ::protowire::Message) -> Self {
+            Self(message)
+        }
+    }
+
+    impl From<CountsEntry> for ::protowire::Message {
+        fn from(message: CountsEntry) -> Self {
+            message.0
+        }
+    }
+
+    impl CountsEntry {
+        pub const FULL_NAME: &'static str = "test.shop.Order.CountsEntry";
+
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn from_wire_data(data: ::protowire::WireData) -> Self {
+            Self(data.into())
+        }
+
+        pub fn builder() -> CountsEntryBuilder {
+            CountsEntryBuilder::default()
+        }
+
+        pub fn as_message(&self) -> &::protowire::Message {
+            &self.0
+        }
+
+        pub fn into_wire_data(self) -> ::protowire::WireData {
+            self.0.serialize()
+        }
+
+        /// `string key = 1`
+        pub fn key(&self) -> Result<&str, ::protowire::DecodeError> {
+            Ok(self.0.get_str(1)?.unwrap_or_default())
+        }
+
+        pub fn set_key(&mut self, value: &str) -> Result<(), ::protowire::DecodeError> {
+            self.0.set(1, ::protowire::MessageObject::Len(::protowire::Len::new_string(value)))
+        }
+
+        pub fn clear_key(&mut self) -> Result<(), ::protowire::DecodeError> {
+            self.0.remove(1).map(|_| ())
+        }
+
+        /// `int32 value = 2`
+        pub fn value(&self) -> Result<i32, ::protowire::DecodeError> {
+            Ok(self.0.get_varint(2)?.map(|v| v.as_proto_int32()).unwrap_or_default())
+        }
+
+        pub fn set_value(&mut self, value: i32) -> Result<(), ::protowire::DecodeError> {
+            self.0.set(2, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value)))
+        }
+
+        pub fn clear_value(&mut self) -> Result<(), ::protowire::DecodeError> {
+            self.0.remove(2).map(|_| ())
+        }
+    }
+
+    /// Builds a `CountsEntry`, appending fields in the order they are given
+    #[derive(Debug, Default)]
+    pub struct CountsEntryBuilder(::protowire::Message);
+
+    impl CountsEntryBuilder {
+        /// `string key = 1`
+        pub fn key(mut self, value: &str) -> Self {
+            self.0.push(::protowire::Field::new(1, ::protowire::MessageObject::Len(::protowire::Len::new_string(value))));
+            self
+        }
+
+        /// `int32 value = 2`
+        pub fn value(mut self, value: i32) -> Self {
+            self.0.push(::protowire::Field::new(2, ::protowire::MessageObject::Varint(::protowire::Varint::new_proto_int32(value))));
+            self
+        }
+
+        pub fn build(self) -> CountsEntry {
+            CountsEntry(self.0)
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Attribute, GenericArgument, LitInt, LitStr, PathArguments, Type};
+
+/// The largest field number protobuf allows
+const MAX_FIELD_ID: u64 = (1 << 29) - 1;
+
+/// Field numbers reserved for the protobuf implementation
+const RESERVED_FIELD_IDS: core::ops::RangeInclusive<u64> = 19000..=19999;
+
+/// The encodings which can be named by `encoding = "..."`, and the types implementing them
+const ENCODINGS: &[(&str, &str)] = &[
+    ("int32", "Int32"),
+    ("int64", "Int64"),
+    ("uint32", "Uint32"),
+    ("uint64", "Uint64"),
+    ("sint32", "Sint32"),
+    ("sint64", "Sint64"),
+    ("fixed32", "Fixed32"),
+    ("fixed64", "Fixed64"),
+    ("sfixed32", "Sfixed32"),
+    ("sfixed64", "Sfixed64"),
+    ("bool", "Bool"),
+    ("float", "Float"),
+    ("double", "Double"),
+    ("string", "String"),
+    ("bytes", "Bytes"),
+    ("message", "Message"),
+    ("enum", "Enumeration"),
+];
+
+/// The options of a field or variant, given by `#[proto(...)]`
+#[derive(Default)]
+pub(crate) struct Attributes {
+    /// the field number, and where it was given
+    pub(crate) tag: Option<(u64, Span)>,
+    /// the type implementing the encoding named by `encoding = "..."`
+    pub(crate) encoding: Option<syn::Ident>,
+    pub(crate) packed: bool,
+    pub(crate) group: bool,
+    pub(crate) oneof: bool,
+    pub(crate) unknown: bool,
+}
+
+impl Attributes {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("proto")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag") {
+                    let lit: LitInt = meta.value()?.parse()?;
+                    let tag = lit.base10_parse::<u64>()?;
+                    if tag == 0 || tag > MAX_FIELD_ID {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            format!("field numbers must be between 1 and {MAX_FIELD_ID}"),
+                        ));
+                    }
+                    if RESERVED_FIELD_IDS.contains(&tag) {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "field numbers 19000 to 19999 are reserved by protobuf",
+                        ));
+                    }
+                    attributes.tag = Some((tag, lit.span()));
+                } else if meta.path.is_ident("encoding") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    let name = lit.value();
+                    let Some((_, encoding)) = ENCODINGS.iter().find(|(n, _)| *n == name) else {
+                        let names: Vec<&str> = ENCODINGS.iter().map(|(n, _)| *n).collect();
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            format!("unknown encoding, expected one of {}", names.join(", ")),
+                        ));
+                    };
+                    attributes.encoding = Some(syn::Ident::new(encoding, lit.span()));
+                } else if meta.path.is_ident("packed") {
+                    attributes.packed = true;
+                } else if meta.path.is_ident("group") {
+                    attributes.group = true;
+                } else if meta.path.is_ident("oneof") {
+                    attributes.oneof = true;
+                } else if meta.path.is_ident("unknown") {
+                    attributes.unknown = true;
+                } else {
+                    return Err(meta.error(
+                        "expected `tag`, `encoding`, `packed`, `group`, `oneof` or `unknown`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        if let (true, Some(encoding)) = (attributes.group, &attributes.encoding) {
+            return Err(syn::Error::new(
+                encoding.span(),
+                "`group` is an encoding, it can't be combined with `encoding`",
+            ));
+        }
+        Ok(attributes)
+    }
+
+    /// the type implementing the encoding of `ty`, the type of a single value
+    pub(crate) fn encoding(&self, ty: &Type) -> TokenStream {
+        if self.group {
+            quote!(::protowire::encoding::Group)
+        } else if let Some(encoding) = &self.encoding {
+            quote!(::protowire::encoding::#encoding)
+        } else {
+            quote!(<#ty as ::protowire::encoding::DefaultEncoding>::Encoding)
+        }
+    }
+}
+
+/// How many values a field holds, by its type
+pub(crate) enum Shape<'a> {
+    /// `T`
+    Singular(&'a Type),
+    /// `Option<T>`
+    Optional(&'a Type),
+    /// `Vec<T>`, other than `Vec<u8>` which is a single `bytes` value
+    Repeated(&'a Type),
+}
+
+impl<'a> Shape<'a> {
+    pub(crate) fn of(ty: &'a Type) -> Self {
+        match generic_argument(ty) {
+            Some(("Option", inner)) => Self::Optional(inner),
+            Some(("Vec", inner)) if !is_u8(inner) => Self::Repeated(inner),
+            _ => Self::Singular(ty),
+        }
+    }
+}
+
+/// the name and only type argument of `ty`, like `Vec` and `u8` for `Vec<u8>`
+fn generic_argument(ty: &Type) -> Option<(&'static str, &Type)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let name = ["Option", "Vec"]
+        .into_iter()
+        .find(|name| segment.ident == name)?;
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first() {
+        Some(GenericArgument::Type(inner)) if arguments.args.len() == 1 => Some((name, inner)),
+        _ => None,
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("u8"))
+}
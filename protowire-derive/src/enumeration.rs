@@ -0,0 +1,158 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DataEnum, DeriveInput, Fields};
+
+use crate::attributes::{Attributes, Shape};
+
+/// `ProtoEnum` for an enum of unit variants, or `ProtoOneof` for an enum whose variants
+/// each hold a single value
+pub(crate) fn expand(input: &DeriveInput, data: &DataEnum) -> syn::Result<TokenStream> {
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            input,
+            "`ProtoWire` needs at least one variant",
+        ));
+    }
+    if data
+        .variants
+        .iter()
+        .all(|variant| matches!(variant.fields, Fields::Unit))
+    {
+        enumeration(input, data)
+    } else {
+        oneof(input, data)
+    }
+}
+
+/// the number of each variant is its discriminant, as for `Variant as i32`
+fn enumeration(input: &DeriveInput, data: &DataEnum) -> syn::Result<TokenStream> {
+    let mut to_i32 = Vec::new();
+    let mut from_i32 = Vec::new();
+    for variant in &data.variants {
+        if variant
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("proto"))
+        {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "the number of an enum value is its discriminant, not a `#[proto]` option",
+            ));
+        }
+        let name = &variant.ident;
+        to_i32.push(quote!(Self::#name => Self::#name as i32,));
+        from_i32.push(quote! {
+            _ if value == Self::#name as i32 => ::core::option::Option::Some(Self::#name),
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::protowire::ProtoEnum for #name #ty_generics #where_clause {
+            fn to_i32(&self) -> i32 {
+                match self {
+                    #(#to_i32)*
+                }
+            }
+
+            fn from_i32(value: i32) -> ::core::option::Option<Self> {
+                match value {
+                    #(#from_i32)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+
+        impl #impl_generics ::protowire::encoding::DefaultEncoding for #name #ty_generics #where_clause {
+            type Encoding = ::protowire::encoding::Enumeration;
+        }
+    })
+}
+
+/// each variant holds the field `#[proto(tag = ...)]`, written even when it holds the
+/// default value, and merged like a singular field when it occurs again
+fn oneof(input: &DeriveInput, data: &DataEnum) -> syn::Result<TokenStream> {
+    let mut tags = Vec::new();
+    let mut encodes = Vec::new();
+    let mut decodes = Vec::new();
+    for variant in &data.variants {
+        let ty = match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "each variant of a oneof holds a single value, like `Variant(T)`",
+                ))
+            }
+        };
+        let attributes = Attributes::parse(&variant.attrs)?;
+        if attributes.packed || attributes.oneof || attributes.unknown {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "the fields of a oneof can't be `packed`, `oneof` or `unknown`",
+            ));
+        }
+        if !matches!(Shape::of(ty), Shape::Singular(_)) {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "the fields of a oneof can't be optional or repeated",
+            ));
+        }
+        let Some((tag, span)) = attributes.tag else {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "missing `#[proto(tag = ...)]`",
+            ));
+        };
+        if tags.contains(&tag) {
+            return Err(syn::Error::new(span, format!("field {tag} is used twice")));
+        }
+        tags.push(tag);
+
+        let name = &variant.ident;
+        let encoding = attributes.encoding(ty);
+        encodes.push(quote! {
+            Self::#name(value) => ::protowire::encoding::write::<#encoding, #ty>(message, #tag, value),
+        });
+        decodes.push(quote! {
+            #tag => ::protowire::encoding::read_variant::<#encoding, #ty, Self>(
+                message,
+                field,
+                value,
+                |value| match value {
+                    Self::#name(value) => ::core::option::Option::Some(value),
+                    _ => ::core::option::Option::None,
+                },
+                Self::#name,
+                depth,
+            ),
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::protowire::ProtoOneof for #name #ty_generics #where_clause {
+            fn encode(&self, message: &mut ::protowire::Message) {
+                match self {
+                    #(#encodes)*
+                }
+            }
+
+            // a oneof of a single variant has no other variants for `_` to match
+            #[allow(unreachable_patterns)]
+            fn merge(
+                value: &mut ::core::option::Option<Self>,
+                message: ::protowire::MessageRef<'_>,
+                field: &::protowire::FieldRef<'_>,
+                depth: usize,
+            ) -> ::core::result::Result<bool, ::protowire::DecodeError> {
+                match field.get_field_id() {
+                    #(#decodes)*
+                    _ => ::core::result::Result::Ok(false),
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,95 @@
+//! `#[derive(ProtoWire)]`, encoding plain Rust types as protobuf messages without a
+//! `.proto` file. Use it through protowire with the `derive` feature.
+//!
+//! On a struct with named fields it implements `protowire::ProtoWire`, each field holding
+//! the field of the message given by `#[proto(...)]`:
+//!
+//! - `tag = N`: the field number, which every field needs unless it is a `oneof` or
+//!   `unknown` field
+//! - `encoding = "sint64"`: the protobuf type of the field, one of `int32`, `int64`,
+//!   `uint32`, `uint64`, `sint32`, `sint64`, `fixed32`, `fixed64`, `sfixed32`,
+//!   `sfixed64`, `bool`, `float`, `double`, `string`, `bytes`, `message` or `enum`. By
+//!   default it follows the Rust type, `int32` for `i32`, `bytes` for `Vec<u8>`, and
+//!   `message` or `enum` for types deriving `ProtoWire`
+//! - `packed`: writes a repeated scalar as a single packed field. Repeated fields are read
+//!   whether or not they are packed
+//! - `group`: holds a message in a group rather than a `Len`
+//! - `oneof`: an `Option` of an enum deriving `ProtoWire` whose variants each hold one of
+//!   the fields of the oneof, with their own `tag` and `encoding`
+//! - `unknown`: a `protowire::Message` holding the fields no other field can, which are
+//!   written back after the others
+//!
+//! `Option<T>` fields have presence, `Vec<T>` fields are repeated, and other fields skip
+//! their default value as proto3 does, so they need to implement `Default`.
+//!
+//! On an enum of unit variants it implements `protowire::ProtoEnum`, numbering each value
+//! by its discriminant.
+//!
+//! ```
+//! use protowire::{Message, ProtoWire};
+//!
+//! #[derive(ProtoWire, Debug, Default, PartialEq)]
+//! enum Status {
+//!     #[default]
+//!     Pending = 0,
+//!     Shipped = 1,
+//! }
+//!
+//! #[derive(ProtoWire, Debug, PartialEq)]
+//! enum Payment {
+//!     #[proto(tag = 4)]
+//!     Card(String),
+//!     #[proto(tag = 5)]
+//!     Voucher(u64),
+//! }
+//!
+//! #[derive(ProtoWire, Debug, PartialEq)]
+//! struct Order {
+//!     #[proto(tag = 1)]
+//!     id: String,
+//!     #[proto(tag = 2, encoding = "sint64")]
+//!     balance: i64,
+//!     #[proto(tag = 3, packed)]
+//!     quantities: Vec<u32>,
+//!     #[proto(oneof)]
+//!     payment: Option<Payment>,
+//!     #[proto(tag = 6)]
+//!     status: Status,
+//!     #[proto(unknown)]
+//!     unknown: Message,
+//! }
+//!
+//! let order = Order {
+//!     id: "A-1".to_string(),
+//!     balance: -3,
+//!     quantities: vec![1, 2],
+//!     payment: Some(Payment::Voucher(7)),
+//!     status: Status::Shipped,
+//!     unknown: Message::new(),
+//! };
+//! let message = order.to_message();
+//! assert_eq!(Order::from_message(&message).unwrap(), order);
+//! ```
+
+mod attributes;
+mod enumeration;
+mod message;
+
+use proc_macro::TokenStream;
+use syn::{Data, DeriveInput};
+
+#[proc_macro_derive(ProtoWire, attributes(proto))]
+pub fn derive_proto_wire(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    let expanded = match &input.data {
+        Data::Struct(data) => message::expand(&input, data),
+        Data::Enum(data) => enumeration::expand(&input, data),
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            &input.ident,
+            "`ProtoWire` can't be derived for unions",
+        )),
+    };
+    expanded
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,191 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DataStruct, DeriveInput, Fields};
+
+use crate::attributes::{Attributes, Shape};
+
+/// `ProtoWire` for a struct, holding a field of the message in each of its fields
+pub(crate) fn expand(input: &DeriveInput, data: &DataStruct) -> syn::Result<TokenStream> {
+    let fields = match &data.fields {
+        Fields::Named(fields) => fields.named.iter().collect(),
+        Fields::Unit => Vec::new(),
+        Fields::Unnamed(_) => {
+            return Err(syn::Error::new_spanned(
+                &data.fields,
+                "`ProtoWire` needs named fields to attach tags to",
+            ))
+        }
+    };
+
+    let unknown_field = fields
+        .iter()
+        .find(|field| Attributes::parse(&field.attrs).is_ok_and(|a| a.unknown))
+        .and_then(|field| field.ident.as_ref());
+    let unknown_arg = match unknown_field {
+        Some(name) => quote!(::core::option::Option::Some(&mut self.#name)),
+        None => quote!(::core::option::Option::None),
+    };
+
+    let mut tags = Vec::new();
+    let mut encodes = Vec::new();
+    let mut decodes = Vec::new();
+    let mut oneofs = Vec::new();
+    let mut unknown = None;
+    let mut names = Vec::new();
+    for field in fields {
+        let name = field.ident.as_ref().expect("named fields have names");
+        names.push(name);
+        let attributes = Attributes::parse(&field.attrs)?;
+
+        if attributes.oneof || attributes.unknown {
+            if attributes.tag.is_some()
+                || attributes.encoding.is_some()
+                || attributes.packed
+                || attributes.group
+                || (attributes.oneof && attributes.unknown)
+            {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "`oneof` and `unknown` fields take no other options",
+                ));
+            }
+            if attributes.unknown {
+                if unknown.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "only one field can hold the unknown fields",
+                    ));
+                }
+                unknown = Some(name);
+                continue;
+            }
+            let Shape::Optional(_) = Shape::of(&field.ty) else {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "a `oneof` field must be an `Option` of an enum deriving `ProtoWire`",
+                ));
+            };
+            encodes.push(quote! {
+                if let ::core::option::Option::Some(value) = &self.#name {
+                    ::protowire::ProtoOneof::encode(value, message);
+                }
+            });
+            oneofs.push(quote! {
+                ::protowire::encoding::read_oneof(message, &field, &mut self.#name, depth)?
+            });
+            continue;
+        }
+
+        let Some((tag, span)) = attributes.tag else {
+            return Err(syn::Error::new_spanned(
+                field,
+                "missing `#[proto(tag = ...)]`, or `#[proto(oneof)]` or `#[proto(unknown)]`",
+            ));
+        };
+        if tags.contains(&tag) {
+            return Err(syn::Error::new(span, format!("field {tag} is used twice")));
+        }
+        tags.push(tag);
+
+        let shape = Shape::of(&field.ty);
+        if attributes.packed && !matches!(shape, Shape::Repeated(_)) {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "only repeated fields, a `Vec`, can be packed",
+            ));
+        }
+        let (encode, decode) = match shape {
+            Shape::Singular(ty) => {
+                let encoding = attributes.encoding(ty);
+                (
+                    quote!(::protowire::encoding::write_implicit::<#encoding, #ty>(message, #tag, &self.#name);),
+                    quote!(::protowire::encoding::read_singular::<#encoding, #ty>(message, &field, &mut self.#name, depth)?),
+                )
+            }
+            Shape::Optional(ty) => {
+                let encoding = attributes.encoding(ty);
+                (
+                    quote! {
+                        if let ::core::option::Option::Some(value) = &self.#name {
+                            ::protowire::encoding::write::<#encoding, #ty>(message, #tag, value);
+                        }
+                    },
+                    quote!(::protowire::encoding::read_optional::<#encoding, #ty>(message, &field, &mut self.#name, depth)?),
+                )
+            }
+            Shape::Repeated(ty) => {
+                let encoding = attributes.encoding(ty);
+                let write = if attributes.packed {
+                    quote!(write_packed)
+                } else {
+                    quote!(write_repeated)
+                };
+                (
+                    quote!(::protowire::encoding::#write::<#encoding, #ty>(message, #tag, &self.#name);),
+                    quote!(::protowire::encoding::read_repeated::<#encoding, #ty>(message, &field, &mut self.#name, #unknown_arg, depth)?),
+                )
+            }
+        };
+        encodes.push(encode);
+        decodes.push(quote!(#tag => #decode,));
+    }
+
+    let (encode_unknown, decode_unknown) = match unknown {
+        Some(name) => (
+            quote!(::protowire::encoding::write_unknown(message, &self.#name);),
+            quote! {
+                if !known {
+                    ::protowire::encoding::read_unknown(&field, &mut self.#name);
+                }
+            },
+        ),
+        None => (quote!(), quote!(let _ = known;)),
+    };
+    let oneofs = if oneofs.is_empty() {
+        quote!(false)
+    } else {
+        quote!(#(#oneofs)||*)
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::protowire::ProtoWire for #name #ty_generics #where_clause {
+            fn encode(&self, message: &mut ::protowire::Message) {
+                #(#encodes)*
+                #encode_unknown
+            }
+
+            fn decode_nested(
+                message: ::protowire::MessageRef<'_>,
+                depth: usize,
+            ) -> ::core::result::Result<Self, ::protowire::DecodeError> {
+                let mut value = Self {
+                    #(#names: ::core::default::Default::default(),)*
+                };
+                ::protowire::ProtoWire::merge_nested(&mut value, message, depth)?;
+                ::core::result::Result::Ok(value)
+            }
+
+            fn merge_nested(
+                &mut self,
+                message: ::protowire::MessageRef<'_>,
+                depth: usize,
+            ) -> ::core::result::Result<(), ::protowire::DecodeError> {
+                for field in message.into_try_iter() {
+                    let field = field?;
+                    let known = match field.get_field_id() {
+                        #(#decodes)*
+                        _ => #oneofs,
+                    };
+                    #decode_unknown
+                }
+                ::core::result::Result::Ok(())
+            }
+        }
+
+        impl #impl_generics ::protowire::encoding::DefaultEncoding for #name #ty_generics #where_clause {
+            type Encoding = ::protowire::encoding::Message;
+        }
+    })
+}
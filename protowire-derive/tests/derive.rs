@@ -0,0 +1,270 @@
+use protowire::{DecodeErrorKind, Message, ProtoWire};
+
+#[derive(ProtoWire, Debug, Default, PartialEq)]
+enum Status {
+    #[default]
+    Pending = 0,
+    Shipped = 1,
+    Lost = -1,
+}
+
+#[derive(ProtoWire, Debug, Default, PartialEq)]
+struct Item {
+    #[proto(tag = 1)]
+    name: String,
+    #[proto(tag = 2, encoding = "fixed32")]
+    price: u32,
+}
+
+#[derive(ProtoWire, Debug, PartialEq)]
+enum Payment {
+    #[proto(tag = 20)]
+    Card(String),
+    #[proto(tag = 21)]
+    Voucher(Item),
+    #[proto(tag = 22, encoding = "sint32")]
+    Credit(i32),
+}
+
+#[derive(ProtoWire, Debug, Default, PartialEq)]
+struct Order {
+    #[proto(tag = 1)]
+    id: String,
+    #[proto(tag = 2, encoding = "sint64")]
+    balance: i64,
+    #[proto(tag = 3, packed)]
+    quantities: Vec<u32>,
+    #[proto(tag = 4)]
+    tags: Vec<String>,
+    #[proto(tag = 5)]
+    status: Status,
+    #[proto(tag = 6)]
+    item: Option<Item>,
+    #[proto(tag = 7, group)]
+    gift: Option<Item>,
+    #[proto(tag = 8)]
+    blob: Vec<u8>,
+    #[proto(tag = 9)]
+    ratio: f64,
+    #[proto(tag = 10, encoding = "sfixed64")]
+    offsets: Vec<i64>,
+    #[proto(tag = 11)]
+    discount: Option<u32>,
+    #[proto(tag = 12, packed)]
+    history: Vec<Status>,
+    #[proto(oneof)]
+    payment: Option<Payment>,
+    #[proto(unknown)]
+    unknown: Message,
+}
+
+fn protoscope(text: &str) -> Vec<u8> {
+    Message::from_protoscope(text)
+        .unwrap()
+        .serialize()
+        .as_ref()
+        .to_vec()
+}
+
+fn decode(data: &[u8]) -> Order {
+    Order::from_message(&Message::from(protowire::WireData::new(data.to_vec()))).unwrap()
+}
+
+#[test]
+fn test_encode() {
+    let order = Order {
+        id: "A-1".to_string(),
+        balance: -2,
+        quantities: vec![1, 300],
+        tags: vec!["x".to_string(), "y".to_string()],
+        status: Status::Lost,
+        item: Some(Item {
+            name: "pen".to_string(),
+            price: 3,
+        }),
+        gift: Some(Item::default()),
+        blob: vec![0xff],
+        ratio: -0.0,
+        offsets: vec![-1],
+        discount: Some(0),
+        history: vec![Status::Shipped, Status::Pending],
+        payment: Some(Payment::Credit(-1)),
+        unknown: Message::new(),
+    };
+    let data = order.to_message().serialize().as_ref().to_vec();
+    // fields holding their default value are left out, except for `Option`s, and enums
+    // are written like `Varint::new_proto_int32`
+    assert_eq!(
+        data,
+        protoscope(
+            r#"
+            1: {"A-1"}
+            2: 3
+            3: {`01ac02`}
+            4: {"x"} 4: {"y"}
+            5: 4294967295
+            6: {1: {"pen"} 2: 3i32}
+            7: !{}
+            8: {`ff`}
+            9: -0.0
+            10: -1i64
+            11: 0
+            12: {`0100`}
+            22: 1
+            "#
+        )
+    );
+    assert_eq!(decode(&data), order);
+
+    // nothing is written for an empty message
+    let empty = Order::default().to_message().serialize();
+    assert!(empty.as_ref().is_empty());
+}
+
+#[test]
+fn test_decode() {
+    // the last occurrence of a singular field wins, repeated fields are read packed or not,
+    // and fields which don't fit go to the unknown fields along with those never declared,
+    // including packed enum values without a variant
+    let data = protoscope(
+        r#"
+        1: {"first"}
+        99: 5
+        2: 1i32
+        1: {"last"}
+        3: 7 3: {`0809`}
+        5: 9
+        12: 1 12: {`0005`}
+        20: {"card"}
+        21: {1: {"gift card"}}
+        "#,
+    );
+    let order = decode(&data);
+    assert_eq!(order.id, "last");
+    assert_eq!(order.balance, 0);
+    assert_eq!(order.quantities, [7, 8, 9]);
+    assert_eq!(order.status, Status::Pending);
+    assert_eq!(order.history, [Status::Shipped, Status::Pending]);
+    assert_eq!(
+        order.payment,
+        Some(Payment::Voucher(Item {
+            name: "gift card".to_string(),
+            price: 0,
+        }))
+    );
+    assert_eq!(
+        order.unknown.clone().serialize().as_ref(),
+        protoscope("99: 5 2: 1i32 5: 9 12: 5").as_slice()
+    );
+
+    // unknown fields are written back after the others
+    let data = order.to_message().serialize().as_ref().to_vec();
+    assert_eq!(
+        data,
+        protoscope(
+            r#"
+            1: {"last"} 3: {`070809`} 12: {`0100`} 21: {1: {"gift card"}}
+            99: 5 2: 1i32 5: 9 12: 5
+            "#
+        )
+    );
+    assert_eq!(decode(&data), order);
+
+    // occurrences of a message are merged, as are those of a oneof holding the same variant
+    let order = decode(&protoscope(
+        r#"
+        6: {1: {"pen"}} 6: {2: 3i32}
+        7: !{2: 4i32} 7: !{1: {"bow"}}
+        21: {1: {"gift card"}} 21: {2: 5i32}
+        "#,
+    ));
+    let item = |name: &str, price| Item {
+        name: name.to_string(),
+        price,
+    };
+    assert_eq!(order.item, Some(item("pen", 3)));
+    assert_eq!(order.gift, Some(item("bow", 4)));
+    assert_eq!(order.payment, Some(Payment::Voucher(item("gift card", 5))));
+    let order = decode(&protoscope(r#"20: {"card"} 21: {2: 5i32}"#));
+    assert_eq!(order.payment, Some(Payment::Voucher(item("", 5))));
+
+    // errors point at the offending bytes, through nested messages
+    let data = protoscope(r#"1: {"ok"} 6: {1: {`ff`}}"#);
+    let error = Order::from_message(&Message::from(protowire::WireData::new(data.clone())))
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), &DecodeErrorKind::InvalidUtf8);
+    assert_eq!(error.path(), &[6, 1]);
+    assert_eq!(&data[error.offset()..], &[0xff]);
+}
+
+#[derive(ProtoWire, Debug, Default, PartialEq)]
+struct Raw {
+    #[proto(unknown)]
+    unknown: Message,
+}
+
+#[derive(ProtoWire, Debug, Default, PartialEq)]
+struct Wrapper {
+    #[proto(tag = 1, group)]
+    raw: Option<Raw>,
+}
+
+#[test]
+fn test_group_payload() {
+    // a group is written as it is encoded, even if it isn't valid wire data
+    let wrapper = Wrapper {
+        raw: Some(Raw {
+            unknown: Message::from(protowire::WireData::new(vec![0x10, 0x01, 0x0a, 0x05])),
+        }),
+    };
+    assert_eq!(
+        wrapper.to_message().serialize().as_ref(),
+        [0x0b, 0x10, 0x01, 0x0a, 0x05, 0x0c]
+    );
+}
+
+#[derive(ProtoWire, Debug, Default, PartialEq)]
+struct Node {
+    #[proto(tag = 1)]
+    children: Vec<Node>,
+}
+
+/// `depth` nodes nested in each other below the root, each the only child of its parent
+fn nested_nodes(depth: usize) -> Vec<u8> {
+    fn varint(mut value: usize, data: &mut Vec<u8>) {
+        while value >= 0x80 {
+            data.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        data.push(value as u8);
+    }
+    // sizes[k] is the encoded size of a node holding k nested nodes
+    let mut sizes = vec![0];
+    for k in 0..depth {
+        let mut prefix = Vec::new();
+        varint(sizes[k], &mut prefix);
+        sizes.push(1 + prefix.len() + sizes[k]);
+    }
+    let mut data = Vec::new();
+    for k in (0..depth).rev() {
+        data.push(0x0a);
+        varint(sizes[k], &mut data);
+    }
+    data
+}
+
+#[test]
+fn test_depth_limit() {
+    // messages nest up to the default limit, and deeper input fails rather than
+    // overflowing the stack
+    let node = Node::decode(protowire::MessageRef::new(&nested_nodes(100))).unwrap();
+    assert_eq!(node.children.len(), 1);
+    for depth in [101, 200_000] {
+        let error = Node::decode(protowire::MessageRef::new(&nested_nodes(depth))).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::DepthLimitExceeded { limit: 100 }
+        );
+    }
+}
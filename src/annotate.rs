@@ -0,0 +1,416 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Formatter, Result, Write};
+use core::ops::Range;
+
+use crate::borrowed::{I32Ref, I64Ref, LenRef, MessageRef, VarintRef};
+use crate::classify::{classify, nice_float, RawValue};
+use crate::decode_options::DecodeOptions;
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::field::Field;
+use crate::group::Group;
+use crate::message::Message;
+
+/// What a range of bytes in an annotated message encodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteRole {
+    /// the tag of a field, holding its field id and wire type
+    Tag,
+    /// the length prefix of a `Len` field
+    Length,
+    /// the value of a varint or fixed width field
+    Value,
+    /// the payload of a `Len` field which isn't a message
+    Payload,
+    /// the end-group tag closing a group
+    EndGroup,
+    /// the bytes from where parsing failed to the end of the data
+    Invalid,
+}
+
+/// One range of bytes of an annotated message and what it means
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedRange {
+    /// the absolute offsets of the bytes
+    pub range: Range<usize>,
+    pub role: ByteRole,
+    /// how many messages and groups enclose the range
+    pub depth: usize,
+    /// the field the bytes belong to, if parsing got as far as its tag
+    pub field_id: Option<u64>,
+    pub wire_type: Option<u64>,
+    /// the decoded meaning, e.g. the value of a varint, the text of a string or the error
+    /// which stopped parsing
+    pub description: String,
+}
+
+/// Wire data broken down into annotated byte ranges, printed as a hex dump by its
+/// `Display` implementation
+///
+/// Nested messages are annotated field by field rather than as a payload, using the same
+/// heuristic as `LenRef::classify`. Malformed data is annotated up to the element which
+/// failed to parse, followed by an `Invalid` range starting at the byte where it begins,
+/// which for a group without an end-group tag is its start tag.
+#[derive(Debug, Clone)]
+pub struct Annotation<'a> {
+    data: &'a [u8],
+    ranges: Vec<AnnotatedRange>,
+    error: Option<DecodeError>,
+}
+
+/// How many bytes are shown on each line of the hex dump
+const BYTES_PER_LINE: usize = 8;
+
+impl Message {
+    pub fn annotate(&self) -> Annotation<'_> {
+        self.view().annotate()
+    }
+}
+
+impl<'a> MessageRef<'a> {
+    /// breaks the message down into the byte ranges of every tag, length prefix, value and
+    /// end-group tag, see `Annotation`
+    pub fn annotate(&self) -> Annotation<'a> {
+        let mut annotator = Annotator {
+            data: self.0,
+            ranges: Vec::new(),
+            options: DecodeOptions::new(),
+            unterminated: None,
+        };
+        let error = annotator.fields(0, self.0.len(), 0, None).err();
+        if let Some(e) = &error {
+            let start = annotator.unterminated.unwrap_or(e.offset());
+            annotator.ranges.retain(|range| range.range.start < start);
+            annotator.ranges.push(AnnotatedRange {
+                range: start..self.0.len(),
+                role: ByteRole::Invalid,
+                depth: annotator.ranges.last().map_or(0, |last| last.depth),
+                field_id: None,
+                wire_type: None,
+                description: e.kind().to_string(),
+            });
+        }
+
+        Annotation {
+            data: self.0,
+            ranges: annotator.ranges,
+            error,
+        }
+    }
+}
+
+impl<'a> Annotation<'a> {
+    pub fn ranges(&self) -> &[AnnotatedRange] {
+        &self.ranges
+    }
+
+    /// the error which stopped parsing, if the data is malformed
+    pub fn error(&self) -> Option<&DecodeError> {
+        self.error.as_ref()
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl core::fmt::Display for ByteRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.pad(match self {
+            Self::Tag => "tag",
+            Self::Length => "length",
+            Self::Value => "value",
+            Self::Payload => "payload",
+            Self::EndGroup => "end-group",
+            Self::Invalid => "invalid",
+        })
+    }
+}
+
+impl core::fmt::Display for Annotation<'_> {
+    /// one line per range, e.g. `000002  96 01    value      150`, payloads longer than
+    /// a line continuing on the following lines without a role
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut first = true;
+        for annotated in &self.ranges {
+            let bytes = &self.data[annotated.range.clone()];
+            let mut lines = bytes.chunks(BYTES_PER_LINE);
+            let mut offset = annotated.range.start;
+            let head = lines.next().unwrap_or_default();
+
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            write_hex(f, offset, head)?;
+            for _ in 0..annotated.depth {
+                write!(f, "  ")?;
+            }
+            write!(f, "{:<9}  {}", annotated.role, annotated.description)?;
+            if annotated.role == ByteRole::Invalid {
+                write!(f, " (at byte {})", annotated.range.start)?;
+            }
+
+            for line in lines {
+                offset += BYTES_PER_LINE;
+                writeln!(f)?;
+                write!(f, "{offset:06x}  {}", hex(line))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the offset and the bytes of one line, padded so the roles line up
+fn write_hex(f: &mut Formatter<'_>, offset: usize, bytes: &[u8]) -> Result {
+    let width = BYTES_PER_LINE * 3 - 1;
+    write!(f, "{offset:06x}  {:<width$}  ", hex(bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(BYTES_PER_LINE * 3);
+    for (index, byte) in bytes.iter().enumerate() {
+        if index > 0 {
+            hex.push(' ');
+        }
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
+
+struct Annotator<'a> {
+    data: &'a [u8],
+    ranges: Vec<AnnotatedRange>,
+    options: DecodeOptions,
+    /// where the start tag of the group missing its end-group tag is, if one is
+    unterminated: Option<usize>,
+}
+
+impl Annotator<'_> {
+    /// annotates the fields in `position..end`, returning where they stopped. Inside a
+    /// group (`group` being its field id) that is just after its end-group tag
+    fn fields(
+        &mut self,
+        mut position: usize,
+        end: usize,
+        depth: usize,
+        group: Option<u64>,
+    ) -> core::result::Result<usize, DecodeError> {
+        while position < end {
+            let (tag, _) =
+                VarintRef::from(&self.data[position..end]).map_err(|e| e.shift(position))?;
+            let tag_value = tag.get();
+            let field_id = Field::field_id_from_tag(tag_value);
+            let wire_type = Field::wire_type_from_tag(tag_value);
+            Field::check_tag(tag_value, &self.options, group.is_some() as usize)
+                .map_err(|e| e.shift(position))?;
+
+            let tag_range = position..position + tag.0.len();
+            if wire_type == 4 {
+                return match group {
+                    Some(id) if id == field_id => {
+                        self.push(
+                            tag_range.clone(),
+                            ByteRole::EndGroup,
+                            depth - 1,
+                            field_id,
+                            4,
+                        )
+                        .push_str(&format!("{field_id}:EGROUP"));
+                        Ok(tag_range.end)
+                    }
+                    _ => Err(Group::mismatched(
+                        group.unwrap_or_default(),
+                        field_id,
+                        position,
+                    )),
+                };
+            }
+            self.push(tag_range.clone(), ByteRole::Tag, depth, field_id, wire_type)
+                .push_str(&format!("{field_id}:{}", wire_type_name(wire_type)));
+
+            position = self
+                .value(field_id, wire_type, tag_range.end, end, depth)
+                .map_err(|e| {
+                    if wire_type == 3
+                        && self.unterminated.is_none()
+                        && e.kind() == &DecodeErrorKind::UnterminatedGroup(field_id)
+                    {
+                        self.unterminated = Some(tag_range.start);
+                    }
+                    e.within(field_id)
+                })?;
+        }
+
+        match group {
+            Some(id) => Err(Group::unterminated(id, position)),
+            None => Ok(position),
+        }
+    }
+
+    /// annotates the value of a field starting at `start`, returning where it ends
+    fn value(
+        &mut self,
+        field_id: u64,
+        wire_type: u64,
+        start: usize,
+        end: usize,
+        depth: usize,
+    ) -> core::result::Result<usize, DecodeError> {
+        let data = &self.data[start..end];
+        let value_end = match wire_type {
+            0 => {
+                let (value, _) = VarintRef::from(data).map_err(|e| e.shift(start))?;
+                let value_end = start + value.0.len();
+                let description = match value.get() as i64 {
+                    signed if signed < 0 => format!("{} ({signed})", value.get()),
+                    _ => value.get().to_string(),
+                };
+                self.push(
+                    start..value_end,
+                    ByteRole::Value,
+                    depth,
+                    field_id,
+                    wire_type,
+                )
+                .push_str(&description);
+                value_end
+            }
+            1 => {
+                let (value, _) = I64Ref::from(data).map_err(|e| e.shift(start))?;
+                let description = fixed64(value.get());
+                self.push(
+                    start..start + 8,
+                    ByteRole::Value,
+                    depth,
+                    field_id,
+                    wire_type,
+                )
+                .push_str(&description);
+                start + 8
+            }
+            5 => {
+                let (value, _) = I32Ref::from(data).map_err(|e| e.shift(start))?;
+                let description = fixed32(value.get());
+                self.push(
+                    start..start + 4,
+                    ByteRole::Value,
+                    depth,
+                    field_id,
+                    wire_type,
+                )
+                .push_str(&description);
+                start + 4
+            }
+            2 => {
+                let (len, _) =
+                    LenRef::from_with_options(data, &self.options).map_err(|e| e.shift(start))?;
+                let payload_start = start + len.length.0.len();
+                let payload_end = payload_start + len.inner.len();
+                self.push(
+                    start..payload_start,
+                    ByteRole::Length,
+                    depth,
+                    field_id,
+                    wire_type,
+                )
+                .push_str(&len.inner.len().to_string());
+
+                // only one level is classified at a time, the nested fields get their own turn
+                let nesting = (depth < self.options.max_depth) as usize;
+                match classify(len.inner, nesting).0 {
+                    RawValue::Message(_) => {
+                        // the classifier only picks a message when it decodes completely
+                        self.fields(payload_start, payload_end, depth + 1, None)?;
+                    }
+                    _ if len.inner.is_empty() => {}
+                    value => {
+                        let description = describe(&value);
+                        self.push(
+                            payload_start..payload_end,
+                            ByteRole::Payload,
+                            depth,
+                            field_id,
+                            wire_type,
+                        )
+                        .push_str(&description);
+                    }
+                }
+                payload_end
+            }
+            3 => {
+                Group::check_depth(&self.options, depth + 1).map_err(|e| e.shift(start))?;
+                self.fields(start, end, depth + 1, Some(field_id))?
+            }
+            _ => unreachable!("wire type was validated by check_tag"),
+        };
+        Ok(value_end)
+    }
+
+    /// adds a range and returns its description to be filled in
+    fn push(
+        &mut self,
+        range: Range<usize>,
+        role: ByteRole,
+        depth: usize,
+        field_id: u64,
+        wire_type: u64,
+    ) -> &mut String {
+        self.ranges.push(AnnotatedRange {
+            range,
+            role,
+            depth,
+            field_id: Some(field_id),
+            wire_type: Some(wire_type),
+            description: String::new(),
+        });
+        &mut self.ranges.last_mut().unwrap().description
+    }
+}
+
+fn wire_type_name(wire_type: u64) -> &'static str {
+    match wire_type {
+        0 => "VARINT",
+        1 => "I64",
+        2 => "LEN",
+        3 => "SGROUP",
+        4 => "EGROUP",
+        _ => "I32",
+    }
+}
+
+/// a short description of a payload which isn't a message
+fn describe(value: &RawValue<'_>) -> String {
+    match value {
+        RawValue::String(s) => format!("{s:?}"),
+        RawValue::Bytes(bytes) => format!("{} bytes", bytes.len()),
+        RawValue::PackedVarint(values) => format!("packed {values:?}"),
+        RawValue::PackedI64(values) => {
+            let values: Vec<String> = values.iter().map(|value| fixed64(*value)).collect();
+            format!("packed [{}]", values.join(", "))
+        }
+        RawValue::PackedI32(values) => {
+            let values: Vec<String> = values.iter().map(|value| fixed32(*value)).collect();
+            format!("packed [{}]", values.join(", "))
+        }
+        _ => String::new(),
+    }
+}
+
+/// a fixed width value as a float if it reads as one, written like protoscope does
+fn fixed64(value: i64) -> String {
+    let float = f64::from_bits(value as u64);
+    match nice_float(float) {
+        true => format!("{float:?}"),
+        false => format!("{value}i64"),
+    }
+}
+
+fn fixed32(value: i32) -> String {
+    let float = f32::from_bits(value as u32);
+    match nice_float(float as f64) {
+        true => format!("{float:?}i32"),
+        false => format!("{value}i32"),
+    }
+}
@@ -0,0 +1,873 @@
+//! Borrowed counterparts of the wire types, parsed straight from a `&[u8]`
+//!
+//! These mirror the owned types in the rest of the crate but hold slices of the caller's
+//! buffer instead of `WireData`, so they can be used over an mmap, a `Vec<u8>` or a network
+//! buffer without copying it or touching a reference count. Each can be converted into its
+//! owned counterpart with `into_owned`, which copies only the bytes it covers.
+
+use alloc::vec::Vec;
+
+use crate::decode_options::DecodeOptions;
+use crate::error::DecodeError;
+use crate::field::Field;
+use crate::group::Group;
+use crate::i32::I32;
+use crate::i64::I64;
+use crate::len::Len;
+use crate::message::Message;
+use crate::message_object::MessageObject;
+use crate::packed_repeated::{PackedRepeatedI32, PackedRepeatedI64, PackedRepeatedVarint};
+use crate::varint::Varint;
+use crate::wire_data::WireData;
+
+fn copy_wire_data(data: &[u8]) -> WireData {
+    WireData::Const(bytes::Bytes::copy_from_slice(data))
+}
+
+/// the position of `slice` within `data`, which it must borrow from
+pub(crate) fn range_of(data: &[u8], slice: &[u8]) -> core::ops::Range<usize> {
+    let start = slice.as_ptr() as usize - data.as_ptr() as usize;
+    start..start + slice.len()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VarintRef<'a>(pub(crate) &'a [u8]);
+
+impl<'a> VarintRef<'a> {
+    pub fn from(data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        Self::from_with_options(data, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        data: &'a [u8],
+        options: &DecodeOptions,
+    ) -> Result<(Self, &'a [u8]), DecodeError> {
+        let len = Varint::scan(data, options)?;
+        let (varint, remainder) = data.split_at(len);
+
+        Ok((Self(varint), remainder))
+    }
+
+    pub fn get(&self) -> u64 {
+        Varint::decode(self.0)
+    }
+
+    pub fn as_proto_int32(&self) -> i32 {
+        self.get() as u32 as i32
+    }
+
+    pub fn as_proto_int64(&self) -> i64 {
+        self.get() as i64
+    }
+
+    pub fn as_proto_sint32(&self) -> i32 {
+        Varint::decode_sint32(self.get())
+    }
+
+    pub fn as_proto_sint64(&self) -> i64 {
+        Varint::decode_sint64(self.get())
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn into_owned(self) -> Varint {
+        Varint(copy_wire_data(self.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct I32Ref<'a>(pub(crate) &'a [u8]);
+
+impl<'a> I32Ref<'a> {
+    pub fn from(data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        I32::check_len(data.len())?;
+        let (value, remainder) = data.split_at(4);
+
+        Ok((Self(value), remainder))
+    }
+
+    pub fn get(&self) -> i32 {
+        // safety: an I32Ref must only be constructed from a 4 byte slice
+        i32::from_le_bytes(self.0.try_into().unwrap())
+    }
+
+    pub fn get_float(&self) -> f32 {
+        // safety: an I32Ref must only be constructed from a 4 byte slice
+        f32::from_le_bytes(self.0.try_into().unwrap())
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn into_owned(self) -> I32 {
+        I32(copy_wire_data(self.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct I64Ref<'a>(pub(crate) &'a [u8]);
+
+impl<'a> I64Ref<'a> {
+    pub fn from(data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        I64::check_len(data.len())?;
+        let (value, remainder) = data.split_at(8);
+
+        Ok((Self(value), remainder))
+    }
+
+    pub fn get(&self) -> i64 {
+        // safety: an I64Ref must only be constructed from an 8 byte slice
+        i64::from_le_bytes(self.0.try_into().unwrap())
+    }
+
+    pub fn get_double(&self) -> f64 {
+        // safety: an I64Ref must only be constructed from an 8 byte slice
+        f64::from_le_bytes(self.0.try_into().unwrap())
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn into_owned(self) -> I64 {
+        I64(copy_wire_data(self.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LenRef<'a> {
+    pub(crate) length: VarintRef<'a>,
+    pub(crate) inner: &'a [u8],
+}
+
+impl<'a> LenRef<'a> {
+    pub fn from(data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        Self::from_with_options(data, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        data: &'a [u8],
+        options: &DecodeOptions,
+    ) -> Result<(Self, &'a [u8]), DecodeError> {
+        let (length, remainder) = VarintRef::from_with_options(data, options)?;
+        let split_len = Len::check_length(length.get(), remainder.len(), options)?;
+        let (inner, remainder) = remainder.split_at(split_len);
+
+        Ok((Self { length, inner }, remainder))
+    }
+
+    pub fn get_data(&self) -> &'a [u8] {
+        self.inner
+    }
+
+    pub fn as_str(&self) -> Result<&'a str, core::str::Utf8Error> {
+        core::str::from_utf8(self.inner)
+    }
+
+    pub fn into_message(self) -> MessageRef<'a> {
+        MessageRef(self.inner)
+    }
+
+    pub fn into_packed_repeated_varint(self) -> PackedRepeatedVarintRef<'a> {
+        PackedRepeatedVarintRef(self.inner)
+    }
+
+    pub fn into_packed_repeated_i64(self) -> PackedRepeatedI64Ref<'a> {
+        PackedRepeatedI64Ref(self.inner)
+    }
+
+    pub fn into_packed_repeated_i32(self) -> PackedRepeatedI32Ref<'a> {
+        PackedRepeatedI32Ref(self.inner)
+    }
+
+    pub fn into_owned(self) -> Len {
+        Len {
+            length: self.length.into_owned(),
+            inner: copy_wire_data(self.inner),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GroupRef<'a> {
+    pub(crate) end_field_id: VarintRef<'a>,
+    pub(crate) fields: Vec<FieldRef<'a>>,
+}
+
+impl<'a> GroupRef<'a> {
+    pub fn from(field_id: u64, data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        Self::from_with_options(field_id, data, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        field_id: u64,
+        data: &'a [u8],
+        options: &DecodeOptions,
+    ) -> Result<(Self, &'a [u8]), DecodeError> {
+        Self::parse(field_id, data, options, 1)
+    }
+
+    /// mirrors `Group::parse`, see there for the meaning of `depth`
+    pub(crate) fn parse(
+        field_id: u64,
+        mut data: &'a [u8],
+        options: &DecodeOptions,
+        depth: usize,
+    ) -> Result<(Self, &'a [u8]), DecodeError> {
+        Group::check_depth(options, depth)?;
+
+        let mut fields = Vec::new();
+        let mut offset = 0;
+        loop {
+            if data.is_empty() {
+                return Err(Group::unterminated(field_id, offset));
+            }
+
+            let start = offset;
+            let (field, remainder) =
+                FieldRef::parse(data, options, depth).map_err(|e| e.shift(start))?;
+            offset += data.len() - remainder.len();
+            data = remainder;
+            match field.get_data() {
+                MessageObjectRef::EGroup if field.get_field_id() == field_id => {
+                    return Ok((
+                        Self {
+                            end_field_id: field.tag,
+                            fields,
+                        },
+                        data,
+                    ));
+                }
+                MessageObjectRef::EGroup if !options.lenient_end_groups => {
+                    return Err(Group::mismatched(field_id, field.get_field_id(), start));
+                }
+                _ => {
+                    Group::check_field_count(fields.len(), options, start)?;
+                    fields.push(field);
+                }
+            }
+        }
+    }
+
+    pub fn get_fields(&self) -> &[FieldRef<'a>] {
+        &self.fields
+    }
+
+    pub fn take_fields(self) -> Vec<FieldRef<'a>> {
+        self.fields
+    }
+
+    pub fn into_owned(self) -> Group {
+        Group {
+            end_field_id: self.end_field_id.into_owned(),
+            fields: self.fields.into_iter().map(FieldRef::into_owned).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MessageObjectRef<'a> {
+    Varint(VarintRef<'a>),
+    I64(I64Ref<'a>),
+    Len(LenRef<'a>),
+    Group(GroupRef<'a>),
+    EGroup,
+    I32(I32Ref<'a>),
+}
+
+impl<'a> MessageObjectRef<'a> {
+    pub fn wire_type(&self) -> u64 {
+        match self {
+            MessageObjectRef::Varint(_) => 0,
+            MessageObjectRef::I64(_) => 1,
+            MessageObjectRef::Len(_) => 2,
+            MessageObjectRef::Group(_) => 3,
+            MessageObjectRef::EGroup => 4,
+            MessageObjectRef::I32(_) => 5,
+        }
+    }
+
+    pub fn into_owned(self) -> MessageObject {
+        match self {
+            MessageObjectRef::Varint(value) => MessageObject::Varint(value.into_owned()),
+            MessageObjectRef::I64(value) => MessageObject::I64(value.into_owned()),
+            MessageObjectRef::Len(value) => MessageObject::Len(value.into_owned()),
+            MessageObjectRef::Group(value) => MessageObject::Group(value.into_owned()),
+            MessageObjectRef::EGroup => MessageObject::EGroup,
+            MessageObjectRef::I32(value) => MessageObject::I32(value.into_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldRef<'a> {
+    pub(crate) tag: VarintRef<'a>,
+    pub(crate) data: MessageObjectRef<'a>,
+    /// the complete encoding of the field, tag included
+    pub(crate) raw: &'a [u8],
+}
+
+impl<'a> FieldRef<'a> {
+    pub fn from(data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        Self::from_with_options(data, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        data: &'a [u8],
+        options: &DecodeOptions,
+    ) -> Result<(Self, &'a [u8]), DecodeError> {
+        Self::parse(data, options, 0)
+    }
+
+    /// mirrors `Field::parse`, see there for the meaning of `depth`
+    pub(crate) fn parse(
+        data: &'a [u8],
+        options: &DecodeOptions,
+        depth: usize,
+    ) -> Result<(Self, &'a [u8]), DecodeError> {
+        let (tag, remainder) = VarintRef::from_with_options(data, options)?;
+        let tag_value = tag.get();
+        let field_id = Field::field_id_from_tag(tag_value);
+        Field::check_tag(tag_value, options, depth)?;
+
+        // errors from the value parsers are relative to the end of the tag
+        let value_offset = tag.0.len();
+        let (value, remainder) = match Field::wire_type_from_tag(tag_value) {
+            0 => VarintRef::from_with_options(remainder, options)
+                .map(|(value, remainder)| (MessageObjectRef::Varint(value), remainder)),
+            1 => I64Ref::from(remainder)
+                .map(|(value, remainder)| (MessageObjectRef::I64(value), remainder)),
+            2 => LenRef::from_with_options(remainder, options)
+                .map(|(value, remainder)| (MessageObjectRef::Len(value), remainder)),
+            3 => GroupRef::parse(field_id, remainder, options, depth + 1)
+                .map(|(value, remainder)| (MessageObjectRef::Group(value), remainder)),
+            4 => Ok((MessageObjectRef::EGroup, remainder)),
+            5 => I32Ref::from(remainder)
+                .map(|(value, remainder)| (MessageObjectRef::I32(value), remainder)),
+            _ => unreachable!("wire type was validated by check_tag"),
+        }
+        .map_err(|e| e.shift(value_offset).within(field_id))?;
+
+        let raw = &data[..data.len() - remainder.len()];
+        Ok((
+            Self {
+                tag,
+                data: value,
+                raw,
+            },
+            remainder,
+        ))
+    }
+
+    pub fn into_varint(self) -> Option<VarintRef<'a>> {
+        match self.data {
+            MessageObjectRef::Varint(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_varint(&self) -> Option<&VarintRef<'a>> {
+        match self.data {
+            MessageObjectRef::Varint(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn into_i64(self) -> Option<I64Ref<'a>> {
+        match self.data {
+            MessageObjectRef::I64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<&I64Ref<'a>> {
+        match self.data {
+            MessageObjectRef::I64(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn into_len(self) -> Option<LenRef<'a>> {
+        match self.data {
+            MessageObjectRef::Len(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_len(&self) -> Option<&LenRef<'a>> {
+        match self.data {
+            MessageObjectRef::Len(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn into_group(self) -> Option<GroupRef<'a>> {
+        match self.data {
+            MessageObjectRef::Group(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_group(&self) -> Option<&GroupRef<'a>> {
+        match self.data {
+            MessageObjectRef::Group(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn into_i32(self) -> Option<I32Ref<'a>> {
+        match self.data {
+            MessageObjectRef::I32(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<&I32Ref<'a>> {
+        match self.data {
+            MessageObjectRef::I32(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_field_id(&self) -> u64 {
+        Field::field_id_from_tag(self.tag.get())
+    }
+
+    pub fn get_wire_type(&self) -> u64 {
+        Field::wire_type_from_tag(self.tag.get())
+    }
+
+    pub fn get_data(&self) -> &MessageObjectRef<'a> {
+        &self.data
+    }
+
+    /// the complete encoding of the field, tag included
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// the fields of a group, between its start and end tags, or `None` for other wire
+    /// types
+    pub(crate) fn group_payload(&self) -> Option<&'a [u8]> {
+        let MessageObjectRef::Group(group) = &self.data else {
+            return None;
+        };
+        Some(&self.raw[self.tag.0.len()..self.raw.len() - group.end_field_id.0.len()])
+    }
+
+    pub fn into_owned(self) -> Field {
+        Field {
+            tag: self.tag.into_owned(),
+            data: self.data.into_owned(),
+            span: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MessageRef<'a>(pub(crate) &'a [u8]);
+
+impl<'a> MessageRef<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self(data)
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn into_try_iter(self) -> MessageRefTryIter<'a> {
+        self.into_try_iter_with_options(&DecodeOptions::default())
+    }
+
+    pub fn into_try_iter_with_options(self, options: &DecodeOptions) -> MessageRefTryIter<'a> {
+        MessageRefTryIter {
+            data: self.0,
+            options: *options,
+            offset: 0,
+            fields: 0,
+            failed: false,
+        }
+    }
+
+    /// the last occurrence of `field_id`, following protobuf's last-one-wins semantics
+    ///
+    /// this has to scan the whole message, so any decode error is reported even if it
+    /// comes after the last occurrence
+    pub fn get(&self, field_id: u64) -> Result<Option<FieldRef<'a>>, DecodeError> {
+        let mut last = None;
+        for field in self.get_all(field_id) {
+            last = Some(field?);
+        }
+        Ok(last)
+    }
+
+    /// the first occurrence of `field_id`, which stops scanning as soon as it is found
+    pub fn first(&self, field_id: u64) -> Result<Option<FieldRef<'a>>, DecodeError> {
+        self.get_all(field_id).next().transpose()
+    }
+
+    /// every occurrence of `field_id`, in the order they appear in the message
+    pub fn get_all(&self, field_id: u64) -> FieldIdIter<'a> {
+        FieldIdIter {
+            inner: self.into_try_iter(),
+            field_id,
+        }
+    }
+
+    pub fn contains(&self, field_id: u64) -> Result<bool, DecodeError> {
+        Ok(self.first(field_id)?.is_some())
+    }
+
+    pub fn into_owned(self) -> Message {
+        Message(copy_wire_data(self.0))
+    }
+}
+
+/// Iterator over the occurrences of one field id in a message, see `MessageRef::get_all`
+pub struct FieldIdIter<'a> {
+    inner: MessageRefTryIter<'a>,
+    field_id: u64,
+}
+
+impl<'a> Iterator for FieldIdIter<'a> {
+    type Item = Result<FieldRef<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for field in self.inner.by_ref() {
+            match field {
+                Ok(field) if field.get_field_id() != self.field_id => continue,
+                field => return Some(field),
+            }
+        }
+        None
+    }
+}
+
+pub struct MessageRefIter<'a>(MessageRefTryIter<'a>);
+
+impl<'a> IntoIterator for MessageRef<'a> {
+    type IntoIter = MessageRefIter<'a>;
+    type Item = <Self::IntoIter as Iterator>::Item;
+
+    fn into_iter(self) -> Self::IntoIter {
+        MessageRefIter(self.into_try_iter())
+    }
+}
+
+impl<'a> Iterator for MessageRefIter<'a> {
+    type Item = FieldRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // parse the field or return None, the error is only visible through MessageRefTryIter
+        self.0.next()?.ok()
+    }
+}
+
+/// Iterator over the fields of a `MessageRef` which reports decode errors
+///
+/// Like `MessageTryIter`, iteration stops after the first error and `into_remainder`
+/// returns the data starting at the field that failed to parse.
+pub struct MessageRefTryIter<'a> {
+    data: &'a [u8],
+    options: DecodeOptions,
+    offset: usize,
+    fields: usize,
+    failed: bool,
+}
+
+impl<'a> MessageRefTryIter<'a> {
+    /// byte offset of the next field to be parsed, relative to the start of the message
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> Iterator for MessageRefTryIter<'a> {
+    type Item = Result<FieldRef<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
+
+        let result =
+            Message::check_limits(self.data.len(), self.offset, self.fields, &self.options)
+                .and_then(|_| FieldRef::from_with_options(self.data, &self.options));
+        match result {
+            Ok((field, remainder)) => {
+                self.offset += self.data.len() - remainder.len();
+                self.fields += 1;
+                self.data = remainder;
+                Some(Ok(field))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.shift(self.offset)))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PackedRepeatedVarintRef<'a>(pub(crate) &'a [u8]);
+
+impl<'a> PackedRepeatedVarintRef<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self(data)
+    }
+
+    pub fn into_try_iter(self) -> PackedRepeatedVarintRefTryIter<'a> {
+        self.into_try_iter_with_options(&DecodeOptions::default())
+    }
+
+    pub fn into_try_iter_with_options(
+        self,
+        options: &DecodeOptions,
+    ) -> PackedRepeatedVarintRefTryIter<'a> {
+        PackedRepeatedVarintRefTryIter {
+            data: self.0,
+            options: *options,
+            offset: 0,
+            failed: false,
+        }
+    }
+
+    pub fn into_owned(self) -> PackedRepeatedVarint {
+        PackedRepeatedVarint(copy_wire_data(self.0))
+    }
+}
+
+impl<'a> IntoIterator for PackedRepeatedVarintRef<'a> {
+    type IntoIter = PackedRepeatedVarintRefIter<'a>;
+    type Item = <Self::IntoIter as Iterator>::Item;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PackedRepeatedVarintRefIter(self.into_try_iter())
+    }
+}
+
+pub struct PackedRepeatedVarintRefIter<'a>(PackedRepeatedVarintRefTryIter<'a>);
+
+impl<'a> Iterator for PackedRepeatedVarintRefIter<'a> {
+    type Item = VarintRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // parse the value or return None, the error is only visible through the TryIter
+        self.0.next()?.ok()
+    }
+}
+
+/// Iterator over packed `VarintRef` values which reports decode errors, stopping after the first
+pub struct PackedRepeatedVarintRefTryIter<'a> {
+    data: &'a [u8],
+    options: DecodeOptions,
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> PackedRepeatedVarintRefTryIter<'a> {
+    /// byte offset of the next value to be parsed, relative to the start of the packed data
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> Iterator for PackedRepeatedVarintRefTryIter<'a> {
+    type Item = Result<VarintRef<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
+
+        match VarintRef::from_with_options(self.data, &self.options) {
+            Ok((value, remainder)) => {
+                self.offset += self.data.len() - remainder.len();
+                self.data = remainder;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.shift(self.offset)))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PackedRepeatedI64Ref<'a>(pub(crate) &'a [u8]);
+
+impl<'a> PackedRepeatedI64Ref<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self(data)
+    }
+
+    pub fn into_try_iter(self) -> PackedRepeatedI64RefTryIter<'a> {
+        PackedRepeatedI64RefTryIter {
+            data: self.0,
+            offset: 0,
+            failed: false,
+        }
+    }
+
+    pub fn into_owned(self) -> PackedRepeatedI64 {
+        PackedRepeatedI64(copy_wire_data(self.0))
+    }
+}
+
+impl<'a> IntoIterator for PackedRepeatedI64Ref<'a> {
+    type IntoIter = PackedRepeatedI64RefIter<'a>;
+    type Item = <Self::IntoIter as Iterator>::Item;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PackedRepeatedI64RefIter(self.into_try_iter())
+    }
+}
+
+pub struct PackedRepeatedI64RefIter<'a>(PackedRepeatedI64RefTryIter<'a>);
+
+impl<'a> Iterator for PackedRepeatedI64RefIter<'a> {
+    type Item = I64Ref<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // parse the value or return None, the error is only visible through the TryIter
+        self.0.next()?.ok()
+    }
+}
+
+/// Iterator over packed `I64Ref` values which reports decode errors, stopping after the first
+pub struct PackedRepeatedI64RefTryIter<'a> {
+    data: &'a [u8],
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> PackedRepeatedI64RefTryIter<'a> {
+    /// byte offset of the next value to be parsed, relative to the start of the packed data
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> Iterator for PackedRepeatedI64RefTryIter<'a> {
+    type Item = Result<I64Ref<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
+
+        match I64Ref::from(self.data) {
+            Ok((value, remainder)) => {
+                self.offset += self.data.len() - remainder.len();
+                self.data = remainder;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.shift(self.offset)))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PackedRepeatedI32Ref<'a>(pub(crate) &'a [u8]);
+
+impl<'a> PackedRepeatedI32Ref<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self(data)
+    }
+
+    pub fn into_try_iter(self) -> PackedRepeatedI32RefTryIter<'a> {
+        PackedRepeatedI32RefTryIter {
+            data: self.0,
+            offset: 0,
+            failed: false,
+        }
+    }
+
+    pub fn into_owned(self) -> PackedRepeatedI32 {
+        PackedRepeatedI32(copy_wire_data(self.0))
+    }
+}
+
+impl<'a> IntoIterator for PackedRepeatedI32Ref<'a> {
+    type IntoIter = PackedRepeatedI32RefIter<'a>;
+    type Item = <Self::IntoIter as Iterator>::Item;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PackedRepeatedI32RefIter(self.into_try_iter())
+    }
+}
+
+pub struct PackedRepeatedI32RefIter<'a>(PackedRepeatedI32RefTryIter<'a>);
+
+impl<'a> Iterator for PackedRepeatedI32RefIter<'a> {
+    type Item = I32Ref<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // parse the value or return None, the error is only visible through the TryIter
+        self.0.next()?.ok()
+    }
+}
+
+/// Iterator over packed `I32Ref` values which reports decode errors, stopping after the first
+pub struct PackedRepeatedI32RefTryIter<'a> {
+    data: &'a [u8],
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> PackedRepeatedI32RefTryIter<'a> {
+    /// byte offset of the next value to be parsed, relative to the start of the packed data
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> Iterator for PackedRepeatedI32RefTryIter<'a> {
+    type Item = Result<I32Ref<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
+
+        match I32Ref::from(self.data) {
+            Ok((value, remainder)) => {
+                self.offset += self.data.len() - remainder.len();
+                self.data = remainder;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.shift(self.offset)))
+            }
+        }
+    }
+}
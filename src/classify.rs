@@ -0,0 +1,292 @@
+use alloc::vec::Vec;
+
+use crate::borrowed::{
+    FieldRef, LenRef, MessageObjectRef, MessageRef, PackedRepeatedI32Ref, PackedRepeatedI64Ref,
+    PackedRepeatedVarintRef,
+};
+use crate::decode_options::DecodeOptions;
+use crate::message::Message;
+
+/// How sure the classifier is about the interpretation it picked for a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Confidence {
+    /// a fallback, or a guess which many unrelated payloads would also satisfy
+    Low,
+    /// plausible, but other interpretations remain likely
+    Medium,
+    /// unambiguous, or very unlikely to be a coincidence
+    High,
+}
+
+/// A message decoded without a schema, every `Len` payload classified by a heuristic
+#[derive(Debug, Clone)]
+pub struct RawMessage<'a> {
+    pub(crate) fields: Vec<RawField<'a>>,
+    remainder: &'a [u8],
+}
+
+/// One field of a `RawMessage`, with the interpretation of its value
+#[derive(Debug, Clone)]
+pub struct RawField<'a> {
+    pub(crate) field: FieldRef<'a>,
+    pub(crate) value: RawValue<'a>,
+    confidence: Confidence,
+}
+
+/// The interpretation picked for the value of a field
+#[derive(Debug, Clone)]
+pub enum RawValue<'a> {
+    Varint(u64),
+    I64(i64),
+    I32(i32),
+    Group(RawMessage<'a>),
+    EGroup,
+    Message(RawMessage<'a>),
+    PackedVarint(Vec<u64>),
+    PackedI64(Vec<i64>),
+    PackedI32(Vec<i32>),
+    String(&'a str),
+    Bytes(&'a [u8]),
+}
+
+/// Field ids beyond this are rare enough in real schemas to cast doubt on a nested message
+const PLAUSIBLE_FIELD_ID: u64 = 10_000;
+
+impl<'a> RawMessage<'a> {
+    pub fn fields(&self) -> &[RawField<'a>] {
+        &self.fields
+    }
+
+    /// the data from the first field which failed to decode onwards, empty if the whole
+    /// message decoded. Only the outermost message can have a remainder, as a nested
+    /// payload is only classified as a message when it decodes completely
+    pub fn remainder(&self) -> &'a [u8] {
+        self.remainder
+    }
+
+    /// decodes `data` as far as it can, classifying payloads up to `depth` messages deep
+    pub(crate) fn decode(data: &'a [u8], depth: usize) -> Self {
+        let options = DecodeOptions {
+            lenient_end_groups: true,
+            ..DecodeOptions::new()
+        };
+        let mut iter = MessageRef(data).into_try_iter_with_options(&options);
+        let mut fields = Vec::new();
+        loop {
+            let offset = iter.offset();
+            match iter.next() {
+                Some(Ok(field)) => fields.push(field),
+                Some(Err(_)) => return Self::from_fields(fields, &data[offset..], depth),
+                None => return Self::from_fields(fields, &[], depth),
+            }
+        }
+    }
+
+    fn from_fields(fields: Vec<FieldRef<'a>>, remainder: &'a [u8], depth: usize) -> Self {
+        Self {
+            fields: fields
+                .into_iter()
+                .map(|field| RawField::classify(field, depth))
+                .collect(),
+            remainder,
+        }
+    }
+}
+
+impl<'a> RawField<'a> {
+    pub fn field(&self) -> &FieldRef<'a> {
+        &self.field
+    }
+
+    pub fn get_field_id(&self) -> u64 {
+        self.field.get_field_id()
+    }
+
+    pub fn value(&self) -> &RawValue<'a> {
+        &self.value
+    }
+
+    pub fn confidence(&self) -> Confidence {
+        self.confidence
+    }
+
+    pub(crate) fn classify(field: FieldRef<'a>, depth: usize) -> Self {
+        let (value, confidence) = match &field.data {
+            MessageObjectRef::Varint(value) => (RawValue::Varint(value.get()), Confidence::High),
+            MessageObjectRef::I64(value) => (RawValue::I64(value.get()), Confidence::High),
+            MessageObjectRef::I32(value) => (RawValue::I32(value.get()), Confidence::High),
+            MessageObjectRef::Len(len) => classify(len.inner, depth),
+            MessageObjectRef::Group(group) => (
+                // groups are delimited by tags, so there is nothing to guess
+                RawValue::Group(RawMessage::from_fields(
+                    group.get_fields().to_vec(),
+                    &[],
+                    depth.saturating_sub(1),
+                )),
+                Confidence::High,
+            ),
+            MessageObjectRef::EGroup => (RawValue::EGroup, Confidence::High),
+        };
+
+        Self {
+            field,
+            value,
+            confidence,
+        }
+    }
+}
+
+impl Message {
+    /// decodes the message without a schema, like `protoc --decode_raw`, see
+    /// `MessageRef::decode_raw`
+    pub fn decode_raw(&self) -> RawMessage<'_> {
+        self.view().decode_raw()
+    }
+}
+
+impl<'a> MessageRef<'a> {
+    /// decodes the message without a schema, like `protoc --decode_raw`
+    ///
+    /// Every `Len` payload is classified by `LenRef::classify`, recursively. Decoding
+    /// stops at the first field which can't be parsed, leaving the rest of the data in
+    /// `RawMessage::remainder`.
+    pub fn decode_raw(&self) -> RawMessage<'a> {
+        RawMessage::decode(self.0, DecodeOptions::new().max_depth)
+    }
+}
+
+impl<'a> LenRef<'a> {
+    /// guesses what the payload holds, trying in order:
+    ///
+    /// - a message, if it decodes completely with strict validation and isn't printable
+    ///   text, with high confidence when every field id is small
+    /// - packed fixed width values if every value reads as a plausible float, then packed
+    ///   varints if every value is canonically encoded and fits in 32 bits, then packed
+    ///   fixed width values if every value is a small integer
+    /// - a string, if it is printable UTF-8
+    /// - bytes otherwise
+    ///
+    /// Payloads which are printable text are never classified as packed values, as most
+    /// text would also decode as varints.
+    pub fn classify(&self) -> (RawValue<'a>, Confidence) {
+        classify(self.inner, DecodeOptions::new().max_depth)
+    }
+}
+
+/// see `LenRef::classify`, `depth` being how many more messages may be nested; at 0 the
+/// payload is only told apart as text or bytes
+pub(crate) fn classify(payload: &[u8], depth: usize) -> (RawValue<'_>, Confidence) {
+    if payload.is_empty() {
+        return (RawValue::String(""), Confidence::Low);
+    }
+
+    // text which happens to decode as a message or as varints is still most likely text
+    let text = is_text(payload);
+    if depth > 0 && !text {
+        if let Some(fields) = message_fields(payload) {
+            let confidence = match fields
+                .iter()
+                .all(|field| field.get_field_id() <= PLAUSIBLE_FIELD_ID)
+            {
+                true => Confidence::High,
+                false => Confidence::Medium,
+            };
+            let message = RawMessage::from_fields(fields, &[], depth - 1);
+            return (RawValue::Message(message), confidence);
+        }
+        if let Some(packed) = packed(payload) {
+            return packed;
+        }
+    }
+
+    match core::str::from_utf8(payload) {
+        Ok(s) if text => (RawValue::String(s), Confidence::High),
+        _ => (RawValue::Bytes(payload), Confidence::Low),
+    }
+}
+
+/// the fields of `payload` if it decodes completely as a message with strict validation
+fn message_fields(payload: &[u8]) -> Option<Vec<FieldRef<'_>>> {
+    MessageRef(payload)
+        .into_try_iter_with_options(&DecodeOptions::strict())
+        .collect::<Result<_, _>>()
+        .ok()
+}
+
+/// the payload as packed values, if every one of them looks plausible
+///
+/// Fixed width values which all read as floats are preferred over varints, as the zero
+/// bytes common in floats are valid varints too. Integers are assumed to be varints before
+/// falling back to small fixed width integers.
+fn packed(payload: &[u8]) -> Option<(RawValue<'_>, Confidence)> {
+    // a single value is a much weaker signal than several agreeing ones
+    let confidence = |count: usize| match count {
+        1 => Confidence::Low,
+        _ => Confidence::Medium,
+    };
+    let fixed64 = || -> Vec<i64> {
+        match payload.len().is_multiple_of(8) {
+            true => PackedRepeatedI64Ref(payload)
+                .into_iter()
+                .map(|v| v.get())
+                .collect(),
+            false => Vec::new(),
+        }
+    };
+    let fixed32 = || -> Vec<i32> {
+        match payload.len().is_multiple_of(4) {
+            true => PackedRepeatedI32Ref(payload)
+                .into_iter()
+                .map(|v| v.get())
+                .collect(),
+            false => Vec::new(),
+        }
+    };
+    let double = |value: &i64| nice_float(f64::from_bits(*value as u64));
+    let float = |value: &i32| nice_float(f32::from_bits(*value as u32) as f64);
+
+    let values = fixed64();
+    if !values.is_empty() && values.iter().all(double) {
+        let confidence = confidence(values.len());
+        return Some((RawValue::PackedI64(values), confidence));
+    }
+    let values = fixed32();
+    if !values.is_empty() && values.iter().all(float) {
+        let confidence = confidence(values.len());
+        return Some((RawValue::PackedI32(values), confidence));
+    }
+
+    let varints = PackedRepeatedVarintRef(payload)
+        .into_try_iter_with_options(&DecodeOptions::strict())
+        .map(|value| value.ok().map(|value| value.get()))
+        .collect::<Option<Vec<u64>>>()
+        .filter(|values| values.iter().all(|value| *value <= u32::MAX as u64));
+    if let Some(values) = varints {
+        let confidence = confidence(values.len());
+        return Some((RawValue::PackedVarint(values), confidence));
+    }
+
+    let values = fixed64();
+    if !values.is_empty() && values.iter().all(|value| value.unsigned_abs() < 1 << 32) {
+        return Some((RawValue::PackedI64(values), Confidence::Low));
+    }
+    let values = fixed32();
+    if !values.is_empty() && values.iter().all(|value| value.unsigned_abs() < 1 << 16) {
+        return Some((RawValue::PackedI32(values), Confidence::Low));
+    }
+
+    None
+}
+
+/// whether the payload is valid UTF-8 without control characters, besides whitespace
+pub(crate) fn is_text(payload: &[u8]) -> bool {
+    core::str::from_utf8(payload).is_ok_and(|text| {
+        text.chars()
+            .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+    })
+}
+
+/// whether a fixed width value reads as a plausible float rather than an integer
+pub(crate) fn nice_float(value: f64) -> bool {
+    value.is_finite() && (1e-9..1e15).contains(&value.abs())
+}
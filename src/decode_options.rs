@@ -0,0 +1,72 @@
+/// Limits applied while decoding, to bound the resources used by untrusted input
+///
+/// The defaults match the limits applied by the reference protobuf implementation, and
+/// every parser without a `_with_options` suffix decodes with `DecodeOptions::default()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// how many groups (or nested messages, for APIs that descend into them) may be
+    /// nested inside each other
+    pub max_depth: usize,
+    /// the largest message, in bytes, that a `Message` iterator will accept
+    pub max_message_size: usize,
+    /// the most fields a single message or group may contain
+    pub max_fields: usize,
+    /// the largest payload a single `Len` may declare
+    pub max_len_size: usize,
+    /// reject anything without exactly one canonical encoding: overlong or overflowing
+    /// varints, and field ids which are 0, above 2^29 - 1 or in the reserved range. When
+    /// disabled those are accepted and kept byte-for-byte
+    pub strict: bool,
+    /// accept end-group tags which don't close the enclosing group, keeping them as
+    /// `MessageObject::EGroup` fields instead of failing with `MismatchedEndGroup` or
+    /// `UnexpectedEndGroup`
+    pub lenient_end_groups: bool,
+}
+
+impl core::default::Default for DecodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecodeOptions {
+    pub const fn new() -> Self {
+        Self {
+            max_depth: 100,
+            max_message_size: i32::MAX as usize,
+            max_fields: usize::MAX,
+            max_len_size: i32::MAX as usize,
+            strict: false,
+            lenient_end_groups: false,
+        }
+    }
+
+    /// options with every limit disabled, for trusted input
+    pub const fn unlimited() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_message_size: usize::MAX,
+            max_fields: usize::MAX,
+            max_len_size: usize::MAX,
+            strict: false,
+            lenient_end_groups: false,
+        }
+    }
+
+    /// default limits with strict validation enabled
+    pub const fn strict() -> Self {
+        Self {
+            strict: true,
+            ..Self::new()
+        }
+    }
+
+    /// options for re-parsing data which has already been validated, or which was
+    /// produced by this crate, where no check should be able to fail
+    pub(crate) const fn trusted() -> Self {
+        Self {
+            lenient_end_groups: true,
+            ..Self::unlimited()
+        }
+    }
+}
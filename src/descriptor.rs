@@ -0,0 +1,767 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::descriptor_pool::DescriptorPool;
+
+/// The syntax a `.proto` file was written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Syntax {
+    Proto2,
+    Proto3,
+    Editions,
+}
+
+/// The cardinality of a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Label {
+    Optional,
+    Required,
+    Repeated,
+}
+
+/// The type of a field, with the definition of message and enum types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind<'a> {
+    Double,
+    Float,
+    Int64,
+    Uint64,
+    Int32,
+    Fixed64,
+    Fixed32,
+    Bool,
+    String,
+    Group(MessageDescriptor<'a>),
+    Message(MessageDescriptor<'a>),
+    Bytes,
+    Uint32,
+    Enum(EnumDescriptor<'a>),
+    Sfixed32,
+    Sfixed64,
+    Sint32,
+    Sint64,
+}
+
+impl Kind<'_> {
+    /// the wire type of a single, unpacked value of this kind
+    pub fn wire_type(&self) -> u64 {
+        match self {
+            Self::Double | Self::Fixed64 | Self::Sfixed64 => 1,
+            Self::Float | Self::Fixed32 | Self::Sfixed32 => 5,
+            Self::String | Self::Bytes | Self::Message(_) => 2,
+            Self::Group(_) => 3,
+            _ => 0,
+        }
+    }
+
+    /// whether repeated values of this kind may be packed into a single `Len`
+    pub fn is_packable(&self) -> bool {
+        !matches!(
+            self,
+            Self::String | Self::Bytes | Self::Message(_) | Self::Group(_)
+        )
+    }
+}
+
+/// The field type before message and enum names are resolved, as stored in the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KindData {
+    Double,
+    Float,
+    Int64,
+    Uint64,
+    Int32,
+    Fixed64,
+    Fixed32,
+    Bool,
+    String,
+    Group(usize),
+    Message(usize),
+    Bytes,
+    Uint32,
+    Enum(usize),
+    Sfixed32,
+    Sfixed64,
+    Sint32,
+    Sint64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FileData {
+    pub(crate) name: String,
+    pub(crate) package: String,
+    pub(crate) dependencies: Vec<usize>,
+    pub(crate) syntax: Syntax,
+    pub(crate) messages: Vec<usize>,
+    pub(crate) enums: Vec<usize>,
+    pub(crate) services: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MessageData {
+    pub(crate) full_name: String,
+    pub(crate) file: usize,
+    pub(crate) parent: Option<usize>,
+    pub(crate) fields: Vec<FieldData>,
+    pub(crate) oneofs: Vec<OneofData>,
+    pub(crate) messages: Vec<usize>,
+    pub(crate) enums: Vec<usize>,
+    pub(crate) map_entry: bool,
+    /// extensions of this message, declared in any file of the pool
+    pub(crate) extensions: Vec<FieldData>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FieldData {
+    pub(crate) name: String,
+    pub(crate) full_name: String,
+    pub(crate) json_name: String,
+    pub(crate) number: u32,
+    pub(crate) label: Label,
+    pub(crate) kind: KindData,
+    pub(crate) oneof: Option<usize>,
+    pub(crate) packed: Option<bool>,
+    pub(crate) proto3_optional: bool,
+    pub(crate) default_value: Option<String>,
+    /// for an extension, the file declaring it
+    pub(crate) extension: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct OneofData {
+    pub(crate) name: String,
+    pub(crate) fields: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct EnumData {
+    pub(crate) full_name: String,
+    pub(crate) file: usize,
+    pub(crate) parent: Option<usize>,
+    pub(crate) values: Vec<EnumValueData>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct EnumValueData {
+    pub(crate) name: String,
+    pub(crate) number: i32,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ServiceData {
+    pub(crate) full_name: String,
+    pub(crate) file: usize,
+    pub(crate) methods: Vec<MethodData>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MethodData {
+    pub(crate) name: String,
+    pub(crate) input: usize,
+    pub(crate) output: usize,
+    pub(crate) client_streaming: bool,
+    pub(crate) server_streaming: bool,
+}
+
+/// Implements `Debug` with the name of the definition, and equality as being the same
+/// definition of the same pool
+macro_rules! handle {
+    ($name:ident, $describe:ident) => {
+        impl core::fmt::Debug for $name<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, "{}({})", stringify!($name), self.$describe())
+            }
+        }
+
+        impl PartialEq for $name<'_> {
+            fn eq(&self, other: &Self) -> bool {
+                core::ptr::eq(self.pool(), other.pool()) && self.key() == other.key()
+            }
+        }
+
+        impl Eq for $name<'_> {}
+    };
+}
+
+/// A `.proto` file in a `DescriptorPool`
+#[derive(Clone, Copy)]
+pub struct FileDescriptor<'a> {
+    pub(crate) pool: &'a DescriptorPool,
+    pub(crate) index: usize,
+}
+
+/// A message type in a `DescriptorPool`
+#[derive(Clone, Copy)]
+pub struct MessageDescriptor<'a> {
+    pub(crate) pool: &'a DescriptorPool,
+    pub(crate) index: usize,
+}
+
+/// A field of a message type, or an extension of it
+#[derive(Clone, Copy)]
+pub struct FieldDescriptor<'a> {
+    pub(crate) message: MessageDescriptor<'a>,
+    pub(crate) index: usize,
+}
+
+/// A oneof of a message type
+#[derive(Clone, Copy)]
+pub struct OneofDescriptor<'a> {
+    pub(crate) message: MessageDescriptor<'a>,
+    pub(crate) index: usize,
+}
+
+/// An enum type in a `DescriptorPool`
+#[derive(Clone, Copy)]
+pub struct EnumDescriptor<'a> {
+    pub(crate) pool: &'a DescriptorPool,
+    pub(crate) index: usize,
+}
+
+/// A value of an enum type
+#[derive(Clone, Copy)]
+pub struct EnumValueDescriptor<'a> {
+    pub(crate) parent: EnumDescriptor<'a>,
+    pub(crate) index: usize,
+}
+
+/// A service in a `DescriptorPool`
+#[derive(Clone, Copy)]
+pub struct ServiceDescriptor<'a> {
+    pub(crate) pool: &'a DescriptorPool,
+    pub(crate) index: usize,
+}
+
+/// A method of a service
+#[derive(Clone, Copy)]
+pub struct MethodDescriptor<'a> {
+    pub(crate) service: ServiceDescriptor<'a>,
+    pub(crate) index: usize,
+}
+
+handle!(FileDescriptor, name);
+handle!(MessageDescriptor, full_name);
+handle!(FieldDescriptor, name);
+handle!(OneofDescriptor, name);
+handle!(EnumDescriptor, full_name);
+handle!(EnumValueDescriptor, name);
+handle!(ServiceDescriptor, full_name);
+handle!(MethodDescriptor, name);
+
+/// the last component of a fully qualified name
+fn short_name(full_name: &str) -> &str {
+    full_name.rsplit('.').next().unwrap_or(full_name)
+}
+
+impl<'a> FileDescriptor<'a> {
+    fn data(&self) -> &'a FileData {
+        &self.pool.files[self.index]
+    }
+
+    fn pool(&self) -> &'a DescriptorPool {
+        self.pool
+    }
+
+    fn key(&self) -> usize {
+        self.index
+    }
+
+    /// the path of the file, e.g. `google/protobuf/descriptor.proto`
+    pub fn name(&self) -> &'a str {
+        &self.data().name
+    }
+
+    pub fn package(&self) -> &'a str {
+        &self.data().package
+    }
+
+    pub fn syntax(&self) -> Syntax {
+        self.data().syntax
+    }
+
+    /// the files imported by this one
+    pub fn dependencies(&self) -> impl ExactSizeIterator<Item = FileDescriptor<'a>> + 'a {
+        let pool = self.pool;
+        self.data()
+            .dependencies
+            .iter()
+            .map(move |index| FileDescriptor {
+                pool,
+                index: *index,
+            })
+    }
+
+    /// the top level messages of the file
+    pub fn messages(&self) -> impl ExactSizeIterator<Item = MessageDescriptor<'a>> + 'a {
+        let pool = self.pool;
+        self.data()
+            .messages
+            .iter()
+            .map(move |index| MessageDescriptor {
+                pool,
+                index: *index,
+            })
+    }
+
+    /// the top level enums of the file
+    pub fn enums(&self) -> impl ExactSizeIterator<Item = EnumDescriptor<'a>> + 'a {
+        let pool = self.pool;
+        self.data().enums.iter().map(move |index| EnumDescriptor {
+            pool,
+            index: *index,
+        })
+    }
+
+    pub fn services(&self) -> impl ExactSizeIterator<Item = ServiceDescriptor<'a>> + 'a {
+        let pool = self.pool;
+        self.data()
+            .services
+            .iter()
+            .map(move |index| ServiceDescriptor {
+                pool,
+                index: *index,
+            })
+    }
+}
+
+impl<'a> MessageDescriptor<'a> {
+    fn data(&self) -> &'a MessageData {
+        &self.pool.messages[self.index]
+    }
+
+    fn pool(&self) -> &'a DescriptorPool {
+        self.pool
+    }
+
+    fn key(&self) -> usize {
+        self.index
+    }
+
+    pub fn parent_pool(&self) -> &'a DescriptorPool {
+        self.pool
+    }
+
+    /// the fully qualified name, e.g. `google.protobuf.DescriptorProto.ExtensionRange`
+    pub fn full_name(&self) -> &'a str {
+        &self.data().full_name
+    }
+
+    pub fn name(&self) -> &'a str {
+        short_name(self.full_name())
+    }
+
+    pub fn file(&self) -> FileDescriptor<'a> {
+        FileDescriptor {
+            pool: self.pool,
+            index: self.data().file,
+        }
+    }
+
+    /// the message this one is nested in, if any
+    pub fn parent_message(&self) -> Option<MessageDescriptor<'a>> {
+        self.data().parent.map(|index| MessageDescriptor {
+            pool: self.pool,
+            index,
+        })
+    }
+
+    /// whether this is the synthesized entry type of a map field
+    pub fn is_map_entry(&self) -> bool {
+        self.data().map_entry
+    }
+
+    /// the fields in the order they were declared
+    pub fn fields(&self) -> impl ExactSizeIterator<Item = FieldDescriptor<'a>> + 'a {
+        let message = *self;
+        (0..self.data().fields.len()).map(move |index| FieldDescriptor { message, index })
+    }
+
+    pub fn get_field(&self, number: u32) -> Option<FieldDescriptor<'a>> {
+        self.fields().find(|field| field.number() == number)
+    }
+
+    pub fn get_field_by_name(&self, name: &str) -> Option<FieldDescriptor<'a>> {
+        self.fields().find(|field| field.name() == name)
+    }
+
+    pub fn get_field_by_json_name(&self, json_name: &str) -> Option<FieldDescriptor<'a>> {
+        self.fields().find(|field| field.json_name() == json_name)
+    }
+
+    /// the extensions of this message declared by any file in the pool
+    pub fn extensions(&self) -> impl ExactSizeIterator<Item = FieldDescriptor<'a>> + 'a {
+        let message = *self;
+        let fields = self.data().fields.len();
+        (0..self.data().extensions.len()).map(move |index| FieldDescriptor {
+            message,
+            index: fields + index,
+        })
+    }
+
+    pub fn get_extension(&self, number: u32) -> Option<FieldDescriptor<'a>> {
+        self.extensions().find(|field| field.number() == number)
+    }
+
+    pub fn oneofs(&self) -> impl ExactSizeIterator<Item = OneofDescriptor<'a>> + 'a {
+        let message = *self;
+        (0..self.data().oneofs.len()).map(move |index| OneofDescriptor { message, index })
+    }
+
+    pub fn nested_messages(&self) -> impl ExactSizeIterator<Item = MessageDescriptor<'a>> + 'a {
+        let pool = self.pool;
+        self.data()
+            .messages
+            .iter()
+            .map(move |index| MessageDescriptor {
+                pool,
+                index: *index,
+            })
+    }
+
+    pub fn nested_enums(&self) -> impl ExactSizeIterator<Item = EnumDescriptor<'a>> + 'a {
+        let pool = self.pool;
+        self.data().enums.iter().map(move |index| EnumDescriptor {
+            pool,
+            index: *index,
+        })
+    }
+}
+
+impl<'a> FieldDescriptor<'a> {
+    /// extensions are indexed after the fields of the message they extend
+    fn data(&self) -> &'a FieldData {
+        let message = self.message.data();
+        match message.fields.get(self.index) {
+            Some(field) => field,
+            None => &message.extensions[self.index - message.fields.len()],
+        }
+    }
+
+    fn pool(&self) -> &'a DescriptorPool {
+        self.message.pool
+    }
+
+    fn key(&self) -> (usize, usize) {
+        (self.message.index, self.index)
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.data().name
+    }
+
+    /// the fully qualified name, e.g. `google.protobuf.FieldDescriptorProto.name`, which for
+    /// an extension is scoped by where it is declared rather than the message it extends
+    pub fn full_name(&self) -> &'a str {
+        &self.data().full_name
+    }
+
+    /// the file declaring the field
+    pub fn file(&self) -> FileDescriptor<'a> {
+        match self.data().extension {
+            Some(index) => FileDescriptor {
+                pool: self.message.pool,
+                index,
+            },
+            None => self.message.file(),
+        }
+    }
+
+    pub fn is_extension(&self) -> bool {
+        self.data().extension.is_some()
+    }
+
+    /// the name of the field in JSON, `lowerCamelCase` unless the schema overrides it
+    pub fn json_name(&self) -> &'a str {
+        &self.data().json_name
+    }
+
+    pub fn number(&self) -> u32 {
+        self.data().number
+    }
+
+    pub fn label(&self) -> Label {
+        self.data().label
+    }
+
+    pub fn kind(&self) -> Kind<'a> {
+        let pool = self.message.pool;
+        let message = |index| MessageDescriptor { pool, index };
+        match self.data().kind {
+            KindData::Double => Kind::Double,
+            KindData::Float => Kind::Float,
+            KindData::Int64 => Kind::Int64,
+            KindData::Uint64 => Kind::Uint64,
+            KindData::Int32 => Kind::Int32,
+            KindData::Fixed64 => Kind::Fixed64,
+            KindData::Fixed32 => Kind::Fixed32,
+            KindData::Bool => Kind::Bool,
+            KindData::String => Kind::String,
+            KindData::Group(index) => Kind::Group(message(index)),
+            KindData::Message(index) => Kind::Message(message(index)),
+            KindData::Bytes => Kind::Bytes,
+            KindData::Uint32 => Kind::Uint32,
+            KindData::Enum(index) => Kind::Enum(EnumDescriptor { pool, index }),
+            KindData::Sfixed32 => Kind::Sfixed32,
+            KindData::Sfixed64 => Kind::Sfixed64,
+            KindData::Sint32 => Kind::Sint32,
+            KindData::Sint64 => Kind::Sint64,
+        }
+    }
+
+    /// the message the field belongs to, which for an extension is the one it extends
+    pub fn containing_message(&self) -> MessageDescriptor<'a> {
+        self.message
+    }
+
+    /// the oneof the field is part of, including the synthetic oneof of a proto3
+    /// `optional` field
+    pub fn containing_oneof(&self) -> Option<OneofDescriptor<'a>> {
+        self.data().oneof.map(|index| OneofDescriptor {
+            message: self.message,
+            index,
+        })
+    }
+
+    /// whether the field is a map, i.e. a repeated field of a map entry type
+    pub fn is_map(&self) -> bool {
+        self.label() == Label::Repeated
+            && matches!(self.kind(), Kind::Message(entry) if entry.is_map_entry())
+    }
+
+    /// whether the field is repeated but not a map
+    pub fn is_list(&self) -> bool {
+        self.label() == Label::Repeated && !self.is_map()
+    }
+
+    /// whether repeated values are encoded packed, by the `packed` option or otherwise by
+    /// the default of the file's syntax
+    pub fn is_packed(&self) -> bool {
+        if self.label() != Label::Repeated || !self.kind().is_packable() {
+            return false;
+        }
+        match self.data().packed {
+            Some(packed) => packed,
+            None => self.file().syntax() != Syntax::Proto2,
+        }
+    }
+
+    /// whether the field was declared `optional` in a proto3 file
+    pub fn is_proto3_optional(&self) -> bool {
+        self.data().proto3_optional
+    }
+
+    /// whether an unset field can be told apart from one set to its default value
+    pub fn has_presence(&self) -> bool {
+        if self.label() == Label::Repeated {
+            return false;
+        }
+        matches!(self.kind(), Kind::Message(_) | Kind::Group(_))
+            || self.data().oneof.is_some()
+            || self.is_extension()
+            || self.file().syntax() == Syntax::Proto2
+    }
+
+    /// the default value as written in the schema, only present for proto2 fields
+    pub fn default_value(&self) -> Option<&'a str> {
+        self.data().default_value.as_deref()
+    }
+}
+
+impl<'a> OneofDescriptor<'a> {
+    fn data(&self) -> &'a OneofData {
+        &self.message.data().oneofs[self.index]
+    }
+
+    fn pool(&self) -> &'a DescriptorPool {
+        self.message.pool
+    }
+
+    fn key(&self) -> (usize, usize) {
+        (self.message.index, self.index)
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.data().name
+    }
+
+    pub fn containing_message(&self) -> MessageDescriptor<'a> {
+        self.message
+    }
+
+    pub fn fields(&self) -> impl ExactSizeIterator<Item = FieldDescriptor<'a>> + 'a {
+        let message = self.message;
+        self.data().fields.iter().map(move |index| FieldDescriptor {
+            message,
+            index: *index,
+        })
+    }
+
+    /// whether the oneof was made up by protoc for a proto3 `optional` field
+    pub fn is_synthetic(&self) -> bool {
+        self.fields().all(|field| field.is_proto3_optional())
+    }
+}
+
+impl<'a> EnumDescriptor<'a> {
+    fn data(&self) -> &'a EnumData {
+        &self.pool.enums[self.index]
+    }
+
+    fn pool(&self) -> &'a DescriptorPool {
+        self.pool
+    }
+
+    fn key(&self) -> usize {
+        self.index
+    }
+
+    pub fn full_name(&self) -> &'a str {
+        &self.data().full_name
+    }
+
+    pub fn name(&self) -> &'a str {
+        short_name(self.full_name())
+    }
+
+    pub fn file(&self) -> FileDescriptor<'a> {
+        FileDescriptor {
+            pool: self.pool,
+            index: self.data().file,
+        }
+    }
+
+    /// the message this enum is nested in, if any
+    pub fn parent_message(&self) -> Option<MessageDescriptor<'a>> {
+        self.data().parent.map(|index| MessageDescriptor {
+            pool: self.pool,
+            index,
+        })
+    }
+
+    pub fn values(&self) -> impl ExactSizeIterator<Item = EnumValueDescriptor<'a>> + 'a {
+        let parent = *self;
+        (0..self.data().values.len()).map(move |index| EnumValueDescriptor { parent, index })
+    }
+
+    /// the first value declared for `number`, as several may share it with `allow_alias`
+    pub fn get_value(&self, number: i32) -> Option<EnumValueDescriptor<'a>> {
+        self.values().find(|value| value.number() == number)
+    }
+
+    pub fn get_value_by_name(&self, name: &str) -> Option<EnumValueDescriptor<'a>> {
+        self.values().find(|value| value.name() == name)
+    }
+
+    /// the first declared value, which is the default
+    pub fn default_value(&self) -> Option<EnumValueDescriptor<'a>> {
+        self.values().next()
+    }
+}
+
+impl<'a> EnumValueDescriptor<'a> {
+    fn data(&self) -> &'a EnumValueData {
+        &self.parent.data().values[self.index]
+    }
+
+    fn pool(&self) -> &'a DescriptorPool {
+        self.parent.pool
+    }
+
+    fn key(&self) -> (usize, usize) {
+        (self.parent.index, self.index)
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.data().name
+    }
+
+    pub fn number(&self) -> i32 {
+        self.data().number
+    }
+
+    pub fn parent_enum(&self) -> EnumDescriptor<'a> {
+        self.parent
+    }
+}
+
+impl<'a> ServiceDescriptor<'a> {
+    fn data(&self) -> &'a ServiceData {
+        &self.pool.services[self.index]
+    }
+
+    fn pool(&self) -> &'a DescriptorPool {
+        self.pool
+    }
+
+    fn key(&self) -> usize {
+        self.index
+    }
+
+    pub fn full_name(&self) -> &'a str {
+        &self.data().full_name
+    }
+
+    pub fn name(&self) -> &'a str {
+        short_name(self.full_name())
+    }
+
+    pub fn file(&self) -> FileDescriptor<'a> {
+        FileDescriptor {
+            pool: self.pool,
+            index: self.data().file,
+        }
+    }
+
+    pub fn methods(&self) -> impl ExactSizeIterator<Item = MethodDescriptor<'a>> + 'a {
+        let service = *self;
+        (0..self.data().methods.len()).map(move |index| MethodDescriptor { service, index })
+    }
+
+    pub fn get_method_by_name(&self, name: &str) -> Option<MethodDescriptor<'a>> {
+        self.methods().find(|method| method.name() == name)
+    }
+}
+
+impl<'a> MethodDescriptor<'a> {
+    fn data(&self) -> &'a MethodData {
+        &self.service.data().methods[self.index]
+    }
+
+    fn pool(&self) -> &'a DescriptorPool {
+        self.service.pool
+    }
+
+    fn key(&self) -> (usize, usize) {
+        (self.service.index, self.index)
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.data().name
+    }
+
+    pub fn parent_service(&self) -> ServiceDescriptor<'a> {
+        self.service
+    }
+
+    pub fn input(&self) -> MessageDescriptor<'a> {
+        MessageDescriptor {
+            pool: self.service.pool,
+            index: self.data().input,
+        }
+    }
+
+    pub fn output(&self) -> MessageDescriptor<'a> {
+        MessageDescriptor {
+            pool: self.service.pool,
+            index: self.data().output,
+        }
+    }
+
+    pub fn is_client_streaming(&self) -> bool {
+        self.data().client_streaming
+    }
+
+    pub fn is_server_streaming(&self) -> bool {
+        self.data().server_streaming
+    }
+}
@@ -0,0 +1,858 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::borrowed::{FieldRef, MessageObjectRef, MessageRef};
+use crate::descriptor::{
+    EnumData, EnumDescriptor, EnumValueData, FieldData, FieldDescriptor, FileData, FileDescriptor,
+    KindData, Label, MessageData, MessageDescriptor, MethodData, OneofData, ServiceData,
+    ServiceDescriptor, Syntax,
+};
+use crate::error::{DecodeError, DecodeErrorKind};
+
+/// A registry of message, enum and service definitions, built from the
+/// `google.protobuf.FileDescriptorSet` output of `protoc --descriptor_set_out`
+///
+/// Descriptors are decoded with the crate's own wire types, and every type name is
+/// resolved across all the files in the pool, using the scoping rules of `.proto` files.
+/// Extensions are found through the message they extend. Options other than `packed` and
+/// `map_entry` are not modelled.
+#[derive(Debug, Clone, Default)]
+pub struct DescriptorPool {
+    pub(crate) files: Vec<FileData>,
+    pub(crate) messages: Vec<MessageData>,
+    pub(crate) enums: Vec<EnumData>,
+    pub(crate) services: Vec<ServiceData>,
+    /// every fully qualified name, without a leading dot
+    symbols: BTreeMap<String, Symbol>,
+}
+
+/// An error raised while adding descriptors to a `DescriptorPool`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DescriptorError {
+    /// the descriptor bytes could not be decoded
+    Decode(DecodeError),
+    /// a field of a descriptor had a different wire type than `descriptor.proto` gives it
+    WrongWireType { field_id: u64, wire_type: u64 },
+    /// a string in a descriptor was not valid UTF-8
+    InvalidUtf8,
+    /// a file of the same name is already in the pool
+    DuplicateFile(String),
+    /// two definitions share a fully qualified name
+    DuplicateName(String),
+    /// a file imports another which is neither in the pool nor being added with it
+    MissingDependency { file: String, dependency: String },
+    /// the type of a field, or the input or output of a method, was not found
+    UnresolvedType { name: String, type_name: String },
+    /// a field has an unknown type or label, or refers to a oneof which doesn't exist
+    InvalidField(String),
+    /// a file declares a syntax other than `proto2`, `proto3` or `editions`
+    UnknownSyntax(String),
+}
+
+impl From<DecodeError> for DescriptorError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl core::fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "{e}"),
+            Self::WrongWireType {
+                field_id,
+                wire_type,
+            } => write!(
+                f,
+                "descriptor field {field_id} has unexpected wire type {wire_type}"
+            ),
+            Self::InvalidUtf8 => write!(f, "descriptor string is not valid UTF-8"),
+            Self::DuplicateFile(name) => write!(f, "file {name} is already in the pool"),
+            Self::DuplicateName(name) => write!(f, "{name} is defined more than once"),
+            Self::MissingDependency { file, dependency } => {
+                write!(f, "{file} imports {dependency}, which is not in the pool")
+            }
+            Self::UnresolvedType { name, type_name } => {
+                write!(f, "type {type_name} of {name} is not defined")
+            }
+            Self::InvalidField(name) => write!(f, "field {name} is invalid"),
+            Self::UnknownSyntax(syntax) => write!(f, "unknown syntax {syntax}"),
+        }
+    }
+}
+
+impl core::error::Error for DescriptorError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Symbol {
+    Package,
+    Message(usize),
+    Enum(usize),
+    Service(usize),
+    /// the extension at `index` among the extensions of `message`
+    Extension {
+        message: usize,
+        index: usize,
+    },
+}
+
+/// How deeply message definitions may be nested, like `DecodeOptions::max_depth`
+const MAX_DEPTH: usize = 100;
+
+impl DescriptorPool {
+    /// `google/protobuf/descriptor.proto` as an encoded `FileDescriptorSet`, limited to the
+    /// messages describing proto2 and proto3 files and their common options, generated
+    /// from `descriptor.txtpb`
+    pub const DESCRIPTOR_PROTO: &'static [u8] = include_bytes!("descriptor.pb");
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a pool holding `google/protobuf/descriptor.proto`, so descriptors can themselves
+    /// be inspected, and other descriptor sets can import it
+    pub fn bootstrap() -> Self {
+        Self::decode(Self::DESCRIPTOR_PROTO).expect("the embedded descriptor.proto is valid")
+    }
+
+    /// a pool of the files in an encoded `FileDescriptorSet`
+    pub fn decode(data: &[u8]) -> Result<Self, DescriptorError> {
+        let mut pool = Self::new();
+        pool.add_file_descriptor_set(data)?;
+        Ok(pool)
+    }
+
+    /// adds the files of an encoded `FileDescriptorSet`, which may import each other in
+    /// any order as well as the files already in the pool
+    ///
+    /// The pool is left unchanged if any file fails to decode or resolve.
+    pub fn add_file_descriptor_set(&mut self, data: &[u8]) -> Result<(), DescriptorError> {
+        let mut files = Vec::new();
+        for field in fields(data) {
+            let field = field?;
+            if field.get_field_id() == 1 {
+                files.push(FileProto::decode(message(&field)?)?);
+            }
+        }
+        self.add_files(files)
+    }
+
+    /// adds a single encoded `FileDescriptorProto`, whose imports must already be in the
+    /// pool
+    pub fn add_file_descriptor_proto(&mut self, data: &[u8]) -> Result<(), DescriptorError> {
+        self.add_files(Vec::from([FileProto::decode(data)?]))
+    }
+
+    pub fn files(&self) -> impl ExactSizeIterator<Item = FileDescriptor<'_>> {
+        (0..self.files.len()).map(|index| FileDescriptor { pool: self, index })
+    }
+
+    pub fn get_file_by_name(&self, name: &str) -> Option<FileDescriptor<'_>> {
+        self.files().find(|file| file.name() == name)
+    }
+
+    /// every message in the pool, nested ones included
+    pub fn messages(&self) -> impl ExactSizeIterator<Item = MessageDescriptor<'_>> {
+        (0..self.messages.len()).map(|index| MessageDescriptor { pool: self, index })
+    }
+
+    /// the message with the fully qualified `name`, which may start with a dot
+    pub fn get_message_by_name(&self, name: &str) -> Option<MessageDescriptor<'_>> {
+        match self.symbol(name)? {
+            Symbol::Message(index) => Some(MessageDescriptor { pool: self, index }),
+            _ => None,
+        }
+    }
+
+    /// every enum in the pool, nested ones included
+    pub fn enums(&self) -> impl ExactSizeIterator<Item = EnumDescriptor<'_>> {
+        (0..self.enums.len()).map(|index| EnumDescriptor { pool: self, index })
+    }
+
+    pub fn get_enum_by_name(&self, name: &str) -> Option<EnumDescriptor<'_>> {
+        match self.symbol(name)? {
+            Symbol::Enum(index) => Some(EnumDescriptor { pool: self, index }),
+            _ => None,
+        }
+    }
+
+    pub fn services(&self) -> impl ExactSizeIterator<Item = ServiceDescriptor<'_>> {
+        (0..self.services.len()).map(|index| ServiceDescriptor { pool: self, index })
+    }
+
+    pub fn get_service_by_name(&self, name: &str) -> Option<ServiceDescriptor<'_>> {
+        match self.symbol(name)? {
+            Symbol::Service(index) => Some(ServiceDescriptor { pool: self, index }),
+            _ => None,
+        }
+    }
+
+    /// the extension with the fully qualified `name`, which may start with a dot
+    pub fn get_extension_by_name(&self, name: &str) -> Option<FieldDescriptor<'_>> {
+        match self.symbol(name)? {
+            Symbol::Extension { message, index } => {
+                let message = MessageDescriptor {
+                    pool: self,
+                    index: message,
+                };
+                Some(FieldDescriptor {
+                    message,
+                    index: self.messages[message.index].fields.len() + index,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn symbol(&self, name: &str) -> Option<Symbol> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        self.symbols.get(name).copied()
+    }
+
+    /// registers the definitions of every file before resolving any names, working on a
+    /// copy so a failure leaves the pool as it was
+    fn add_files(&mut self, files: Vec<FileProto>) -> Result<(), DescriptorError> {
+        let mut pool = self.clone();
+        let first_file = pool.files.len();
+        let mut pending = Pending {
+            messages: Vec::new(),
+            services: Vec::new(),
+            extensions: Vec::new(),
+        };
+        for file in &files {
+            pool.register_file(file, &mut pending)?;
+        }
+
+        for (offset, file) in files.iter().enumerate() {
+            for dependency in &file.dependencies {
+                let Some(index) = pool.files.iter().position(|f| f.name == *dependency) else {
+                    return Err(DescriptorError::MissingDependency {
+                        file: file.name.clone(),
+                        dependency: dependency.clone(),
+                    });
+                };
+                pool.files[first_file + offset].dependencies.push(index);
+            }
+        }
+
+        for (index, message) in pending.messages {
+            let fields = pool.resolve_fields(index, message)?;
+            pool.messages[index].fields = fields;
+        }
+        // extensions come after fields, so those of the extended message are final
+        for (file, scope, extension) in pending.extensions {
+            let name = join(&scope, &extension.name);
+            let Some(Symbol::Message(message)) = pool.resolve(&scope, &extension.extendee) else {
+                return Err(DescriptorError::UnresolvedType {
+                    name,
+                    type_name: extension.extendee.clone(),
+                });
+            };
+            let mut field = pool.resolve_field(&scope, extension)?;
+            field.extension = Some(file);
+            let index = pool.messages[message].extensions.len();
+            pool.insert_symbol(&name, Symbol::Extension { message, index })?;
+            pool.messages[message].extensions.push(field);
+        }
+        for (index, service) in pending.services {
+            let scope = package_of(&pool.services[index].full_name).to_string();
+            let mut methods = Vec::new();
+            for method in &service.methods {
+                let name = join(&pool.services[index].full_name, &method.name);
+                let message = |type_name: &str| match pool.resolve(&scope, type_name) {
+                    Some(Symbol::Message(index)) => Ok(index),
+                    _ => Err(DescriptorError::UnresolvedType {
+                        name: name.clone(),
+                        type_name: type_name.to_string(),
+                    }),
+                };
+                methods.push(MethodData {
+                    name: method.name.clone(),
+                    input: message(&method.input_type)?,
+                    output: message(&method.output_type)?,
+                    client_streaming: method.client_streaming,
+                    server_streaming: method.server_streaming,
+                });
+            }
+            pool.services[index].methods = methods;
+        }
+
+        *self = pool;
+        Ok(())
+    }
+
+    /// adds the file and its definitions with empty fields and methods, collecting the
+    /// messages, services and extensions to be resolved once every file is registered
+    fn register_file<'p>(
+        &mut self,
+        file: &'p FileProto,
+        pending: &mut Pending<'p>,
+    ) -> Result<(), DescriptorError> {
+        if self.files.iter().any(|f| f.name == file.name) {
+            return Err(DescriptorError::DuplicateFile(file.name.clone()));
+        }
+        let syntax = match file.syntax.as_str() {
+            "" | "proto2" => Syntax::Proto2,
+            "proto3" => Syntax::Proto3,
+            "editions" => Syntax::Editions,
+            other => return Err(DescriptorError::UnknownSyntax(other.to_string())),
+        };
+
+        let index = self.files.len();
+        self.files.push(FileData {
+            name: file.name.clone(),
+            package: file.package.clone(),
+            dependencies: Vec::new(),
+            syntax,
+            messages: Vec::new(),
+            enums: Vec::new(),
+            services: Vec::new(),
+        });
+
+        // every prefix of the package is a scope names can be resolved against
+        let mut package = String::new();
+        for part in file.package.split('.').filter(|part| !part.is_empty()) {
+            package = join(&package, part);
+            if let Some(Symbol::Package) | None = self.symbols.get(&package) {
+                self.symbols.insert(package.clone(), Symbol::Package);
+            } else {
+                return Err(DescriptorError::DuplicateName(package));
+            }
+        }
+
+        for message in &file.messages {
+            let message = self.register_message(index, &file.package, None, message, pending)?;
+            self.files[index].messages.push(message);
+        }
+        for e in &file.enums {
+            let e = self.register_enum(index, &file.package, None, e)?;
+            self.files[index].enums.push(e);
+        }
+        for service in &file.services {
+            let full_name = join(&file.package, &service.name);
+            let service_index = self.services.len();
+            self.insert_symbol(&full_name, Symbol::Service(service_index))?;
+            self.services.push(ServiceData {
+                full_name,
+                file: index,
+                methods: Vec::new(),
+            });
+            self.files[index].services.push(service_index);
+            pending.services.push((service_index, service));
+        }
+        for extension in &file.extensions {
+            pending
+                .extensions
+                .push((index, file.package.clone(), extension));
+        }
+        Ok(())
+    }
+
+    fn register_message<'p>(
+        &mut self,
+        file: usize,
+        scope: &str,
+        parent: Option<usize>,
+        message: &'p MessageProto,
+        pending: &mut Pending<'p>,
+    ) -> Result<usize, DescriptorError> {
+        let full_name = join(scope, &message.name);
+        let index = self.messages.len();
+        self.insert_symbol(&full_name, Symbol::Message(index))?;
+        self.messages.push(MessageData {
+            full_name: full_name.clone(),
+            file,
+            parent,
+            fields: Vec::new(),
+            oneofs: message
+                .oneofs
+                .iter()
+                .map(|name| OneofData {
+                    name: name.clone(),
+                    fields: Vec::new(),
+                })
+                .collect(),
+            messages: Vec::new(),
+            enums: Vec::new(),
+            map_entry: message.map_entry,
+            extensions: Vec::new(),
+        });
+        pending.messages.push((index, message));
+
+        for nested in &message.messages {
+            let nested = self.register_message(file, &full_name, Some(index), nested, pending)?;
+            self.messages[index].messages.push(nested);
+        }
+        for extension in &message.extensions {
+            pending
+                .extensions
+                .push((file, full_name.clone(), extension));
+        }
+        for e in &message.enums {
+            let e = self.register_enum(file, &full_name, Some(index), e)?;
+            self.messages[index].enums.push(e);
+        }
+        Ok(index)
+    }
+
+    fn register_enum(
+        &mut self,
+        file: usize,
+        scope: &str,
+        parent: Option<usize>,
+        e: &EnumProto,
+    ) -> Result<usize, DescriptorError> {
+        let full_name = join(scope, &e.name);
+        let index = self.enums.len();
+        self.insert_symbol(&full_name, Symbol::Enum(index))?;
+        self.enums.push(EnumData {
+            full_name,
+            file,
+            parent,
+            values: e.values.clone(),
+        });
+        Ok(index)
+    }
+
+    fn insert_symbol(&mut self, name: &str, symbol: Symbol) -> Result<(), DescriptorError> {
+        if self.symbols.contains_key(name) {
+            return Err(DescriptorError::DuplicateName(name.to_string()));
+        }
+        self.symbols.insert(name.to_string(), symbol);
+        Ok(())
+    }
+
+    /// the fields of the message at `index` with their types resolved, also filling in
+    /// the fields of its oneofs
+    fn resolve_fields(
+        &mut self,
+        index: usize,
+        message: &MessageProto,
+    ) -> Result<Vec<FieldData>, DescriptorError> {
+        let scope = self.messages[index].full_name.clone();
+        let mut fields = Vec::new();
+        for (position, field) in message.fields.iter().enumerate() {
+            if let Some(oneof) = field.oneof {
+                match self.messages[index].oneofs.get_mut(oneof) {
+                    Some(oneof) => oneof.fields.push(position),
+                    None => return Err(DescriptorError::InvalidField(join(&scope, &field.name))),
+                }
+            }
+            fields.push(self.resolve_field(&scope, field)?);
+        }
+        Ok(fields)
+    }
+
+    /// a field or extension declared in `scope`, with its type resolved
+    fn resolve_field(&self, scope: &str, field: &FieldProto) -> Result<FieldData, DescriptorError> {
+        let name = join(scope, &field.name);
+        let invalid = || DescriptorError::InvalidField(name.clone());
+        let resolved = match field.type_name.is_empty() {
+            true => None,
+            false => match self.resolve(scope, &field.type_name) {
+                Some(symbol @ (Symbol::Message(_) | Symbol::Enum(_))) => Some(symbol),
+                _ => {
+                    return Err(DescriptorError::UnresolvedType {
+                        name,
+                        type_name: field.type_name.clone(),
+                    })
+                }
+            },
+        };
+
+        let kind = match (field.kind, resolved) {
+            (1, _) => KindData::Double,
+            (2, _) => KindData::Float,
+            (3, _) => KindData::Int64,
+            (4, _) => KindData::Uint64,
+            (5, _) => KindData::Int32,
+            (6, _) => KindData::Fixed64,
+            (7, _) => KindData::Fixed32,
+            (8, _) => KindData::Bool,
+            (9, _) => KindData::String,
+            (10, Some(Symbol::Message(index))) => KindData::Group(index),
+            // the type is left out of descriptors which haven't been resolved by protoc
+            (0 | 11, Some(Symbol::Message(index))) => KindData::Message(index),
+            (12, _) => KindData::Bytes,
+            (13, _) => KindData::Uint32,
+            (0 | 14, Some(Symbol::Enum(index))) => KindData::Enum(index),
+            (15, _) => KindData::Sfixed32,
+            (16, _) => KindData::Sfixed64,
+            (17, _) => KindData::Sint32,
+            (18, _) => KindData::Sint64,
+            _ => return Err(invalid()),
+        };
+        let label = match field.label {
+            0 | 1 => Label::Optional,
+            2 => Label::Required,
+            3 => Label::Repeated,
+            _ => return Err(invalid()),
+        };
+
+        Ok(FieldData {
+            name: field.name.clone(),
+            json_name: field
+                .json_name
+                .clone()
+                .unwrap_or_else(|| json_name(&field.name)),
+            number: field.number,
+            label,
+            kind,
+            oneof: field.oneof,
+            packed: field.packed,
+            proto3_optional: field.proto3_optional,
+            default_value: field.default_value.clone(),
+            full_name: name,
+            extension: None,
+        })
+    }
+
+    /// resolves `type_name` as written in `scope`: names starting with a dot are fully
+    /// qualified, others are looked up from the innermost scope outwards, the first scope
+    /// defining their first component being the only one searched
+    fn resolve(&self, scope: &str, type_name: &str) -> Option<Symbol> {
+        if let Some(full_name) = type_name.strip_prefix('.') {
+            return self.symbols.get(full_name).copied();
+        }
+
+        let first = type_name.split('.').next().unwrap_or(type_name);
+        let mut scope = scope;
+        loop {
+            if self.symbols.contains_key(&join(scope, first)) {
+                return self.symbols.get(&join(scope, type_name)).copied();
+            }
+            if scope.is_empty() {
+                return None;
+            }
+            scope = package_of(scope);
+        }
+    }
+}
+
+/// `name` qualified by `scope`, which may be empty
+fn join(scope: &str, name: &str) -> String {
+    match scope.is_empty() {
+        true => name.to_string(),
+        false => alloc::format!("{scope}.{name}"),
+    }
+}
+
+/// the scope enclosing a fully qualified name
+fn package_of(full_name: &str) -> &str {
+    full_name.rsplit_once('.').map_or("", |(scope, _)| scope)
+}
+
+/// the JSON name protoc derives from a field name, dropping underscores and capitalising
+/// the letter after each
+fn json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut capitalise = false;
+    for c in name.chars() {
+        match c {
+            '_' => capitalise = true,
+            c if capitalise => {
+                json_name.push(c.to_ascii_uppercase());
+                capitalise = false;
+            }
+            c => json_name.push(c),
+        }
+    }
+    json_name
+}
+
+/// The definitions registered by `add_files` whose names are resolved once every file is
+/// registered, with the scope extensions are declared in
+struct Pending<'p> {
+    messages: Vec<(usize, &'p MessageProto)>,
+    services: Vec<(usize, &'p ServiceProto)>,
+    extensions: Vec<(usize, String, &'p FieldProto)>,
+}
+
+// the parts of `descriptor.proto` the pool is built from, decoded field by field
+
+struct FileProto {
+    name: String,
+    package: String,
+    dependencies: Vec<String>,
+    messages: Vec<MessageProto>,
+    enums: Vec<EnumProto>,
+    services: Vec<ServiceProto>,
+    extensions: Vec<FieldProto>,
+    syntax: String,
+}
+
+struct MessageProto {
+    name: String,
+    fields: Vec<FieldProto>,
+    messages: Vec<MessageProto>,
+    enums: Vec<EnumProto>,
+    oneofs: Vec<String>,
+    extensions: Vec<FieldProto>,
+    map_entry: bool,
+}
+
+struct FieldProto {
+    name: String,
+    number: u32,
+    label: u64,
+    kind: u64,
+    type_name: String,
+    extendee: String,
+    default_value: Option<String>,
+    oneof: Option<usize>,
+    json_name: Option<String>,
+    packed: Option<bool>,
+    proto3_optional: bool,
+}
+
+struct EnumProto {
+    name: String,
+    values: Vec<EnumValueData>,
+}
+
+struct ServiceProto {
+    name: String,
+    methods: Vec<MethodProto>,
+}
+
+struct MethodProto {
+    name: String,
+    input_type: String,
+    output_type: String,
+    client_streaming: bool,
+    server_streaming: bool,
+}
+
+impl FileProto {
+    fn decode(data: &[u8]) -> Result<Self, DescriptorError> {
+        let mut file = Self {
+            name: String::new(),
+            package: String::new(),
+            dependencies: Vec::new(),
+            messages: Vec::new(),
+            enums: Vec::new(),
+            services: Vec::new(),
+            extensions: Vec::new(),
+            syntax: String::new(),
+        };
+        for field in fields(data) {
+            let field = field?;
+            match field.get_field_id() {
+                1 => file.name = string(&field)?,
+                2 => file.package = string(&field)?,
+                3 => file.dependencies.push(string(&field)?),
+                4 => file
+                    .messages
+                    .push(MessageProto::decode(message(&field)?, MAX_DEPTH)?),
+                5 => file.enums.push(EnumProto::decode(message(&field)?)?),
+                6 => file.services.push(ServiceProto::decode(message(&field)?)?),
+                7 => file.extensions.push(FieldProto::decode(message(&field)?)?),
+                12 => file.syntax = string(&field)?,
+                _ => {}
+            }
+        }
+        Ok(file)
+    }
+}
+
+impl MessageProto {
+    /// `depth` is how many more messages may be nested inside this one
+    fn decode(data: &[u8], depth: usize) -> Result<Self, DescriptorError> {
+        if depth == 0 {
+            let kind = DecodeErrorKind::DepthLimitExceeded { limit: MAX_DEPTH };
+            return Err(DescriptorError::Decode(DecodeError::new(kind, 0)));
+        }
+        let mut proto = Self {
+            name: String::new(),
+            fields: Vec::new(),
+            messages: Vec::new(),
+            enums: Vec::new(),
+            oneofs: Vec::new(),
+            extensions: Vec::new(),
+            map_entry: false,
+        };
+        for field in fields(data) {
+            let field = field?;
+            match field.get_field_id() {
+                1 => proto.name = string(&field)?,
+                2 => proto.fields.push(FieldProto::decode(message(&field)?)?),
+                3 => proto
+                    .messages
+                    .push(Self::decode(message(&field)?, depth - 1)?),
+                4 => proto.enums.push(EnumProto::decode(message(&field)?)?),
+                6 => proto.extensions.push(FieldProto::decode(message(&field)?)?),
+                // MessageOptions.map_entry
+                7 => {
+                    for option in fields(message(&field)?) {
+                        let option = option?;
+                        if option.get_field_id() == 7 {
+                            proto.map_entry = varint(&option)? != 0;
+                        }
+                    }
+                }
+                // OneofDescriptorProto, of which only the name matters
+                8 => {
+                    let mut name = String::new();
+                    for oneof in fields(message(&field)?) {
+                        let oneof = oneof?;
+                        if oneof.get_field_id() == 1 {
+                            name = string(&oneof)?;
+                        }
+                    }
+                    proto.oneofs.push(name);
+                }
+                _ => {}
+            }
+        }
+        Ok(proto)
+    }
+}
+
+impl FieldProto {
+    fn decode(data: &[u8]) -> Result<Self, DescriptorError> {
+        let mut proto = Self {
+            name: String::new(),
+            number: 0,
+            label: 0,
+            kind: 0,
+            type_name: String::new(),
+            extendee: String::new(),
+            default_value: None,
+            oneof: None,
+            json_name: None,
+            packed: None,
+            proto3_optional: false,
+        };
+        for field in fields(data) {
+            let field = field?;
+            match field.get_field_id() {
+                1 => proto.name = string(&field)?,
+                2 => proto.extendee = string(&field)?,
+                3 => proto.number = varint(&field)? as u32,
+                4 => proto.label = varint(&field)?,
+                5 => proto.kind = varint(&field)?,
+                6 => proto.type_name = string(&field)?,
+                7 => proto.default_value = Some(string(&field)?),
+                // FieldOptions.packed
+                8 => {
+                    for option in fields(message(&field)?) {
+                        let option = option?;
+                        if option.get_field_id() == 2 {
+                            proto.packed = Some(varint(&option)? != 0);
+                        }
+                    }
+                }
+                9 => proto.oneof = Some(varint(&field)? as usize),
+                10 => proto.json_name = Some(string(&field)?),
+                17 => proto.proto3_optional = varint(&field)? != 0,
+                _ => {}
+            }
+        }
+        Ok(proto)
+    }
+}
+
+impl EnumProto {
+    fn decode(data: &[u8]) -> Result<Self, DescriptorError> {
+        let mut proto = Self {
+            name: String::new(),
+            values: Vec::new(),
+        };
+        for field in fields(data) {
+            let field = field?;
+            match field.get_field_id() {
+                1 => proto.name = string(&field)?,
+                2 => {
+                    let mut value = EnumValueData {
+                        name: String::new(),
+                        number: 0,
+                    };
+                    for field in fields(message(&field)?) {
+                        let field = field?;
+                        match field.get_field_id() {
+                            1 => value.name = string(&field)?,
+                            // int32, so negative numbers are sign extended to 64 bits
+                            2 => value.number = varint(&field)? as i32,
+                            _ => {}
+                        }
+                    }
+                    proto.values.push(value);
+                }
+                _ => {}
+            }
+        }
+        Ok(proto)
+    }
+}
+
+impl ServiceProto {
+    fn decode(data: &[u8]) -> Result<Self, DescriptorError> {
+        let mut proto = Self {
+            name: String::new(),
+            methods: Vec::new(),
+        };
+        for field in fields(data) {
+            let field = field?;
+            match field.get_field_id() {
+                1 => proto.name = string(&field)?,
+                2 => {
+                    let mut method = MethodProto {
+                        name: String::new(),
+                        input_type: String::new(),
+                        output_type: String::new(),
+                        client_streaming: false,
+                        server_streaming: false,
+                    };
+                    for field in fields(message(&field)?) {
+                        let field = field?;
+                        match field.get_field_id() {
+                            1 => method.name = string(&field)?,
+                            2 => method.input_type = string(&field)?,
+                            3 => method.output_type = string(&field)?,
+                            5 => method.client_streaming = varint(&field)? != 0,
+                            6 => method.server_streaming = varint(&field)? != 0,
+                            _ => {}
+                        }
+                    }
+                    proto.methods.push(method);
+                }
+                _ => {}
+            }
+        }
+        Ok(proto)
+    }
+}
+
+fn fields(data: &[u8]) -> impl Iterator<Item = Result<FieldRef<'_>, DescriptorError>> {
+    MessageRef(data)
+        .into_try_iter()
+        .map(|field| field.map_err(DescriptorError::Decode))
+}
+
+fn wrong_wire_type(field: &FieldRef<'_>) -> DescriptorError {
+    DescriptorError::WrongWireType {
+        field_id: field.get_field_id(),
+        wire_type: field.get_wire_type(),
+    }
+}
+
+fn message<'a>(field: &FieldRef<'a>) -> Result<&'a [u8], DescriptorError> {
+    match &field.data {
+        MessageObjectRef::Len(len) => Ok(len.inner),
+        _ => Err(wrong_wire_type(field)),
+    }
+}
+
+fn string(field: &FieldRef<'_>) -> Result<String, DescriptorError> {
+    core::str::from_utf8(message(field)?)
+        .map(|s| s.to_string())
+        .map_err(|_| DescriptorError::InvalidUtf8)
+}
+
+fn varint(field: &FieldRef<'_>) -> Result<u64, DescriptorError> {
+    match &field.data {
+        MessageObjectRef::Varint(value) => Ok(value.get()),
+        _ => Err(wrong_wire_type(field)),
+    }
+}
@@ -0,0 +1,713 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::borrowed::{
+    FieldRef, I32Ref, I64Ref, MessageObjectRef, PackedRepeatedI32Ref, PackedRepeatedI64Ref,
+    PackedRepeatedVarintRef, VarintRef,
+};
+use crate::descriptor::{FieldDescriptor, Kind, Label, MessageDescriptor};
+use crate::error::DecodeError;
+use crate::field::Field;
+use crate::group::Group;
+use crate::i32::I32;
+use crate::i64::I64;
+use crate::len::Len;
+use crate::message::Message;
+use crate::message_object::MessageObject;
+use crate::varint::Varint;
+use crate::wire_data::WireData;
+
+/// A `Message` paired with its message type, giving access to fields by name
+///
+/// Fields are decoded from the wire data on every access and setters rewrite only the
+/// fields they touch, so fields the descriptor doesn't know about are kept as they are.
+/// Absent fields read as their default value from the schema.
+pub struct DynamicMessage<'a> {
+    descriptor: MessageDescriptor<'a>,
+    message: Message,
+}
+
+/// The value of a field of a `DynamicMessage`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Bool(bool),
+    /// `int32`, `sint32` and `sfixed32`
+    I32(i32),
+    /// `int64`, `sint64` and `sfixed64`
+    I64(i64),
+    /// `uint32` and `fixed32`
+    U32(u32),
+    /// `uint64` and `fixed64`
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    /// the number of an enum value, which may not be one the enum declares
+    Enum(i32),
+    /// a message or group
+    Message(DynamicMessage<'a>),
+    List(Vec<Value<'a>>),
+    Map(BTreeMap<MapKey, Value<'a>>),
+}
+
+/// The key of a map entry
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MapKey {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    String(String),
+}
+
+/// An error raised while reading or writing the fields of a `DynamicMessage`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynamicError {
+    /// the wire data could not be decoded
+    Decode(DecodeError),
+    /// the message type has no field of this name
+    UnknownField(String),
+    /// a path passes through a field which is not a singular message
+    NotAMessage(String),
+    /// the field was encoded with a wire type its type can't have
+    WrongWireType { field: String, wire_type: u64 },
+    /// a `string` field held invalid UTF-8
+    InvalidUtf8(String),
+    /// the value given for a field doesn't match its type
+    TypeMismatch(String),
+    /// an enum value was set by a name the enum doesn't declare
+    UnknownEnumValue { field: String, name: String },
+}
+
+impl From<DecodeError> for DynamicError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl core::fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "{e}"),
+            Self::UnknownField(name) => write!(f, "no field named {name}"),
+            Self::NotAMessage(name) => write!(f, "field {name} is not a singular message"),
+            Self::WrongWireType { field, wire_type } => {
+                write!(f, "field {field} has unexpected wire type {wire_type}")
+            }
+            Self::InvalidUtf8(field) => write!(f, "field {field} is not valid UTF-8"),
+            Self::TypeMismatch(field) => write!(f, "value does not match the type of {field}"),
+            Self::UnknownEnumValue { field, name } => {
+                write!(f, "enum of field {field} has no value named {name}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for DynamicError {}
+
+impl Clone for DynamicMessage<'_> {
+    fn clone(&self) -> Self {
+        Self {
+            descriptor: self.descriptor,
+            message: Message(self.message.0.clone()),
+        }
+    }
+}
+
+impl PartialEq for DynamicMessage<'_> {
+    /// messages are equal when they have the same type and the same encoding
+    fn eq(&self, other: &Self) -> bool {
+        self.descriptor == other.descriptor && self.message.0.as_ref() == other.message.0.as_ref()
+    }
+}
+
+impl core::fmt::Debug for DynamicMessage<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DynamicMessage")
+            .field("descriptor", &self.descriptor)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl<'a> DynamicMessage<'a> {
+    /// an empty message of the given type
+    pub fn new(descriptor: MessageDescriptor<'a>) -> Self {
+        Self::from(descriptor, Message::new())
+    }
+
+    pub fn from(descriptor: MessageDescriptor<'a>, message: Message) -> Self {
+        Self {
+            descriptor,
+            message,
+        }
+    }
+
+    pub fn descriptor(&self) -> MessageDescriptor<'a> {
+        self.descriptor
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn into_message(self) -> Message {
+        self.message
+    }
+
+    /// the value of the field at `path`, a dot separated list of field names such as
+    /// `user.address.city`, every field but the last being a singular message
+    ///
+    /// Absent fields, including absent messages along the path, read as their default.
+    pub fn get(&self, path: &str) -> Result<Value<'a>, DynamicError> {
+        match path.split_once('.') {
+            Some((name, rest)) => self.child(name)?.get(rest),
+            None => self.get_field(self.field(path)?),
+        }
+    }
+
+    /// the value of `field`, see `get`
+    pub fn get_field(&self, field: FieldDescriptor<'a>) -> Result<Value<'a>, DynamicError> {
+        if field.is_map() {
+            return self.map(field).map(Value::Map);
+        }
+
+        let mut values = self.values(field)?;
+        if field.is_list() {
+            return Ok(Value::List(values));
+        }
+        match field.kind() {
+            // every occurrence of a message is merged, which concatenating their encodings does
+            Kind::Message(descriptor) | Kind::Group(descriptor) if values.len() > 1 => {
+                let mut merged = bytes::BytesMut::new();
+                for value in values {
+                    if let Value::Message(message) = value {
+                        merged.extend_from_slice(message.message.0.as_ref());
+                    }
+                }
+                Ok(Value::Message(Self::from(
+                    descriptor,
+                    Message(WireData::Mut(merged)),
+                )))
+            }
+            _ => match values.pop() {
+                Some(value) => Ok(value),
+                None => default_value(field),
+            },
+        }
+    }
+
+    /// the name of the enum value held by the field at `path`, if the enum declares it
+    pub fn get_enum_name(&self, path: &str) -> Result<Option<&'a str>, DynamicError> {
+        if let Some((name, rest)) = path.split_once('.') {
+            return self.child(name)?.get_enum_name(rest);
+        }
+
+        let field = self.field(path)?;
+        match (field.kind(), self.get_field(field)?) {
+            (Kind::Enum(e), Value::Enum(number)) => Ok(e.get_value(number).map(|v| v.name())),
+            _ => Err(DynamicError::TypeMismatch(field.name().to_string())),
+        }
+    }
+
+    /// whether the field at `path` is present on the wire, for a repeated field whether it
+    /// has any elements
+    pub fn has(&self, path: &str) -> Result<bool, DynamicError> {
+        match path.split_once('.') {
+            Some((name, rest)) => Ok(self.has(name)? && self.child(name)?.has(rest)?),
+            None => {
+                let number = self.field(path)?.number() as u64;
+                Ok(self.message.get(number)?.is_some())
+            }
+        }
+    }
+
+    /// removes every occurrence of the field at `path`
+    pub fn clear(&mut self, path: &str) -> Result<(), DynamicError> {
+        let Some((name, rest)) = path.split_once('.') else {
+            let number = self.field(path)?.number() as u64;
+            self.message.remove(number)?;
+            return Ok(());
+        };
+
+        // clearing inside an absent message changes nothing
+        if !self.has(name)? {
+            return Ok(());
+        }
+        let mut child = self.child(name)?;
+        child.clear(rest)?;
+        self.set_field(self.field(name)?, Value::Message(child))
+    }
+
+    /// sets the field at `path`, creating the messages along the path which are absent
+    ///
+    /// Enum fields take either a `Value::Enum` or the name of a value as a `Value::String`.
+    /// Setting a field of a oneof clears the other fields of the oneof.
+    pub fn set(&mut self, path: &str, value: Value<'a>) -> Result<(), DynamicError> {
+        let Some((name, rest)) = path.split_once('.') else {
+            return self.set_field(self.field(path)?, value);
+        };
+
+        let mut child = self.child(name)?;
+        child.set(rest, value)?;
+        self.set_field(self.field(name)?, Value::Message(child))
+    }
+
+    /// sets `field`, see `set`
+    pub fn set_field(
+        &mut self,
+        field: FieldDescriptor<'a>,
+        value: Value<'a>,
+    ) -> Result<(), DynamicError> {
+        let fields = encode(field, value)?;
+
+        if let Some(oneof) = field.containing_oneof() {
+            for other in oneof.fields().filter(|other| *other != field) {
+                self.message.remove(other.number() as u64)?;
+            }
+        }
+        let number = field.number() as u64;
+        match fields.len() {
+            1 => {
+                let field = fields.into_iter().next().unwrap();
+                self.message.set(number, field.data)?;
+            }
+            _ => {
+                self.message.remove(number)?;
+                for field in fields {
+                    self.message.push(field);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the value of `field` if it is set: for a field with presence if it is on the wire,
+    /// otherwise if it differs from the zero value of its type
+    pub(crate) fn get_present_field(
+        &self,
+        field: FieldDescriptor<'a>,
+    ) -> Result<Option<Value<'a>>, DynamicError> {
+        let value = self.get_field(field)?;
+        let present = match field.is_list() || field.is_map() || !field.has_presence() {
+            true => !is_zero(&value),
+            false => self.message.contains(field.number() as u64)?,
+        };
+        Ok(present.then_some(value))
+    }
+
+    /// sets `field`, unless it has no presence and `value` is the zero value of its type,
+    /// which isn't encoded
+    pub(crate) fn set_present_field(
+        &mut self,
+        field: FieldDescriptor<'a>,
+        value: Value<'a>,
+    ) -> Result<(), DynamicError> {
+        let implicit = field.is_list() || field.is_map() || !field.has_presence();
+        match implicit && is_zero(&value) {
+            true => Ok(()),
+            false => self.set_field(field, value),
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<FieldDescriptor<'a>, DynamicError> {
+        self.descriptor
+            .get_field_by_name(name)
+            .ok_or_else(|| DynamicError::UnknownField(name.to_string()))
+    }
+
+    /// the singular message field `name`, empty if it is absent
+    fn child(&self, name: &str) -> Result<Self, DynamicError> {
+        let field = self.field(name)?;
+        match (field.kind(), field.label()) {
+            (Kind::Message(_) | Kind::Group(_), Label::Optional | Label::Required) => {
+                match self.get_field(field)? {
+                    Value::Message(message) => Ok(message),
+                    _ => unreachable!("message fields have message values"),
+                }
+            }
+            _ => Err(DynamicError::NotAMessage(name.to_string())),
+        }
+    }
+
+    /// every value of `field` in the order they appear, unpacking packed values
+    fn values(&self, field: FieldDescriptor<'a>) -> Result<Vec<Value<'a>>, DynamicError> {
+        let kind = field.kind();
+        let mut values = Vec::new();
+        for occurrence in self.message.get_all(field.number() as u64) {
+            let occurrence = occurrence?;
+            let wire_type = occurrence.get_wire_type();
+            if wire_type == 2 && kind.is_packable() {
+                unpack(field, &occurrence, &mut values)?;
+            } else if wire_type == kind.wire_type() {
+                values.push(decode(field, &occurrence)?);
+            } else {
+                return Err(DynamicError::WrongWireType {
+                    field: field.name().to_string(),
+                    wire_type,
+                });
+            }
+        }
+        Ok(values)
+    }
+
+    fn map(&self, field: FieldDescriptor<'a>) -> Result<BTreeMap<MapKey, Value<'a>>, DynamicError> {
+        let mut map = BTreeMap::new();
+        for entry in self.values(field)? {
+            let Value::Message(entry) = entry else {
+                continue;
+            };
+            let (Some(key), Some(value)) =
+                (entry.descriptor.get_field(1), entry.descriptor.get_field(2))
+            else {
+                return Err(DynamicError::TypeMismatch(field.name().to_string()));
+            };
+            let key = map_key(entry.get_field(key)?)
+                .ok_or_else(|| DynamicError::TypeMismatch(field.name().to_string()))?;
+            // later entries for the same key replace earlier ones
+            map.insert(key, entry.get_field(value)?);
+        }
+        Ok(map)
+    }
+}
+
+/// whether `value` is the zero value of its type, which fields without presence omit
+fn is_zero(value: &Value<'_>) -> bool {
+    match value {
+        Value::Bool(value) => !value,
+        Value::I32(value) | Value::Enum(value) => *value == 0,
+        Value::I64(value) => *value == 0,
+        Value::U32(value) => *value == 0,
+        Value::U64(value) => *value == 0,
+        // -0.0 is not the default, it has a different encoding
+        Value::F32(value) => value.to_bits() == 0,
+        Value::F64(value) => value.to_bits() == 0,
+        Value::String(value) => value.is_empty(),
+        Value::Bytes(value) => value.is_empty(),
+        Value::List(values) => values.is_empty(),
+        Value::Map(map) => map.is_empty(),
+        Value::Message(_) => false,
+    }
+}
+
+fn map_key(value: Value<'_>) -> Option<MapKey> {
+    match value {
+        Value::Bool(value) => Some(MapKey::Bool(value)),
+        Value::I32(value) => Some(MapKey::I32(value)),
+        Value::I64(value) => Some(MapKey::I64(value)),
+        Value::U32(value) => Some(MapKey::U32(value)),
+        Value::U64(value) => Some(MapKey::U64(value)),
+        Value::String(value) => Some(MapKey::String(value)),
+        _ => None,
+    }
+}
+
+impl From<MapKey> for Value<'_> {
+    fn from(key: MapKey) -> Self {
+        match key {
+            MapKey::Bool(value) => Self::Bool(value),
+            MapKey::I32(value) => Self::I32(value),
+            MapKey::I64(value) => Self::I64(value),
+            MapKey::U32(value) => Self::U32(value),
+            MapKey::U64(value) => Self::U64(value),
+            MapKey::String(value) => Self::String(value),
+        }
+    }
+}
+
+fn varint_value(kind: Kind<'_>, value: &VarintRef<'_>) -> Value<'static> {
+    match kind {
+        Kind::Int32 => Value::I32(value.as_proto_int32()),
+        Kind::Sint32 => Value::I32(value.as_proto_sint32()),
+        Kind::Int64 => Value::I64(value.as_proto_int64()),
+        Kind::Sint64 => Value::I64(value.as_proto_sint64()),
+        Kind::Uint32 => Value::U32(value.get() as u32),
+        Kind::Bool => Value::Bool(value.get() != 0),
+        Kind::Enum(_) => Value::Enum(value.as_proto_int32()),
+        _ => Value::U64(value.get()),
+    }
+}
+
+fn i32_value(kind: Kind<'_>, value: &I32Ref<'_>) -> Value<'static> {
+    match kind {
+        Kind::Float => Value::F32(value.get_float()),
+        Kind::Fixed32 => Value::U32(value.get() as u32),
+        _ => Value::I32(value.get()),
+    }
+}
+
+fn i64_value(kind: Kind<'_>, value: &I64Ref<'_>) -> Value<'static> {
+    match kind {
+        Kind::Double => Value::F64(value.get_double()),
+        Kind::Fixed64 => Value::U64(value.get() as u64),
+        _ => Value::I64(value.get()),
+    }
+}
+
+/// a single value of `field`, whose wire type has been checked
+fn decode<'a>(field: FieldDescriptor<'a>, value: &FieldRef<'_>) -> Result<Value<'a>, DynamicError> {
+    let kind = field.kind();
+    Ok(match &value.data {
+        MessageObjectRef::Varint(value) => varint_value(kind, value),
+        MessageObjectRef::I32(value) => i32_value(kind, value),
+        MessageObjectRef::I64(value) => i64_value(kind, value),
+        MessageObjectRef::Len(len) => match kind {
+            Kind::String => match core::str::from_utf8(len.inner) {
+                Ok(s) => Value::String(s.to_string()),
+                Err(_) => return Err(DynamicError::InvalidUtf8(field.name().to_string())),
+            },
+            Kind::Message(descriptor) => Value::Message(DynamicMessage::from(
+                descriptor,
+                Message(WireData::new(bytes::Bytes::copy_from_slice(len.inner))),
+            )),
+            _ => Value::Bytes(len.inner.to_vec()),
+        },
+        MessageObjectRef::Group(group) => {
+            let Kind::Group(descriptor) = kind else {
+                unreachable!("wire type was checked against the kind")
+            };
+            let content =
+                &value.raw[value.tag.0.len()..value.raw.len() - group.end_field_id.0.len()];
+            Value::Message(DynamicMessage::from(
+                descriptor,
+                Message(WireData::new(bytes::Bytes::copy_from_slice(content))),
+            ))
+        }
+        MessageObjectRef::EGroup => unreachable!("wire type was checked against the kind"),
+    })
+}
+
+/// the packed values of `field` held by `occurrence`
+fn unpack<'a>(
+    field: FieldDescriptor<'a>,
+    occurrence: &FieldRef<'_>,
+    values: &mut Vec<Value<'a>>,
+) -> Result<(), DynamicError> {
+    let kind = field.kind();
+    let MessageObjectRef::Len(len) = &occurrence.data else {
+        unreachable!("only called for Len fields")
+    };
+    match kind.wire_type() {
+        1 => {
+            for value in PackedRepeatedI64Ref(len.inner).into_try_iter() {
+                values.push(i64_value(kind, &value?));
+            }
+        }
+        5 => {
+            for value in PackedRepeatedI32Ref(len.inner).into_try_iter() {
+                values.push(i32_value(kind, &value?));
+            }
+        }
+        _ => {
+            for value in PackedRepeatedVarintRef(len.inner).into_try_iter() {
+                values.push(varint_value(kind, &value?));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// the value of an absent field: the default from the schema, or the zero value of its
+/// type
+fn default_value<'a>(field: FieldDescriptor<'a>) -> Result<Value<'a>, DynamicError> {
+    let default = field.default_value();
+    let parse = |zero: Value<'a>| -> Result<Value<'a>, DynamicError> {
+        let Some(default) = default else {
+            return Ok(zero);
+        };
+        let invalid = || DynamicError::TypeMismatch(field.name().to_string());
+        Ok(match zero {
+            Value::Bool(_) => Value::Bool(default == "true"),
+            Value::I32(_) => Value::I32(default.parse().map_err(|_| invalid())?),
+            Value::I64(_) => Value::I64(default.parse().map_err(|_| invalid())?),
+            Value::U32(_) => Value::U32(default.parse().map_err(|_| invalid())?),
+            Value::U64(_) => Value::U64(default.parse().map_err(|_| invalid())?),
+            Value::F32(_) => Value::F32(parse_float(default).ok_or_else(invalid)? as f32),
+            Value::F64(_) => Value::F64(parse_float(default).ok_or_else(invalid)?),
+            Value::String(_) => Value::String(default.to_string()),
+            Value::Bytes(_) => Value::Bytes(unescape(default)),
+            other => other,
+        })
+    };
+
+    match field.kind() {
+        Kind::Double => parse(Value::F64(0.0)),
+        Kind::Float => parse(Value::F32(0.0)),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => parse(Value::I32(0)),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => parse(Value::I64(0)),
+        Kind::Uint32 | Kind::Fixed32 => parse(Value::U32(0)),
+        Kind::Uint64 | Kind::Fixed64 => parse(Value::U64(0)),
+        Kind::Bool => parse(Value::Bool(false)),
+        Kind::String => parse(Value::String(String::new())),
+        Kind::Bytes => parse(Value::Bytes(Vec::new())),
+        Kind::Enum(e) => {
+            let value = default
+                .and_then(|name| e.get_value_by_name(name))
+                .or_else(|| e.default_value());
+            Ok(Value::Enum(value.map_or(0, |value| value.number())))
+        }
+        Kind::Message(descriptor) | Kind::Group(descriptor) => {
+            Ok(Value::Message(DynamicMessage::new(descriptor)))
+        }
+    }
+}
+
+/// a float default as protoc writes it, which includes `inf`, `-inf` and `nan`
+fn parse_float(default: &str) -> Option<f64> {
+    match default {
+        "inf" => Some(f64::INFINITY),
+        "-inf" => Some(f64::NEG_INFINITY),
+        "nan" => Some(f64::NAN),
+        _ => default.parse().ok(),
+    }
+}
+
+/// a `bytes` default, which protoc writes with C escapes
+fn unescape(default: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(default.len());
+    let mut iter = default.bytes().peekable();
+    while let Some(byte) = iter.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match iter.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b'r') => bytes.push(b'\r'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'x') => {
+                let mut value = 0u8;
+                while let Some(digit) = iter.peek().and_then(|c| (*c as char).to_digit(16)) {
+                    value = value.wrapping_mul(16).wrapping_add(digit as u8);
+                    iter.next();
+                }
+                bytes.push(value);
+            }
+            Some(digit @ b'0'..=b'7') => {
+                let mut value = digit - b'0';
+                for _ in 0..2 {
+                    match iter.peek() {
+                        Some(digit @ b'0'..=b'7') => {
+                            value = value.wrapping_mul(8).wrapping_add(digit - b'0');
+                            iter.next();
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(value);
+            }
+            Some(other) => bytes.push(other),
+            None => bytes.push(b'\\'),
+        }
+    }
+    bytes
+}
+
+/// the fields encoding `value` as `field`: one per element of a list or map unless it is
+/// packed, in which case a single field holds every element
+fn encode<'a>(field: FieldDescriptor<'a>, value: Value<'a>) -> Result<Vec<Field>, DynamicError> {
+    let number = field.number() as u64;
+    let mismatch = || DynamicError::TypeMismatch(field.name().to_string());
+
+    if field.is_map() {
+        let (Value::Map(map), Kind::Message(entry)) = (value, field.kind()) else {
+            return Err(mismatch());
+        };
+        let (Some(key_field), Some(value_field)) = (entry.get_field(1), entry.get_field(2)) else {
+            return Err(mismatch());
+        };
+        let mut fields = Vec::with_capacity(map.len());
+        for (key, value) in map {
+            let mut message = Message::new();
+            message.push(Field::new(1, object(key_field, key.into())?));
+            message.push(Field::new(2, object(value_field, value)?));
+            fields.push(Field::new(
+                number,
+                MessageObject::Len(Len::new_message(message)),
+            ));
+        }
+        return Ok(fields);
+    }
+
+    if field.is_list() {
+        let Value::List(values) = value else {
+            return Err(mismatch());
+        };
+        let mut objects = Vec::with_capacity(values.len());
+        for value in values {
+            objects.push(object(field, value)?);
+        }
+        if !field.is_packed() {
+            return Ok(objects
+                .into_iter()
+                .map(|object| Field::new(number, object))
+                .collect());
+        }
+        if objects.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut packed = bytes::BytesMut::new();
+        for object in &objects {
+            object.serialize_into(&mut packed);
+        }
+        let mut len = Len::new();
+        len.set_bytes(&packed);
+        return Ok(Vec::from([Field::new(number, MessageObject::Len(len))]));
+    }
+
+    Ok(Vec::from([Field::new(number, object(field, value)?)]))
+}
+
+/// a single value of `field`
+fn object(field: FieldDescriptor<'_>, value: Value<'_>) -> Result<MessageObject, DynamicError> {
+    let mismatch = || DynamicError::TypeMismatch(field.name().to_string());
+    Ok(match (field.kind(), value) {
+        (Kind::Int32, Value::I32(value)) => MessageObject::Varint(Varint::new_proto_int32(value)),
+        (Kind::Sint32, Value::I32(value)) => MessageObject::Varint(Varint::new_proto_sint32(value)),
+        (Kind::Sfixed32, Value::I32(value)) => MessageObject::I32(I32::new(value)),
+        (Kind::Int64, Value::I64(value)) => MessageObject::Varint(Varint::new_proto_int64(value)),
+        (Kind::Sint64, Value::I64(value)) => MessageObject::Varint(Varint::new_proto_sint64(value)),
+        (Kind::Sfixed64, Value::I64(value)) => MessageObject::I64(I64::new(value)),
+        (Kind::Uint32, Value::U32(value)) => MessageObject::Varint(Varint::new(value as u64)),
+        (Kind::Fixed32, Value::U32(value)) => MessageObject::I32(I32::new(value as i32)),
+        (Kind::Uint64, Value::U64(value)) => MessageObject::Varint(Varint::new(value)),
+        (Kind::Fixed64, Value::U64(value)) => MessageObject::I64(I64::new(value as i64)),
+        (Kind::Float, Value::F32(value)) => MessageObject::I32(I32::new_float(value)),
+        (Kind::Double, Value::F64(value)) => MessageObject::I64(I64::new_double(value)),
+        (Kind::Bool, Value::Bool(value)) => MessageObject::Varint(Varint::new(value as u64)),
+        (Kind::Enum(_), Value::Enum(number)) => {
+            MessageObject::Varint(Varint::new_proto_int32(number))
+        }
+        (Kind::Enum(e), Value::String(name)) => match e.get_value_by_name(&name) {
+            Some(value) => MessageObject::Varint(Varint::new_proto_int32(value.number())),
+            None => {
+                return Err(DynamicError::UnknownEnumValue {
+                    field: field.name().to_string(),
+                    name,
+                })
+            }
+        },
+        (Kind::String, Value::String(value)) => MessageObject::Len(Len::new_string(&value)),
+        (Kind::Bytes, Value::Bytes(value)) => {
+            let mut len = Len::new();
+            len.set_bytes(&value);
+            MessageObject::Len(len)
+        }
+        (Kind::Message(descriptor), Value::Message(message))
+            if message.descriptor == descriptor =>
+        {
+            MessageObject::Len(Len::new_message(message.message))
+        }
+        (Kind::Group(descriptor), Value::Message(message)) if message.descriptor == descriptor => {
+            let mut group = Group::new(field.number() as u64);
+            for field in message.message.into_try_iter() {
+                group.fields.push(field?);
+            }
+            MessageObject::Group(group)
+        }
+        _ => return Err(mismatch()),
+    })
+}
@@ -0,0 +1,546 @@
+//! How the fields of `ProtoWire` types are encoded, named after the protobuf types
+//!
+//! Each encoding reads and writes the Rust types it applies to, like `Sint64` for `i64`.
+//! `DefaultEncoding` picks the encoding of a field which doesn't name one, and the
+//! functions below read and write whole fields, as used by `#[derive(ProtoWire)]`.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::borrowed::{range_of, FieldRef, LenRef, MessageObjectRef, MessageRef};
+use crate::decode_options::DecodeOptions;
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::field::Field;
+use crate::group;
+use crate::len::Len;
+use crate::message;
+use crate::message_object::MessageObject;
+use crate::packed_repeated::{PackedRepeatedI32, PackedRepeatedI64, PackedRepeatedVarint};
+use crate::proto_wire::{ProtoEnum, ProtoOneof, ProtoWire};
+use crate::{i32::I32, i64::I64, varint::Varint};
+
+/// Reads and writes values of `T` as protobuf fields
+pub trait Encoding<T> {
+    /// appends `value` to `message` as field `field_id`
+    fn write(message: &mut message::Message, field_id: u64, value: &T);
+
+    /// the value held by `field`, `None` if this encoding can't hold it. `depth` is how
+    /// many messages enclose `field`, see `ProtoWire::merge_nested`. Errors are at offsets
+    /// within the payload of `field`
+    fn read(field: &FieldRef<'_>, depth: usize) -> Option<Result<T, DecodeError>>;
+
+    /// reads `field` into `value`, see `read`. Messages merge into `value` the way
+    /// protobuf merges repeated occurrences of a singular field, other values replace it
+    fn merge(field: &FieldRef<'_>, value: &mut T, depth: usize) -> Option<Result<(), DecodeError>> {
+        Some(Self::read(field, depth)?.map(|read| *value = read))
+    }
+
+    /// whether `value` is the default, which fields without presence don't write
+    fn is_default(value: &T) -> bool;
+
+    /// the values packed into `len`, `None` if this encoding can't be packed. Values it
+    /// can't hold are added to `rejected` as the varints they were. Errors are at offsets
+    /// within the payload of `len`
+    fn read_packed(
+        len: &LenRef<'_>,
+        rejected: &mut Vec<u64>,
+    ) -> Option<Result<Vec<T>, DecodeError>> {
+        let _ = (len, rejected);
+        None
+    }
+}
+
+/// An encoding of scalars, which repeated fields can pack into a single `Len`
+pub trait PackedEncoding<T>: Encoding<T> {
+    /// `values` packed into a `Len`
+    fn pack(values: &[T]) -> Len;
+}
+
+/// The encoding of a field which doesn't name one
+pub trait DefaultEncoding: Sized {
+    type Encoding: Encoding<Self>;
+}
+
+macro_rules! encodings {
+    ($($(#[$doc:meta])* $name:ident,)*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, Copy)]
+            pub struct $name;
+        )*
+    };
+}
+
+encodings! {
+    /// `int32`, a varint sign extended to 64 bits
+    Int32,
+    /// `int64`, a varint
+    Int64,
+    /// `uint32`, a varint
+    Uint32,
+    /// `uint64`, a varint
+    Uint64,
+    /// `sint32`, a zigzag encoded varint
+    Sint32,
+    /// `sint64`, a zigzag encoded varint
+    Sint64,
+    /// `fixed32`, an `I32`
+    Fixed32,
+    /// `fixed64`, an `I64`
+    Fixed64,
+    /// `sfixed32`, an `I32`
+    Sfixed32,
+    /// `sfixed64`, an `I64`
+    Sfixed64,
+    /// `bool`, a varint
+    Bool,
+    /// `float`, an `I32`
+    Float,
+    /// `double`, an `I64`
+    Double,
+    /// `string`, a `Len` holding UTF-8
+    String,
+    /// `bytes`, a `Len`
+    Bytes,
+    /// a message held by a `Len`
+    Message,
+    /// a message held by a group, for `#[proto(group)]`
+    Group,
+    /// an enum, a varint like `int32`. Numbers the Rust enum has no variant for are
+    /// treated as unknown fields, those from packed fields as unpacked occurrences
+    Enumeration,
+}
+
+macro_rules! scalar {
+    (
+        $encoding:ident, $ty:ty, $wire:ident, $packed:ident, $into_packed:ident, $set_packed:ident,
+        write: |$value:ident| $write:expr,
+        read: |$v:ident| $read:expr,
+        is_default: |$d:ident| $is_default:expr,
+    ) => {
+        impl Encoding<$ty> for $encoding {
+            fn write(message: &mut message::Message, field_id: u64, $value: &$ty) {
+                let $value = *$value;
+                message.push(Field::new(field_id, MessageObject::$wire($write)));
+            }
+
+            fn read(field: &FieldRef<'_>, _depth: usize) -> Option<Result<$ty, DecodeError>> {
+                match field.get_data() {
+                    MessageObjectRef::$wire($v) => Some(Ok($read)),
+                    _ => None,
+                }
+            }
+
+            fn is_default($d: &$ty) -> bool {
+                $is_default
+            }
+
+            fn read_packed(
+                len: &LenRef<'_>,
+                _rejected: &mut Vec<u64>,
+            ) -> Option<Result<Vec<$ty>, DecodeError>> {
+                Some(
+                    len.$into_packed()
+                        .into_try_iter()
+                        .map(|$v| $v.map(|$v| $read))
+                        .collect(),
+                )
+            }
+        }
+
+        impl PackedEncoding<$ty> for $encoding {
+            fn pack(values: &[$ty]) -> Len {
+                let mut packed = $packed::new();
+                for &$value in values {
+                    packed.push($write);
+                }
+                let mut len = Len::new();
+                len.$set_packed(packed);
+                len
+            }
+        }
+    };
+}
+
+macro_rules! varint {
+    ($encoding:ident, $ty:ty, write: |$value:ident| $write:expr, read: |$v:ident| $read:expr,) => {
+        scalar! {
+            $encoding, $ty, Varint, PackedRepeatedVarint, into_packed_repeated_varint,
+            set_packed_repeated_varint,
+            write: |$value| $write,
+            read: |$v| $read,
+            is_default: |value| *value == <$ty>::default(),
+        }
+    };
+}
+
+varint! { Int32, i32, write: |value| Varint::new_proto_int32(value), read: |v| v.as_proto_int32(), }
+varint! { Int64, i64, write: |value| Varint::new_proto_int64(value), read: |v| v.as_proto_int64(), }
+varint! { Uint32, u32, write: |value| Varint::new(value as u64), read: |v| v.get() as u32, }
+varint! { Uint64, u64, write: |value| Varint::new(value), read: |v| v.get(), }
+varint! { Sint32, i32, write: |value| Varint::new_proto_sint32(value), read: |v| v.as_proto_sint32(), }
+varint! { Sint64, i64, write: |value| Varint::new_proto_sint64(value), read: |v| v.as_proto_sint64(), }
+varint! { Bool, bool, write: |value| Varint::new(value as u64), read: |v| v.get() != 0, }
+
+scalar! {
+    Fixed32, u32, I32, PackedRepeatedI32, into_packed_repeated_i32, set_packed_repeated_i32,
+    write: |value| I32::new(value as i32),
+    read: |v| v.get() as u32,
+    is_default: |value| *value == 0,
+}
+scalar! {
+    Sfixed32, i32, I32, PackedRepeatedI32, into_packed_repeated_i32, set_packed_repeated_i32,
+    write: |value| I32::new(value),
+    read: |v| v.get(),
+    is_default: |value| *value == 0,
+}
+scalar! {
+    Float, f32, I32, PackedRepeatedI32, into_packed_repeated_i32, set_packed_repeated_i32,
+    write: |value| I32::new_float(value),
+    read: |v| v.get_float(),
+    // -0.0 is not the default, it has to be written to survive
+    is_default: |value| value.to_bits() == 0,
+}
+scalar! {
+    Fixed64, u64, I64, PackedRepeatedI64, into_packed_repeated_i64, set_packed_repeated_i64,
+    write: |value| I64::new(value as i64),
+    read: |v| v.get() as u64,
+    is_default: |value| *value == 0,
+}
+scalar! {
+    Sfixed64, i64, I64, PackedRepeatedI64, into_packed_repeated_i64, set_packed_repeated_i64,
+    write: |value| I64::new(value),
+    read: |v| v.get(),
+    is_default: |value| *value == 0,
+}
+scalar! {
+    Double, f64, I64, PackedRepeatedI64, into_packed_repeated_i64, set_packed_repeated_i64,
+    write: |value| I64::new_double(value),
+    read: |v| v.get_double(),
+    is_default: |value| value.to_bits() == 0,
+}
+
+impl Encoding<alloc::string::String> for String {
+    fn write(message: &mut message::Message, field_id: u64, value: &alloc::string::String) {
+        message.push(Field::new(
+            field_id,
+            MessageObject::Len(Len::new_string(value)),
+        ));
+    }
+
+    fn read(
+        field: &FieldRef<'_>,
+        _depth: usize,
+    ) -> Option<Result<alloc::string::String, DecodeError>> {
+        let len = field.as_len()?;
+        Some(
+            len.as_str()
+                .map(ToString::to_string)
+                .map_err(|_| DecodeError::new(DecodeErrorKind::InvalidUtf8, 0)),
+        )
+    }
+
+    fn is_default(value: &alloc::string::String) -> bool {
+        value.is_empty()
+    }
+}
+
+impl Encoding<Vec<u8>> for Bytes {
+    fn write(message: &mut message::Message, field_id: u64, value: &Vec<u8>) {
+        message.push(Field::new(
+            field_id,
+            MessageObject::Len(Len::new_bytes(value)),
+        ));
+    }
+
+    fn read(field: &FieldRef<'_>, _depth: usize) -> Option<Result<Vec<u8>, DecodeError>> {
+        Some(Ok(field.as_len()?.get_data().to_vec()))
+    }
+
+    fn is_default(value: &Vec<u8>) -> bool {
+        value.is_empty()
+    }
+}
+
+impl<T: ProtoWire> Encoding<T> for Message {
+    fn write(message: &mut message::Message, field_id: u64, value: &T) {
+        let len = Len::new_message(value.to_message());
+        message.push(Field::new(field_id, MessageObject::Len(len)));
+    }
+
+    fn read(field: &FieldRef<'_>, depth: usize) -> Option<Result<T, DecodeError>> {
+        let payload = MessageRef::new(field.as_len()?.get_data());
+        Some(nested(depth).and_then(|depth| T::decode_nested(payload, depth)))
+    }
+
+    fn merge(field: &FieldRef<'_>, value: &mut T, depth: usize) -> Option<Result<(), DecodeError>> {
+        let payload = MessageRef::new(field.as_len()?.get_data());
+        Some(nested(depth).and_then(|depth| value.merge_nested(payload, depth)))
+    }
+
+    /// messages have presence, so they are always written
+    fn is_default(_value: &T) -> bool {
+        false
+    }
+}
+
+impl<T: ProtoWire> Encoding<T> for Group {
+    /// the fields of `value` between the start and end tags of the group, copied as they
+    /// are encoded so unknown fields are kept even if they aren't valid wire data
+    fn write(message: &mut message::Message, field_id: u64, value: &T) {
+        let payload = value.to_message();
+        let dest = message.0.get_mut();
+        // 3 is the wire type of the tag starting a group
+        Varint::encode_into(field_id << 3 | 3, dest);
+        dest.extend_from_slice(payload.0.as_ref());
+        Varint::encode_into(field_id << 3 | MessageObject::EGroup.wire_type(), dest);
+    }
+
+    fn read(field: &FieldRef<'_>, depth: usize) -> Option<Result<T, DecodeError>> {
+        let payload = MessageRef::new(field.group_payload()?);
+        Some(nested(depth).and_then(|depth| T::decode_nested(payload, depth)))
+    }
+
+    fn merge(field: &FieldRef<'_>, value: &mut T, depth: usize) -> Option<Result<(), DecodeError>> {
+        let payload = MessageRef::new(field.group_payload()?);
+        Some(nested(depth).and_then(|depth| value.merge_nested(payload, depth)))
+    }
+
+    /// messages have presence, so they are always written
+    fn is_default(_value: &T) -> bool {
+        false
+    }
+}
+
+impl<T: ProtoEnum> Encoding<T> for Enumeration {
+    fn write(message: &mut message::Message, field_id: u64, value: &T) {
+        let varint = Varint::new_proto_int32(value.to_i32());
+        message.push(Field::new(field_id, MessageObject::Varint(varint)));
+    }
+
+    fn read(field: &FieldRef<'_>, _depth: usize) -> Option<Result<T, DecodeError>> {
+        T::from_i32(field.as_varint()?.as_proto_int32()).map(Ok)
+    }
+
+    fn is_default(value: &T) -> bool {
+        value.to_i32() == 0
+    }
+
+    fn read_packed(
+        len: &LenRef<'_>,
+        rejected: &mut Vec<u64>,
+    ) -> Option<Result<Vec<T>, DecodeError>> {
+        let mut values = Vec::new();
+        for value in len.into_packed_repeated_varint().into_try_iter() {
+            match value {
+                Ok(value) => match T::from_i32(value.as_proto_int32()) {
+                    Some(known) => values.push(known),
+                    None => rejected.push(value.get()),
+                },
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(Ok(values))
+    }
+}
+
+impl<T: ProtoEnum> PackedEncoding<T> for Enumeration {
+    fn pack(values: &[T]) -> Len {
+        let mut packed = PackedRepeatedVarint::new();
+        for value in values {
+            packed.push(Varint::new_proto_int32(value.to_i32()));
+        }
+        let mut len = Len::new();
+        len.set_packed_repeated_varint(packed);
+        len
+    }
+}
+
+macro_rules! default_encoding {
+    ($($ty:ty => $encoding:ident,)*) => {
+        $(
+            impl DefaultEncoding for $ty {
+                type Encoding = $encoding;
+            }
+        )*
+    };
+}
+
+default_encoding! {
+    i32 => Int32,
+    i64 => Int64,
+    u32 => Uint32,
+    u64 => Uint64,
+    bool => Bool,
+    f32 => Float,
+    f64 => Double,
+    alloc::string::String => String,
+    Vec<u8> => Bytes,
+}
+
+/// appends `value` to `message` as field `field_id`
+pub fn write<E: Encoding<T>, T>(message: &mut message::Message, field_id: u64, value: &T) {
+    E::write(message, field_id, value);
+}
+
+/// see `write`, skipping default values as fields without presence do
+pub fn write_implicit<E: Encoding<T>, T>(message: &mut message::Message, field_id: u64, value: &T) {
+    if !E::is_default(value) {
+        write::<E, T>(message, field_id, value);
+    }
+}
+
+/// appends `values` to `message`, one field `field_id` each
+pub fn write_repeated<E: Encoding<T>, T>(
+    message: &mut message::Message,
+    field_id: u64,
+    values: &[T],
+) {
+    for value in values {
+        write::<E, T>(message, field_id, value);
+    }
+}
+
+/// appends `values` to `message` packed into a single field `field_id`, or nothing at all
+/// if there are none
+pub fn write_packed<E: PackedEncoding<T>, T>(
+    message: &mut message::Message,
+    field_id: u64,
+    values: &[T],
+) {
+    if !values.is_empty() {
+        message.push(Field::new(field_id, MessageObject::Len(E::pack(values))));
+    }
+}
+
+/// appends the fields of `unknown` to `message` as they are
+pub fn write_unknown(message: &mut message::Message, unknown: &message::Message) {
+    message.0.get_mut().extend_from_slice(unknown.0.as_ref());
+}
+
+/// the value held by `field`, a field of `message`, `None` if `E` can't hold it
+pub fn read<E: Encoding<T>, T>(
+    message: MessageRef<'_>,
+    field: &FieldRef<'_>,
+    depth: usize,
+) -> Result<Option<T>, DecodeError> {
+    E::read(field, depth)
+        .transpose()
+        .map_err(|e| locate(e, message, field))
+}
+
+/// reads `field` into `value`, returning whether `E` could hold it. Messages merge into
+/// `value`, other values replace it
+pub fn read_singular<E: Encoding<T>, T>(
+    message: MessageRef<'_>,
+    field: &FieldRef<'_>,
+    value: &mut T,
+    depth: usize,
+) -> Result<bool, DecodeError> {
+    match E::merge(field, value, depth) {
+        Some(merged) => merged.map(|()| true).map_err(|e| locate(e, message, field)),
+        None => Ok(false),
+    }
+}
+
+/// see `read_singular`
+pub fn read_optional<E: Encoding<T>, T>(
+    message: MessageRef<'_>,
+    field: &FieldRef<'_>,
+    value: &mut Option<T>,
+    depth: usize,
+) -> Result<bool, DecodeError> {
+    if let Some(value) = value {
+        return read_singular::<E, T>(message, field, value, depth);
+    }
+    let Some(read) = read::<E, T>(message, field, depth)? else {
+        return Ok(false);
+    };
+    *value = Some(read);
+    Ok(true)
+}
+
+/// appends the value of `field` to `values`, or the values packed into it, returning
+/// whether `E` could hold them. Packed values `E` can't hold are added to `unknown` as
+/// unpacked varints, as protobuf does, or dropped if there is no `unknown`
+pub fn read_repeated<E: Encoding<T>, T>(
+    message: MessageRef<'_>,
+    field: &FieldRef<'_>,
+    values: &mut Vec<T>,
+    unknown: Option<&mut message::Message>,
+    depth: usize,
+) -> Result<bool, DecodeError> {
+    if let Some(read) = read::<E, T>(message, field, depth)? {
+        values.push(read);
+        return Ok(true);
+    }
+    let mut rejected = Vec::new();
+    let Some(packed) = field
+        .as_len()
+        .and_then(|len| E::read_packed(len, &mut rejected))
+    else {
+        return Ok(false);
+    };
+    values.extend(packed.map_err(|e| locate(e, message, field))?);
+    if let Some(unknown) = unknown {
+        for value in rejected {
+            let varint = MessageObject::Varint(Varint::new(value));
+            unknown.push(Field::new(field.get_field_id(), varint));
+        }
+    }
+    Ok(true)
+}
+
+/// reads `field` into `value` if it is one of the fields of the oneof `O`, returning
+/// whether it is
+pub fn read_oneof<O: ProtoOneof>(
+    message: MessageRef<'_>,
+    field: &FieldRef<'_>,
+    value: &mut Option<O>,
+    depth: usize,
+) -> Result<bool, DecodeError> {
+    O::merge(value, message, field, depth)
+}
+
+/// reads `field` into `value` as the variant `variant` of a oneof, for `ProtoOneof::merge`.
+/// If `value` already holds that variant `field` is merged into it, like `read_singular`,
+/// otherwise `field` replaces it as a new `variant`. `get` is the value of `variant`, if
+/// `value` holds it
+pub fn read_variant<E: Encoding<T>, T, O>(
+    message: MessageRef<'_>,
+    field: &FieldRef<'_>,
+    value: &mut Option<O>,
+    get: impl FnOnce(&mut O) -> Option<&mut T>,
+    variant: impl FnOnce(T) -> O,
+    depth: usize,
+) -> Result<bool, DecodeError> {
+    if let Some(current) = value.as_mut().and_then(get) {
+        return read_singular::<E, T>(message, field, current, depth);
+    }
+    let Some(read) = read::<E, T>(message, field, depth)? else {
+        return Ok(false);
+    };
+    *value = Some(variant(read));
+    Ok(true)
+}
+
+/// appends `field` to `unknown` as it is
+pub fn read_unknown(field: &FieldRef<'_>, unknown: &mut message::Message) {
+    unknown.0.get_mut().extend_from_slice(field.as_bytes());
+}
+
+/// the depth of a message held by a field at `depth`, failing if that is deeper than
+/// `DecodeOptions::max_depth` allows
+fn nested(depth: usize) -> Result<usize, DecodeError> {
+    group::Group::check_depth(&DecodeOptions::default(), depth + 1)?;
+    Ok(depth + 1)
+}
+
+/// `e`, at an offset within the payload of `field`, at its offset within `message`
+fn locate(e: DecodeError, message: MessageRef<'_>, field: &FieldRef<'_>) -> DecodeError {
+    let payload = match field.as_len() {
+        Some(len) => range_of(message.0, len.inner).start,
+        None => range_of(message.0, field.raw).start + field.tag.0.len(),
+    };
+    e.shift(payload).within(field.get_field_id())
+}
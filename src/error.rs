@@ -0,0 +1,182 @@
+use alloc::vec::Vec;
+
+/// The category of failure encountered while decoding wire data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// the buffer ended before a byte without the continuation bit was found
+    TruncatedVarint,
+    /// a varint continued past the 10 bytes needed to encode a u64
+    OverlongVarint,
+    /// a length prefix claims more bytes than remain in the buffer
+    LengthOverrun { length: u64, available: usize },
+    /// a fixed width value (I32 or I64) has fewer bytes remaining than it needs
+    TruncatedFixed { expected: usize, available: usize },
+    /// a tag uses one of the unassigned wire types 6 or 7
+    InvalidWireType(u64),
+    /// a group was closed by an end-group tag for a different field id
+    MismatchedEndGroup { expected: u64, found: u64 },
+    /// an end-group tag appeared without a matching start-group
+    UnexpectedEndGroup(u64),
+    /// the buffer ended before the end-group tag of an open group
+    UnterminatedGroup(u64),
+    /// groups or messages were nested deeper than `DecodeOptions::max_depth`
+    DepthLimitExceeded { limit: usize },
+    /// a message was larger than `DecodeOptions::max_message_size`
+    MessageTooLarge { size: usize, limit: usize },
+    /// a message or group had more fields than `DecodeOptions::max_fields`
+    TooManyFields { limit: usize },
+    /// a length prefix was larger than `DecodeOptions::max_len_size`
+    LenTooLarge { length: u64, limit: usize },
+    /// (strict) a varint was encoded with more bytes than its value needs
+    NonCanonicalVarint,
+    /// (strict) the 10th byte of a varint sets bits above bit 63
+    VarintOverflow,
+    /// (strict) a tag carried a field id that protobuf does not allow
+    InvalidFieldId(FieldIdError),
+    /// a `Len` read as a string held invalid UTF-8
+    InvalidUtf8,
+}
+
+/// Why a field id is not valid in strict mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldIdError {
+    /// field ids start at 1
+    Zero,
+    /// field ids must fit in 29 bits
+    TooLarge(u64),
+    /// 19000 through 19999 are reserved for the protobuf implementation
+    Reserved(u64),
+}
+
+/// An error raised while decoding wire data
+///
+/// Besides the kind of failure, a `DecodeError` records the byte offset at which the
+/// failing element starts (relative to the buffer handed to the outermost parser) and
+/// the path of field ids that were being decoded when it happened, outermost first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    kind: DecodeErrorKind,
+    offset: usize,
+    path: Vec<u64>,
+}
+
+impl DecodeError {
+    pub fn new(kind: DecodeErrorKind, offset: usize) -> Self {
+        Self {
+            kind,
+            offset,
+            path: Vec::new(),
+        }
+    }
+
+    pub fn kind(&self) -> &DecodeErrorKind {
+        &self.kind
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn path(&self) -> &[u64] {
+        &self.path
+    }
+
+    /// moves the error `by` bytes further into the buffer, used by parsers to translate
+    /// errors from a nested parser (which only sees a suffix of the buffer) into their own
+    /// frame of reference
+    pub(crate) fn shift(mut self, by: usize) -> Self {
+        self.offset += by;
+        self
+    }
+
+    /// records that the error happened while decoding the value of `field_id`
+    pub(crate) fn within(mut self, field_id: u64) -> Self {
+        self.path.insert(0, field_id);
+        self
+    }
+}
+
+impl core::fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TruncatedVarint => write!(f, "varint has no terminating byte"),
+            Self::OverlongVarint => write!(f, "varint is longer than 10 bytes"),
+            Self::LengthOverrun { length, available } => write!(
+                f,
+                "length {length} overruns buffer with {available} bytes remaining"
+            ),
+            Self::TruncatedFixed {
+                expected,
+                available,
+            } => write!(
+                f,
+                "fixed width value needs {expected} bytes but only {available} remain"
+            ),
+            Self::InvalidWireType(wire_type) => write!(f, "invalid wire type {wire_type}"),
+            Self::MismatchedEndGroup { expected, found } => write!(
+                f,
+                "group {expected} closed by end-group tag for field {found}"
+            ),
+            Self::UnexpectedEndGroup(field_id) => {
+                write!(f, "end-group tag for field {field_id} has no open group")
+            }
+            Self::UnterminatedGroup(field_id) => {
+                write!(f, "group {field_id} has no end-group tag")
+            }
+            Self::DepthLimitExceeded { limit } => {
+                write!(f, "nesting is deeper than the limit of {limit}")
+            }
+            Self::MessageTooLarge { size, limit } => write!(
+                f,
+                "message of {size} bytes exceeds the limit of {limit} bytes"
+            ),
+            Self::TooManyFields { limit } => {
+                write!(f, "more than the limit of {limit} fields")
+            }
+            Self::LenTooLarge { length, limit } => {
+                write!(f, "length {length} exceeds the limit of {limit} bytes")
+            }
+            Self::NonCanonicalVarint => write!(f, "varint is not minimally encoded"),
+            Self::VarintOverflow => write!(f, "varint overflows 64 bits"),
+            Self::InvalidFieldId(e) => write!(f, "{e}"),
+            Self::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+        }
+    }
+}
+
+impl core::fmt::Display for FieldIdError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Zero => write!(f, "field id 0 is not allowed"),
+            Self::TooLarge(field_id) => {
+                write!(f, "field id {field_id} is larger than 2^29 - 1")
+            }
+            Self::Reserved(field_id) => {
+                write!(
+                    f,
+                    "field id {field_id} is in the reserved range 19000-19999"
+                )
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)?;
+        if !self.path.is_empty() {
+            write!(f, " in field ")?;
+            for (index, field_id) in self.path.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ".")?;
+                }
+                write!(f, "{field_id}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+impl core::error::Error for FieldIdError {}
@@ -1,3 +1,6 @@
+use crate::decode_options::DecodeOptions;
+use crate::error::{DecodeError, DecodeErrorKind, FieldIdError};
+use crate::field_span::FieldSpan;
 use crate::group::Group;
 use crate::i32::I32;
 use crate::i64::I64;
@@ -6,89 +9,143 @@ use crate::message_object::MessageObject;
 use crate::varint::Varint;
 use crate::wire_data::WireData;
 
-use anyhow::{anyhow, Context, Result};
-
 pub struct Field {
     pub(crate) tag: Varint,
     pub(crate) data: MessageObject,
+    /// where the field was parsed from, `None` for fields built in code
+    pub(crate) span: Option<FieldSpan>,
 }
 
 impl Field {
+    pub const MAX_FIELD_ID: u64 = (1 << 29) - 1;
+
+    /// a field of id `id`, encoded as is even if protobuf doesn't allow it, see
+    /// `new_strict`. Panics if the id is 2^61 or above, which doesn't fit in a tag
     pub fn new(id: u64, object: MessageObject) -> Self {
+        assert!(id <= u64::MAX >> 3, "field id {id} doesn't fit in a tag");
         let tag = (id << 3) | (object.wire_type() & 0b0000_0111);
 
         Self {
             tag: Varint::new(tag),
             data: object,
+            span: None,
         }
     }
-    pub fn from(data: WireData) -> Result<(Self, WireData)> {
-        let (tag, remainder) = Varint::from(data).context("Field must start with a Varint")?;
+
+    /// like `new`, but rejects the field ids protobuf doesn't allow, see
+    /// `validate_field_id`, which also keeps them from being truncated
+    pub fn new_strict(id: u64, object: MessageObject) -> Result<Self, FieldIdError> {
+        Self::validate_field_id(id)?;
+
+        Ok(Self::new(id, object))
+    }
+
+    pub fn validate_field_id(id: u64) -> Result<(), FieldIdError> {
+        match id {
+            0 => Err(FieldIdError::Zero),
+            19000..=19999 => Err(FieldIdError::Reserved(id)),
+            id if id > Self::MAX_FIELD_ID => Err(FieldIdError::TooLarge(id)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn from(data: WireData) -> Result<(Self, WireData), DecodeError> {
+        Self::from_with_options(data, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        data: WireData,
+        options: &DecodeOptions,
+    ) -> Result<(Self, WireData), DecodeError> {
+        Self::parse(data, options, 0, 0)
+    }
+
+    /// `depth` is the number of groups enclosing the field being parsed, and `offset` the
+    /// absolute offset of `data` in the outermost buffer, used for the field's span
+    pub(crate) fn parse(
+        data: WireData,
+        options: &DecodeOptions,
+        depth: usize,
+        offset: usize,
+    ) -> Result<(Self, WireData), DecodeError> {
+        let available = data.len();
+        let (tag, remainder) = Varint::from_with_options(data, options)?;
         let tag_value = tag.get();
-        match Self::wire_type_from_tag(tag_value) {
-            0 => Varint::from(remainder)
-                .map(|(value, remainder)| {
-                    (
-                        Self {
-                            tag,
-                            data: MessageObject::Varint(value),
-                        },
-                        remainder,
-                    )
-                })
-                .context("Field could not parse Varint"),
+        let field_id = Self::field_id_from_tag(tag_value);
+        Self::check_tag(tag_value, options, depth)?;
+
+        // errors from the value parsers are relative to the end of the tag
+        let value_offset = tag.0.len();
+        let (data, remainder) = match Self::wire_type_from_tag(tag_value) {
+            0 => Varint::from_with_options(remainder, options)
+                .map(|(value, remainder)| (MessageObject::Varint(value), remainder)),
             1 => I64::from(remainder)
-                .map(|(value, remainder)| {
-                    (
-                        Self {
-                            tag,
-                            data: MessageObject::I64(value),
-                        },
-                        remainder,
-                    )
-                })
-                .context("Field could not parse I64"),
-            2 => Len::from(remainder)
-                .map(|(value, remainder)| {
-                    (
-                        Self {
-                            tag,
-                            data: MessageObject::Len(value),
-                        },
-                        remainder,
-                    )
-                })
-                .context("Field could not parse Len"),
-            3 => Group::from(Self::field_id_from_tag(tag_value), remainder)
-                .map(|(value, remainder)| {
-                    (
-                        Self {
-                            tag,
-                            data: MessageObject::Group(value),
-                        },
-                        remainder,
-                    )
-                })
-                .context("Field could not parse Group"),
-            4 => Ok((
-                Self {
-                    tag,
-                    data: MessageObject::EGroup,
-                },
+                .map(|(value, remainder)| (MessageObject::I64(value), remainder)),
+            2 => Len::from_with_options(remainder, options)
+                .map(|(value, remainder)| (MessageObject::Len(value), remainder)),
+            3 => Group::parse(
+                field_id,
                 remainder,
-            )),
+                options,
+                depth + 1,
+                offset + value_offset,
+            )
+            .map(|(value, remainder)| (MessageObject::Group(value), remainder)),
+            4 => Ok((MessageObject::EGroup, remainder)),
             5 => I32::from(remainder)
-                .map(|(value, remainder)| {
-                    (
-                        Self {
-                            tag,
-                            data: MessageObject::I32(value),
-                        },
-                        remainder,
-                    )
-                })
-                .context("Field could not parse I32"),
-            _ => Err(anyhow!("Invalid wire type")),
+                .map(|(value, remainder)| (MessageObject::I32(value), remainder)),
+            _ => unreachable!("wire type was validated by check_tag"),
+        }
+        .map_err(|e| e.shift(value_offset).within(field_id))?;
+
+        let end = offset + available - remainder.len();
+        let tag_end = offset + value_offset;
+        let (header_end, value_end) = match &data {
+            MessageObject::Len(len) => (tag_end + len.length.0.len(), end),
+            MessageObject::Group(group) => (tag_end, end - group.end_field_id.0.len()),
+            _ => (tag_end, end),
+        };
+        let span = FieldSpan {
+            tag: offset..tag_end,
+            header: offset..header_end,
+            value: header_end..value_end,
+            range: offset..end,
+        };
+
+        Ok((
+            Self {
+                tag,
+                data,
+                span: Some(span),
+            },
+            remainder,
+        ))
+    }
+
+    /// checks applied to every tag before its value is parsed, shared with `FieldRef`
+    pub(crate) fn check_tag(
+        tag: u64,
+        options: &DecodeOptions,
+        depth: usize,
+    ) -> Result<(), DecodeError> {
+        let field_id = Self::field_id_from_tag(tag);
+        if options.strict {
+            Self::validate_field_id(field_id)
+                .map_err(|e| DecodeError::new(DecodeErrorKind::InvalidFieldId(e), 0))?;
+        }
+
+        match Self::wire_type_from_tag(tag) {
+            // only a group knows whether an end-group closes it, so outside of one it can
+            // never be valid
+            4 if depth == 0 && !options.lenient_end_groups => Err(DecodeError::new(
+                DecodeErrorKind::UnexpectedEndGroup(field_id),
+                0,
+            )),
+            6 | 7 => Err(DecodeError::new(
+                DecodeErrorKind::InvalidWireType(Self::wire_type_from_tag(tag)),
+                0,
+            )),
+            _ => Ok(()),
         }
     }
 
@@ -170,7 +227,18 @@ impl Field {
         Self::wire_type_from_tag(self.tag.get())
     }
 
+    /// where the field was parsed from, see `FieldSpan`. Fields built in code have no span,
+    /// and the span of a parsed field is not updated when it is modified
+    pub fn span(&self) -> Option<&FieldSpan> {
+        self.span.as_ref()
+    }
+
+    /// like `new`, panics unless `field_id` is below 2^61 to fit in the tag
     pub fn set_tag(&mut self, field_id: u64, wire_type: u64) {
+        assert!(
+            field_id <= u64::MAX >> 3,
+            "field id {field_id} doesn't fit in a tag"
+        );
         self.tag.set(field_id << 3 | wire_type);
     }
 
@@ -202,7 +270,7 @@ impl Field {
         WireData::Mut(dest)
     }
 
-    pub(crate) fn serialize_into(self, dest: &mut bytes::BytesMut) {
+    pub(crate) fn serialize_into(&self, dest: &mut bytes::BytesMut) {
         dest.extend_from_slice(self.tag.0.as_ref());
         self.data.serialize_into(dest);
     }
@@ -0,0 +1,298 @@
+use alloc::vec::Vec;
+
+use crate::borrowed::{FieldRef, MessageObjectRef, MessageRef};
+use crate::decode_options::DecodeOptions;
+use crate::error::DecodeError;
+
+/// One step of a `FieldPath`: a field id, optionally narrowed to one of its occurrences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathSegment {
+    pub field_id: u64,
+    /// which occurrence of a repeated field to follow, counting from 0. When `None` the
+    /// last occurrence is followed by single-value lookups and every occurrence by
+    /// `get_all_path`
+    pub index: Option<usize>,
+}
+
+impl PathSegment {
+    pub fn new(field_id: u64) -> Self {
+        Self {
+            field_id,
+            index: None,
+        }
+    }
+
+    pub fn with_index(field_id: u64, index: usize) -> Self {
+        Self {
+            field_id,
+            index: Some(index),
+        }
+    }
+}
+
+impl From<u64> for PathSegment {
+    fn from(field_id: u64) -> Self {
+        Self::new(field_id)
+    }
+}
+
+/// A path of field ids through nested messages and groups
+///
+/// Paths can be built from a slice of field ids, e.g. `&[3, 61, 1]`, or parsed from text
+/// where segments are separated by `.` and may pick an occurrence of a repeated field with
+/// `[index]`, e.g. `"3.61[2].1"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldPath(Vec<PathSegment>);
+
+impl FieldPath {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn push(&mut self, segment: impl Into<PathSegment>) {
+        self.0.push(segment.into());
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<PathSegment>> for FieldPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self(segments)
+    }
+}
+
+impl From<&[u64]> for FieldPath {
+    fn from(field_ids: &[u64]) -> Self {
+        Self(field_ids.iter().copied().map(PathSegment::new).collect())
+    }
+}
+
+impl<const N: usize> From<&[u64; N]> for FieldPath {
+    fn from(field_ids: &[u64; N]) -> Self {
+        Self::from(field_ids.as_slice())
+    }
+}
+
+impl From<&FieldPath> for FieldPath {
+    fn from(path: &FieldPath) -> Self {
+        path.clone()
+    }
+}
+
+impl core::str::FromStr for FieldPath {
+    type Err = ParseFieldPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut offset = 0;
+        for part in s.split('.') {
+            let error = ParseFieldPathError { offset };
+            let (field_id, index) = match part.split_once('[') {
+                Some((field_id, index)) => {
+                    let index = index.strip_suffix(']').ok_or(error)?;
+                    (field_id, Some(index.parse().map_err(|_| error)?))
+                }
+                None => (part, None),
+            };
+            segments.push(PathSegment {
+                field_id: field_id.parse().map_err(|_| error)?,
+                index,
+            });
+            offset += part.len() + 1;
+        }
+
+        Ok(Self(segments))
+    }
+}
+
+impl core::fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (position, segment) in self.0.iter().enumerate() {
+            if position > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", segment.field_id)?;
+            if let Some(index) = segment.index {
+                write!(f, "[{index}]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The segment of a textual field path starting at `offset` could not be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseFieldPathError {
+    pub offset: usize,
+}
+
+impl core::fmt::Display for ParseFieldPathError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid field path segment at character {}", self.offset)
+    }
+}
+
+impl core::error::Error for ParseFieldPathError {}
+
+/// An error raised while editing a message through a `FieldPath`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// the data along the path could not be decoded
+    Decode(DecodeError),
+    /// editing needs at least one field id to address
+    Empty,
+    /// the segment at `depth` passes through a field which is neither a message nor a group
+    NotAContainer { depth: usize },
+    /// the segment at `depth` selects an occurrence of its field which doesn't exist
+    IndexOutOfRange { depth: usize, index: usize },
+}
+
+impl From<DecodeError> for PathError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl core::fmt::Display for PathError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "{e}"),
+            Self::Empty => write!(f, "field path is empty"),
+            Self::NotAContainer { depth } => write!(
+                f,
+                "segment {depth} of the path is neither a message nor a group"
+            ),
+            Self::IndexOutOfRange { depth, index } => write!(
+                f,
+                "segment {depth} of the path selects missing occurrence {index}"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for PathError {}
+
+impl<'a> MessageRef<'a> {
+    /// the field at the end of `path`, descending through both `Len` submessages and
+    /// groups. Segments without an index follow the last occurrence of their field, so
+    /// this matches `get` for a path of one field id
+    pub fn get_path(
+        &self,
+        path: impl Into<FieldPath>,
+    ) -> Result<Option<FieldRef<'a>>, DecodeError> {
+        let path = path.into();
+        let mut found = Vec::new();
+        self.walk(self.0, path.segments(), false, &mut found)?;
+
+        Ok(found.pop())
+    }
+
+    /// every field matching `path`, where segments without an index match every
+    /// occurrence of their field, at every level of the path
+    pub fn get_all_path(
+        &self,
+        path: impl Into<FieldPath>,
+    ) -> Result<Vec<FieldRef<'a>>, DecodeError> {
+        let path = path.into();
+        let mut found = Vec::new();
+        self.walk(self.0, path.segments(), true, &mut found)?;
+
+        Ok(found)
+    }
+
+    /// appends the fields of this message matching `path` to `found`, `root` being the
+    /// outermost message so errors can be reported relative to it
+    fn walk(
+        &self,
+        root: &'a [u8],
+        path: &[PathSegment],
+        all: bool,
+        found: &mut Vec<FieldRef<'a>>,
+    ) -> Result<(), DecodeError> {
+        let Some((segment, rest)) = path.split_first() else {
+            return Ok(());
+        };
+
+        let base = self.0.as_ptr() as usize - root.as_ptr() as usize;
+        let mut matches = Vec::new();
+        for field in self.into_try_iter_with_options(&DecodeOptions::default()) {
+            let field = field.map_err(|e| e.shift(base))?;
+            if field.get_field_id() == segment.field_id {
+                matches.push(field);
+            }
+        }
+        let matches = select(matches, segment, all);
+
+        if rest.is_empty() {
+            found.extend(matches);
+            return Ok(());
+        }
+
+        for field in matches {
+            MessageRef::descend(root, field, rest, all, found)?;
+        }
+        Ok(())
+    }
+
+    /// continues the walk for `rest` inside `field`, if it is a message or a group
+    fn descend(
+        root: &'a [u8],
+        field: FieldRef<'a>,
+        rest: &[PathSegment],
+        all: bool,
+        found: &mut Vec<FieldRef<'a>>,
+    ) -> Result<(), DecodeError> {
+        let field_id = field.get_field_id();
+        match field.data {
+            MessageObjectRef::Len(len) => len
+                .into_message()
+                .walk(root, rest, all, found)
+                .map_err(|e| e.within(field_id)),
+            MessageObjectRef::Group(group) => {
+                let segment = &rest[0];
+                let matches = group
+                    .take_fields()
+                    .into_iter()
+                    .filter(|field| field.get_field_id() == segment.field_id)
+                    .collect();
+                let matches = select(matches, segment, all);
+                if rest.len() == 1 {
+                    found.extend(matches);
+                    return Ok(());
+                }
+                for field in matches {
+                    MessageRef::descend(root, field, &rest[1..], all, found)
+                        .map_err(|e| e.within(field_id))?;
+                }
+                Ok(())
+            }
+            // scalars have nothing beneath them, so the path doesn't match
+            _ => Ok(()),
+        }
+    }
+}
+
+/// narrows the occurrences of a field down to the ones `segment` follows
+fn select<'a>(
+    mut matches: Vec<FieldRef<'a>>,
+    segment: &PathSegment,
+    all: bool,
+) -> Vec<FieldRef<'a>> {
+    match segment.index {
+        Some(index) if index < matches.len() => Vec::from([matches.swap_remove(index)]),
+        Some(_) => Vec::new(),
+        None if all => matches,
+        None => matches.pop().into_iter().collect(),
+    }
+}
@@ -0,0 +1,31 @@
+use core::ops::Range;
+
+/// Where the parts of a parsed field sat in the buffer it was parsed from
+///
+/// Offsets are absolute, relative to the start of the buffer handed to the outermost
+/// parser, so the fields of a group share a frame of reference with the group itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldSpan {
+    /// the tag
+    pub tag: Range<usize>,
+    /// the tag and, for a `Len` field, its length prefix
+    pub header: Range<usize>,
+    /// the value after the header: the payload of a `Len`, the fields of a group without
+    /// its end-group tag, or the encoded varint or fixed width value
+    pub value: Range<usize>,
+    /// the whole field, from the tag to the end of its value (or end-group tag)
+    pub range: Range<usize>,
+}
+
+impl FieldSpan {
+    /// the span moved `by` bytes further into the buffer
+    pub fn shift(&self, by: usize) -> Self {
+        let shift = |range: &Range<usize>| range.start + by..range.end + by;
+        Self {
+            tag: shift(&self.tag),
+            header: shift(&self.header),
+            value: shift(&self.value),
+            range: shift(&self.range),
+        }
+    }
+}
@@ -1,10 +1,13 @@
+use alloc::vec::Vec;
+
+use crate::decode_options::DecodeOptions;
+use crate::error::{DecodeError, DecodeErrorKind};
 use crate::field::Field;
+use crate::message::Message;
 use crate::message_object::MessageObject;
 use crate::varint::Varint;
 use crate::wire_data::WireData;
 
-use anyhow::Result;
-
 pub struct Group {
     pub(crate) end_field_id: Varint,
     pub(crate) fields: Vec<Field>,
@@ -25,20 +28,56 @@ impl Group {
         }
     }
 
-    pub fn from(field_id: u64, mut data: WireData) -> Result<(Self, WireData)> {
+    /// a group holding the fields of `m`, failing if they can't be decoded
+    pub fn from_message(field_id: u64, m: Message) -> Result<Self, DecodeError> {
+        let mut group = Self::new(field_id);
+        for field in m.into_try_iter() {
+            group.push(field?);
+        }
+        Ok(group)
+    }
+
+    pub fn from(field_id: u64, data: WireData) -> Result<(Self, WireData), DecodeError> {
+        Self::from_with_options(field_id, data, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        field_id: u64,
+        data: WireData,
+        options: &DecodeOptions,
+    ) -> Result<(Self, WireData), DecodeError> {
+        Self::parse(field_id, data, options, 1, 0)
+    }
+
+    /// `depth` is the nesting level of this group, 1 for a group which is not inside
+    /// another group. Recursion happens through `Field::parse`, so bounding `depth` here
+    /// bounds the stack used by hostile input. `base` is the absolute offset of `data`,
+    /// giving the fields of the group absolute spans
+    pub(crate) fn parse(
+        field_id: u64,
+        mut data: WireData,
+        options: &DecodeOptions,
+        depth: usize,
+        base: usize,
+    ) -> Result<(Self, WireData), DecodeError> {
+        Self::check_depth(options, depth)?;
+
         let mut fields = Vec::new();
+        // bytes consumed so far, so errors from nested fields are relative to the group start
+        let mut offset = 0;
         loop {
-            let (field, remainder) = Field::from(data)?;
+            if data.is_empty() {
+                return Err(Self::unterminated(field_id, offset));
+            }
+
+            let start = offset;
+            let before = data.len();
+            let (field, remainder) =
+                Field::parse(data, options, depth, base + start).map_err(|e| e.shift(start))?;
+            offset += before - remainder.len();
+            data = remainder;
             match field.get_data() {
-                // in well-formed proto it would be impossible to find an EGroup for another
-                // field id, as we are using `Field::from` on each field, which would parse
-                // the entirety of a nested group (including its EGroup), however because
-                // malformed proto could have mismatched EGroups we must verify so the error
-                // we raise can be more central to the actual issue in the data rather than
-                // raising a red-herring elsewhere when something else randomly fails
                 MessageObject::EGroup if field.get_field_id() == field_id => {
-                    data = remainder;
-
                     return Ok((
                         Self {
                             end_field_id: field.tag,
@@ -47,14 +86,65 @@ impl Group {
                         data,
                     ));
                 }
+                // in well-formed proto it would be impossible to find an EGroup for another
+                // field id, as we are using `Field::parse` on each field, which would parse
+                // the entirety of a nested group (including its EGroup), so this can only be
+                // malformed data. Reporting it here keeps the error central to the actual
+                // issue rather than raising a red-herring elsewhere when something else fails
+                MessageObject::EGroup if !options.lenient_end_groups => {
+                    return Err(Self::mismatched(field_id, field.get_field_id(), start));
+                }
                 _ => {
+                    Self::check_field_count(fields.len(), options, start)?;
                     fields.push(field);
-                    data = remainder;
                 }
             }
         }
     }
 
+    // the checks below are shared with `GroupRef::parse`
+
+    pub(crate) fn check_depth(options: &DecodeOptions, depth: usize) -> Result<(), DecodeError> {
+        if depth > options.max_depth {
+            Err(DecodeError::new(
+                DecodeErrorKind::DepthLimitExceeded {
+                    limit: options.max_depth,
+                },
+                0,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn check_field_count(
+        count: usize,
+        options: &DecodeOptions,
+        offset: usize,
+    ) -> Result<(), DecodeError> {
+        if count >= options.max_fields {
+            Err(DecodeError::new(
+                DecodeErrorKind::TooManyFields {
+                    limit: options.max_fields,
+                },
+                offset,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn unterminated(field_id: u64, offset: usize) -> DecodeError {
+        DecodeError::new(DecodeErrorKind::UnterminatedGroup(field_id), offset)
+    }
+
+    pub(crate) fn mismatched(expected: u64, found: u64, offset: usize) -> DecodeError {
+        DecodeError::new(
+            DecodeErrorKind::MismatchedEndGroup { expected, found },
+            offset,
+        )
+    }
+
     pub fn get_fields(&self) -> &[Field] {
         &self.fields
     }
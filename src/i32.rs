@@ -1,10 +1,9 @@
+use crate::error::{DecodeError, DecodeErrorKind};
 use crate::wire_data::WireData;
 
-use anyhow::{anyhow, Result};
-
 pub struct I32(pub(crate) WireData);
 
-impl std::default::Default for I32 {
+impl core::default::Default for I32 {
     fn default() -> Self {
         Self::new(i32::default())
     }
@@ -29,12 +28,23 @@ impl I32 {
         result
     }
 
-    pub fn from(mut data: WireData) -> Result<(Self, WireData)> {
-        if data.len() < 4 {
-            Err(anyhow!("I32 must be 4 bytes"))
+    pub fn from(mut data: WireData) -> Result<(Self, WireData), DecodeError> {
+        Self::check_len(data.len())?;
+        let remainder = data.split_off(4);
+        Ok((Self(data), remainder))
+    }
+
+    pub(crate) fn check_len(available: usize) -> Result<(), DecodeError> {
+        if available < 4 {
+            Err(DecodeError::new(
+                DecodeErrorKind::TruncatedFixed {
+                    expected: 4,
+                    available,
+                },
+                0,
+            ))
         } else {
-            let remainder = data.split_off(4);
-            Ok((Self(data), remainder))
+            Ok(())
         }
     }
 
@@ -1,10 +1,9 @@
+use crate::error::{DecodeError, DecodeErrorKind};
 use crate::wire_data::WireData;
 
-use anyhow::{anyhow, Result};
-
 pub struct I64(pub(crate) WireData);
 
-impl std::default::Default for I64 {
+impl core::default::Default for I64 {
     fn default() -> Self {
         Self::new(i64::default())
     }
@@ -29,12 +28,23 @@ impl I64 {
         result
     }
 
-    pub fn from(mut data: WireData) -> Result<(Self, WireData)> {
-        if data.len() < 8 {
-            Err(anyhow!("I64 must be 8 bytes"))
+    pub fn from(mut data: WireData) -> Result<(Self, WireData), DecodeError> {
+        Self::check_len(data.len())?;
+        let remainder = data.split_off(8);
+        Ok((Self(data), remainder))
+    }
+
+    pub(crate) fn check_len(available: usize) -> Result<(), DecodeError> {
+        if available < 8 {
+            Err(DecodeError::new(
+                DecodeErrorKind::TruncatedFixed {
+                    expected: 8,
+                    available,
+                },
+                0,
+            ))
         } else {
-            let remainder = data.split_off(8);
-            Ok((Self(data), remainder))
+            Ok(())
         }
     }
 
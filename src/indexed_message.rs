@@ -0,0 +1,163 @@
+use alloc::vec::Vec;
+use core::cell::OnceCell;
+use core::ops::Range;
+
+use hashbrown::HashMap;
+
+use crate::borrowed::{FieldRef, MessageRef};
+use crate::decode_options::DecodeOptions;
+use crate::error::DecodeError;
+use crate::field::Field;
+use crate::message::Message;
+
+/// A `Message` which remembers where each of its fields are
+///
+/// The first lookup makes a single pass over the message recording the byte range of
+/// every field, grouped by field id. Later lookups are a hash map lookup on the field id,
+/// O(1), and only parse the fields they return, so their cost no longer depends on the
+/// size of the message. The index is kept up to date by `push`, and dropped by any other
+/// mutation.
+pub struct IndexedMessage {
+    message: Message,
+    options: DecodeOptions,
+    index: OnceCell<HashMap<u64, Vec<Range<usize>>>>,
+}
+
+impl From<Message> for IndexedMessage {
+    fn from(message: Message) -> Self {
+        Self::new(message)
+    }
+}
+
+impl IndexedMessage {
+    pub fn new(message: Message) -> Self {
+        Self::new_with_options(message, &DecodeOptions::default())
+    }
+
+    pub fn new_with_options(message: Message, options: &DecodeOptions) -> Self {
+        Self {
+            message,
+            options: *options,
+            index: OnceCell::new(),
+        }
+    }
+
+    /// the last occurrence of `field_id`, following protobuf's last-one-wins semantics
+    pub fn get(&self, field_id: u64) -> Result<Option<FieldRef<'_>>, DecodeError> {
+        Ok(self.get_all(field_id)?.next_back())
+    }
+
+    pub fn first(&self, field_id: u64) -> Result<Option<FieldRef<'_>>, DecodeError> {
+        Ok(self.get_all(field_id)?.next())
+    }
+
+    /// every occurrence of `field_id`, in the order they appear in the message
+    pub fn get_all(&self, field_id: u64) -> Result<IndexedFieldIter<'_>, DecodeError> {
+        let ranges = match self.index()?.get(&field_id) {
+            Some(ranges) => ranges.as_slice(),
+            None => &[],
+        };
+
+        Ok(IndexedFieldIter {
+            data: self.message.0.as_ref(),
+            ranges: ranges.iter(),
+        })
+    }
+
+    pub fn contains(&self, field_id: u64) -> Result<bool, DecodeError> {
+        Ok(self.index()?.contains_key(&field_id))
+    }
+
+    /// appends a field to the message, adding it to the index if one has been built
+    pub fn push(&mut self, f: Field) {
+        let field_id = f.get_field_id();
+        let start = self.message.0.len();
+        self.message.push(f);
+        let end = self.message.0.len();
+
+        if let Some(index) = self.index.get_mut() {
+            index.entry(field_id).or_default().push(start..end);
+        }
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// mutable access to the underlying message, which discards the index as any
+    /// change could move the fields around
+    pub fn message_mut(&mut self) -> &mut Message {
+        self.invalidate();
+        &mut self.message
+    }
+
+    pub fn into_message(self) -> Message {
+        self.message
+    }
+
+    /// discard the index, it will be rebuilt by the next lookup
+    pub fn invalidate(&mut self) {
+        self.index.take();
+    }
+
+    fn index(&self) -> Result<&HashMap<u64, Vec<Range<usize>>>, DecodeError> {
+        if let Some(index) = self.index.get() {
+            return Ok(index);
+        }
+
+        let mut index: HashMap<u64, Vec<Range<usize>>> = HashMap::new();
+        let mut iter =
+            MessageRef(self.message.0.as_ref()).into_try_iter_with_options(&self.options);
+        loop {
+            let start = iter.offset();
+            let Some(field) = iter.next() else {
+                break;
+            };
+            index
+                .entry(field?.get_field_id())
+                .or_default()
+                .push(start..iter.offset());
+        }
+
+        Ok(self.index.get_or_init(|| index))
+    }
+}
+
+/// Iterator over the occurrences of one field id in an `IndexedMessage`
+pub struct IndexedFieldIter<'a> {
+    data: &'a [u8],
+    ranges: core::slice::Iter<'a, Range<usize>>,
+}
+
+impl<'a> IndexedFieldIter<'a> {
+    fn parse(&self, range: &Range<usize>) -> FieldRef<'a> {
+        // safety: every range in the index was either produced by successfully parsing the
+        // field or by serializing it in `push`, and the index is dropped whenever the data
+        // could change
+        FieldRef::from_with_options(&self.data[range.clone()], &DecodeOptions::trusted())
+            .unwrap()
+            .0
+    }
+}
+
+impl<'a> Iterator for IndexedFieldIter<'a> {
+    type Item = FieldRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.ranges.next()?;
+        Some(self.parse(range))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ranges.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IndexedFieldIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let range = self.ranges.next_back()?;
+        Some(self.parse(range))
+    }
+}
+
+impl ExactSizeIterator for IndexedFieldIter<'_> {}
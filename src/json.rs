@@ -0,0 +1,292 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// A parsed JSON value
+///
+/// Numbers keep the text they were written as, so 64-bit integers survive without going
+/// through a float, and objects keep their members in order, duplicates included.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// How deeply arrays and objects may be nested before parsing fails, bounding recursion
+const MAX_DEPTH: usize = 100;
+
+impl Json {
+    /// parses `text`, which must hold exactly one value, returning the byte offset of the
+    /// first problem on failure
+    pub(crate) fn parse(text: &str) -> Result<Self, usize> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            position: 0,
+        };
+        let value = parser.value(0)?;
+        parser.whitespace();
+        match parser.position == parser.text.len() {
+            true => Ok(value),
+            false => Err(parser.position),
+        }
+    }
+
+    /// writes the value compactly, without any whitespace
+    pub(crate) fn write(&self, out: &mut String) {
+        match self {
+            Self::Null => out.push_str("null"),
+            Self::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+            Self::Number(value) => out.push_str(value),
+            Self::String(value) => write_string(value, out),
+            Self::Array(values) => {
+                out.push('[');
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    value.write(out);
+                }
+                out.push(']');
+            }
+            Self::Object(members) => {
+                out.push('{');
+                for (index, (name, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    write_string(name, out);
+                    out.push(':');
+                    value.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), usize> {
+        match self.peek() == Some(byte) {
+            true => {
+                self.position += 1;
+                Ok(())
+            }
+            false => Err(self.position),
+        }
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Result<Json, usize> {
+        match self.text[self.position..].starts_with(literal.as_bytes()) {
+            true => {
+                self.position += literal.len();
+                Ok(value)
+            }
+            false => Err(self.position),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, usize> {
+        self.whitespace();
+        if depth > MAX_DEPTH {
+            return Err(self.position);
+        }
+        match self.peek() {
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b'[') => {
+                self.position += 1;
+                let mut values = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value(depth + 1)?);
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Json::Array(values));
+                        }
+                        _ => return Err(self.position),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut members = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.whitespace();
+                    let name = self.string()?;
+                    self.whitespace();
+                    self.expect(b':')?;
+                    members.push((name, self.value(depth + 1)?));
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(Json::Object(members));
+                        }
+                        _ => return Err(self.position),
+                    }
+                }
+            }
+            _ => Err(self.position),
+        }
+    }
+
+    /// a number, validated against the JSON grammar but kept as text
+    fn number(&mut self) -> Result<Json, usize> {
+        let start = self.position;
+        let digits = |parser: &mut Self| -> Result<(), usize> {
+            let first = parser.position;
+            while let Some(b'0'..=b'9') = parser.peek() {
+                parser.position += 1;
+            }
+            match parser.position > first {
+                true => Ok(()),
+                false => Err(parser.position),
+            }
+        };
+
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        match self.peek() {
+            // no leading zeros
+            Some(b'0') => self.position += 1,
+            _ => digits(self)?,
+        }
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            digits(self)?;
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.position += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.position += 1;
+            }
+            digits(self)?;
+        }
+
+        // the bytes matched are all ASCII
+        let text = core::str::from_utf8(&self.text[start..self.position]).unwrap_or_default();
+        Ok(Json::Number(text.into()))
+    }
+
+    fn string(&mut self) -> Result<String, usize> {
+        self.expect(b'"')?;
+        let mut value = Vec::new();
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(self.position);
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self.peek().ok_or(self.position)?;
+                    self.position += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.position - 1),
+                    };
+                    let mut buf = [0; 4];
+                    value.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                0..0x20 => return Err(self.position - 1),
+                byte => value.push(byte),
+            }
+        }
+        // the input was a str and escapes produce whole characters, so this is valid
+        String::from_utf8(value).map_err(|_| self.position)
+    }
+
+    /// the character of a `\uXXXX` escape, combining a surrogate pair into one
+    fn unicode_escape(&mut self) -> Result<char, usize> {
+        let start = self.position;
+        let high = self.hex4()?;
+        let code = match high {
+            0xd800..=0xdbff => {
+                self.expect(b'\\')?;
+                self.expect(b'u')?;
+                let low = self.hex4()?;
+                if !(0xdc00..=0xdfff).contains(&low) {
+                    return Err(start);
+                }
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+            }
+            _ => high,
+        };
+        char::from_u32(code).ok_or(start)
+    }
+
+    fn hex4(&mut self) -> Result<u32, usize> {
+        let digits = self
+            .text
+            .get(self.position..self.position + 4)
+            .ok_or(self.position)?;
+        let digits = core::str::from_utf8(digits).map_err(|_| self.position)?;
+        let value = u32::from_str_radix(digits, 16).map_err(|_| self.position)?;
+        self.position += 4;
+        Ok(value)
+    }
+}
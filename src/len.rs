@@ -1,5 +1,6 @@
-use anyhow::{anyhow, Context, Result};
-
+use crate::borrowed::{LenRef, VarintRef};
+use crate::decode_options::DecodeOptions;
+use crate::error::{DecodeError, DecodeErrorKind};
 use crate::message::Message;
 use crate::packed_repeated::{PackedRepeatedI32, PackedRepeatedI64, PackedRepeatedVarint};
 use crate::varint::Varint;
@@ -10,7 +11,7 @@ pub struct Len {
     pub(crate) inner: WireData,
 }
 
-impl std::default::Default for Len {
+impl core::default::Default for Len {
     fn default() -> Self {
         Self::new()
     }
@@ -31,6 +32,13 @@ impl Len {
         }
     }
 
+    pub fn new_bytes(data: &[u8]) -> Self {
+        Self {
+            length: Varint::new(data.len() as u64),
+            inner: WireData::Const(bytes::Bytes::copy_from_slice(data)),
+        }
+    }
+
     pub fn new_message(m: Message) -> Self {
         Self {
             length: Varint::new(m.0.len() as u64),
@@ -45,15 +53,18 @@ impl Len {
         }
     }
 
-    pub fn from(data: WireData) -> Result<(Self, WireData)> {
-        let (len, mut len_remainder) =
-            Varint::from(data).context("Len must start with a Varint")?;
+    pub fn from(data: WireData) -> Result<(Self, WireData), DecodeError> {
+        Self::from_with_options(data, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        data: WireData,
+        options: &DecodeOptions,
+    ) -> Result<(Self, WireData), DecodeError> {
+        let (len, mut len_remainder) = Varint::from_with_options(data, options)?;
 
         // need to split remainder on the given length
-        let split_len = len.get() as usize;
-        if split_len > len_remainder.len() {
-            return Err(anyhow!("Len message overruns buffer"));
-        }
+        let split_len = Self::check_length(len.get(), len_remainder.len(), options)?;
         let final_remainder = len_remainder.split_off(split_len);
         Ok((
             Self {
@@ -64,12 +75,45 @@ impl Len {
         ))
     }
 
+    /// validates a length prefix against the bytes available after it and the limits in
+    /// `options`, returning it as a usize
+    pub(crate) fn check_length(
+        length: u64,
+        available: usize,
+        options: &DecodeOptions,
+    ) -> Result<usize, DecodeError> {
+        if length > options.max_len_size as u64 {
+            Err(DecodeError::new(
+                DecodeErrorKind::LenTooLarge {
+                    length,
+                    limit: options.max_len_size,
+                },
+                0,
+            ))
+        } else if length > available as u64 {
+            Err(DecodeError::new(
+                DecodeErrorKind::LengthOverrun { length, available },
+                0,
+            ))
+        } else {
+            Ok(length as usize)
+        }
+    }
+
+    /// borrow the len as a `LenRef`, without copying or reference counting
+    pub fn view(&self) -> LenRef<'_> {
+        LenRef {
+            length: VarintRef(self.length.0.as_ref()),
+            inner: self.inner.as_ref(),
+        }
+    }
+
     pub fn get_data(&self) -> WireData {
         self.inner.clone()
     }
 
-    pub fn as_str(&self) -> Result<&str> {
-        std::str::from_utf8(self.inner.as_ref()).context("Invalid UTF-8")
+    pub fn as_str(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.inner.as_ref())
     }
 
     pub fn into_message(self) -> Message {
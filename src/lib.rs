@@ -1,24 +1,86 @@
 //! Library
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+mod annotate;
+mod base64;
+mod borrowed;
+mod classify;
+mod decode_options;
+mod descriptor;
+mod descriptor_pool;
+mod dynamic_message;
+pub mod encoding;
+mod error;
 mod field;
+mod field_path;
+mod field_span;
 mod group;
 mod i32;
 mod i64;
+mod indexed_message;
+mod json;
 mod len;
 mod message;
+mod message_edit;
 mod message_object;
+mod message_tree;
 mod packed_repeated;
+mod proto_json;
+mod proto_wire;
+mod protoscope_parser;
+mod protoscope_printer;
+#[cfg(feature = "serde")]
+mod serde_view;
+mod text_format;
+mod typed_fields;
 mod varint;
 mod wire_data;
 
+pub use annotate::{AnnotatedRange, Annotation, ByteRole};
+pub use borrowed::{
+    FieldIdIter, FieldRef, GroupRef, I32Ref, I64Ref, LenRef, MessageObjectRef, MessageRef,
+    MessageRefIter, MessageRefTryIter, PackedRepeatedI32Ref, PackedRepeatedI32RefIter,
+    PackedRepeatedI32RefTryIter, PackedRepeatedI64Ref, PackedRepeatedI64RefIter,
+    PackedRepeatedI64RefTryIter, PackedRepeatedVarintRef, PackedRepeatedVarintRefIter,
+    PackedRepeatedVarintRefTryIter, VarintRef,
+};
+pub use classify::{Confidence, RawField, RawMessage, RawValue};
+pub use decode_options::DecodeOptions;
+pub use descriptor::{
+    EnumDescriptor, EnumValueDescriptor, FieldDescriptor, FileDescriptor, Kind, Label,
+    MessageDescriptor, MethodDescriptor, OneofDescriptor, ServiceDescriptor, Syntax,
+};
+pub use descriptor_pool::{DescriptorError, DescriptorPool};
+pub use dynamic_message::{DynamicError, DynamicMessage, MapKey, Value};
+pub use error::{DecodeError, DecodeErrorKind, FieldIdError};
 pub use field::Field;
+pub use field_path::{FieldPath, ParseFieldPathError, PathError, PathSegment};
+pub use field_span::FieldSpan;
 pub use group::Group;
 pub use i32::I32;
 pub use i64::I64;
+pub use indexed_message::{IndexedFieldIter, IndexedMessage};
 pub use len::Len;
-pub use message::Message;
+pub use message::{Message, MessageIter, MessageSpanIter, MessageTryIter};
+pub use message_edit::EditError;
 pub use message_object::MessageObject;
-pub use packed_repeated::{PackedRepeatedI32, PackedRepeatedI64, PackedRepeatedVarint};
+pub use message_tree::{FieldNode, MessageTree, Node};
+pub use packed_repeated::{
+    PackedRepeatedI32, PackedRepeatedI32Iter, PackedRepeatedI32TryIter, PackedRepeatedI64,
+    PackedRepeatedI64Iter, PackedRepeatedI64TryIter, PackedRepeatedVarint,
+    PackedRepeatedVarintIter, PackedRepeatedVarintTryIter,
+};
+pub use proto_json::{JsonError, JsonOptions};
+pub use proto_wire::{ProtoEnum, ProtoOneof, ProtoWire};
+pub use protoscope_parser::{ProtoscopeError, ProtoscopeErrorKind};
+pub use protoscope_printer::{BytesFormat, PrintOptions, Protoscope};
+#[cfg(feature = "derive")]
+pub use protowire_derive::ProtoWire;
+#[cfg(feature = "serde")]
+pub use serde_view::{SerdeOptions, SerdeView};
+pub use text_format::{TextFormatError, TextFormatErrorKind};
 pub use varint::Varint;
 pub use wire_data::WireData;
 
@@ -252,6 +314,1775 @@ mod tests {
         assert_eq!(view.get_float(), -13.37);
     }
 
+    #[test]
+    fn test_decode_errors() {
+        // 1: {`61`} with a length prefix claiming 5 bytes
+        let data = WireData::new(vec![0b00001010, 0b00000101, 0b01100001]);
+        let error = Field::from(data).err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::LengthOverrun {
+                length: 5,
+                available: 1
+            }
+        );
+        assert_eq!(error.offset(), 1);
+        assert_eq!(error.path(), &[1]);
+
+        // tag for field 1 with wire type 6
+        let data = WireData::new(vec![0b00001110]);
+        let error = Field::from(data).err().unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::InvalidWireType(6));
+        assert_eq!(error.offset(), 0);
+        assert!(error.path().is_empty());
+
+        // 1: followed by a varint that never terminates
+        let data = WireData::new(vec![0b00001000, 0b10000000]);
+        let error = Field::from(data).err().unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::TruncatedVarint);
+        assert_eq!(error.offset(), 1);
+        assert_eq!(error.path(), &[1]);
+
+        let data = WireData::new(vec![0b10000000; 11]);
+        let error = Varint::from(data).err().unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::OverlongVarint);
+
+        // 2: !{ 3: !{ 1: <2 bytes of an i32> } }
+        let data = WireData::new(vec![
+            0b00010011, 0b00011011, 0b00001101, 0b00000001, 0b00000000,
+        ]);
+        let error = Field::from(data).err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::TruncatedFixed {
+                expected: 4,
+                available: 2
+            }
+        );
+        assert_eq!(error.offset(), 3);
+        assert_eq!(error.path(), &[2, 3, 1]);
+        assert_eq!(
+            error.to_string(),
+            "fixed width value needs 4 bytes but only 2 remain at byte 3 in field 2.3.1"
+        );
+
+        // 2: !{ 1: 1 with no end-group tag
+        let data = WireData::new(vec![0b00010011, 0b00001000, 0b00000001]);
+        let error = Field::from(data).err().unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::UnterminatedGroup(2));
+        assert_eq!(error.offset(), 3);
+        assert_eq!(error.path(), &[2]);
+    }
+
+    #[test]
+    fn test_try_iter() {
+        // 1: 1 followed by 2: {"abc"} with the final byte missing
+        let message = Len::from(WireData::new(vec![
+            0b00000110, 0b00001000, 0b00000001, 0b00010010, 0b00000011, 0b01100001, 0b01100010,
+        ]))
+        .unwrap()
+        .0
+        .into_message();
+        let mut iter = message.into_try_iter();
+        let field = iter.next().unwrap().unwrap();
+        assert_eq!(field.get_field_id(), 1);
+        assert_eq!(iter.offset(), 2);
+        let error = iter.next().unwrap().err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::LengthOverrun {
+                length: 3,
+                available: 2
+            }
+        );
+        assert_eq!(error.offset(), 3);
+        assert_eq!(error.path(), &[2]);
+        assert!(iter.next().is_none());
+        assert_eq!(
+            iter.into_remainder().as_ref(),
+            &[0b00010010, 0b00000011, 0b01100001, 0b01100010]
+        );
+
+        // packed i32 values 1 and 2 followed by two stray bytes
+        let mut packed = PackedRepeatedI32::new();
+        packed.push(I32::new(1));
+        packed.push(I32::new(2));
+        let mut len = Len::new();
+        len.set_packed_repeated_i32(packed);
+        len.inner.get_mut().extend_from_slice(&[0, 0]);
+        let mut iter = len.into_packed_repeated_i32().into_try_iter();
+        assert_eq!(iter.next().unwrap().unwrap().get(), 1);
+        assert_eq!(iter.next().unwrap().unwrap().get(), 2);
+        let error = iter.next().unwrap().err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::TruncatedFixed {
+                expected: 4,
+                available: 2
+            }
+        );
+        assert_eq!(error.offset(), 8);
+        assert!(iter.next().is_none());
+        assert_eq!(iter.into_remainder().len(), 2);
+    }
+
+    #[test]
+    fn test_decode_options() {
+        // 100,000 nested start-group tags for field 1 would overflow the stack if unbounded
+        let data = WireData::new(vec![0b00001011; 100_000]);
+        let error = Field::from(data).err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::DepthLimitExceeded { limit: 100 }
+        );
+        assert_eq!(error.offset(), 101);
+        assert_eq!(error.path().len(), 101);
+
+        let options = DecodeOptions {
+            max_depth: 1,
+            ..DecodeOptions::default()
+        };
+        // 1: !{ 1: !{} }
+        let data = WireData::new(vec![0b00001011, 0b00001011, 0b00001100, 0b00001100]);
+        assert!(Field::from(data.clone()).is_ok());
+        let error = Field::from_with_options(data, &options).err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::DepthLimitExceeded { limit: 1 }
+        );
+        assert_eq!(error.path(), &[1, 1]);
+
+        // 1: 1 2: 2 3: 3
+        let mut message = Message::new();
+        message.push(Field::new(1, MessageObject::Varint(Varint::new(1))));
+        message.push(Field::new(2, MessageObject::Varint(Varint::new(2))));
+        message.push(Field::new(3, MessageObject::Varint(Varint::new(3))));
+        let data = message.serialize();
+
+        let options = DecodeOptions {
+            max_fields: 2,
+            ..DecodeOptions::default()
+        };
+        let mut iter = Len::new_message(Message(data.clone()))
+            .into_message()
+            .into_try_iter_with_options(&options);
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_ok());
+        let error = iter.next().unwrap().err().unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::TooManyFields { limit: 2 });
+        assert_eq!(error.offset(), 4);
+
+        let options = DecodeOptions {
+            max_message_size: 5,
+            ..DecodeOptions::default()
+        };
+        let error = Message(data)
+            .into_try_iter_with_options(&options)
+            .next()
+            .unwrap()
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::MessageTooLarge { size: 6, limit: 5 }
+        );
+
+        let options = DecodeOptions {
+            max_len_size: 4,
+            ..DecodeOptions::default()
+        };
+        let data = Len::new_string("hello").length.0;
+        let mut data = data.into_mut();
+        data.get_mut().extend_from_slice(b"hello");
+        let error = Len::from_with_options(data, &options).err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::LenTooLarge {
+                length: 5,
+                limit: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let strict = DecodeOptions::strict();
+
+        // 1: long-form:1 0
+        let data = WireData::new(vec![0b00001000, 0b10000000, 0b00000000]);
+        let (field, remainder) = Field::from(data.clone()).unwrap();
+        assert!(remainder.is_empty());
+        assert_eq!(field.into_varint().unwrap().get(), 0);
+        let error = Field::from_with_options(data.clone(), &strict)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::NonCanonicalVarint);
+        assert_eq!(error.offset(), 1);
+        assert_eq!(error.path(), &[1]);
+
+        // relaxed mode keeps the original bytes
+        let (field, _) = Field::from(data.clone()).unwrap();
+        assert_eq!(field.serialize().as_ref(), data.as_ref());
+
+        // a 10 byte varint whose final byte sets bits above bit 63
+        let mut bytes = vec![0b11111111; 9];
+        bytes.push(0b01111111);
+        let (varint, _) = Varint::from(WireData::new(bytes.clone())).unwrap();
+        assert_eq!(varint.get(), u64::MAX);
+        let error = Varint::from_with_options(WireData::new(bytes), &strict)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::VarintOverflow);
+        assert!(Varint::from_with_options(Varint::new(u64::MAX).0, &strict).is_ok());
+
+        // field ids
+        for (field_id, expected) in [
+            (0, FieldIdError::Zero),
+            (19000, FieldIdError::Reserved(19000)),
+            (19999, FieldIdError::Reserved(19999)),
+            (1 << 29, FieldIdError::TooLarge(1 << 29)),
+        ] {
+            let field = Field::new(field_id, MessageObject::Varint(Varint::new(1)));
+            let data = field.serialize();
+            assert!(Field::from(data.clone()).is_ok());
+            let error = Field::from_with_options(data, &strict).err().unwrap();
+            assert_eq!(error.kind(), &DecodeErrorKind::InvalidFieldId(expected));
+            assert_eq!(error.offset(), 0);
+
+            let error = Field::new_strict(field_id, MessageObject::Varint(Varint::new(1)))
+                .err()
+                .unwrap();
+            assert_eq!(error, expected);
+        }
+        assert!(Field::new_strict(18999, MessageObject::Varint(Varint::new(1))).is_ok());
+        assert!(
+            Field::new_strict(Field::MAX_FIELD_ID, MessageObject::Varint(Varint::new(1))).is_ok()
+        );
+
+        // `new` takes any id which fits in a tag, and catches those which don't in debug
+        let largest = u64::MAX >> 3;
+        let field = Field::new(largest, MessageObject::Varint(Varint::new(1)));
+        assert_eq!(field.get_field_id(), largest);
+        assert_eq!(
+            Field::from(field.serialize()).unwrap().0.get_field_id(),
+            largest
+        );
+        let too_large = std::panic::catch_unwind(|| {
+            Field::new(largest + 1, MessageObject::Varint(Varint::new(1)))
+        });
+        assert!(too_large.is_err());
+        let too_large = std::panic::catch_unwind(|| {
+            let mut field = Field::new(1, MessageObject::Varint(Varint::new(1)));
+            field.set_tag(largest + 1, 0);
+        });
+        assert!(too_large.is_err());
+    }
+
+    #[test]
+    fn test_end_groups() {
+        let lenient = DecodeOptions {
+            lenient_end_groups: true,
+            ..DecodeOptions::default()
+        };
+
+        // 1: !{ 2:EGROUP }
+        let data = WireData::new(vec![0b00001011, 0b00010100, 0b00001100]);
+        let error = Field::from(data.clone()).err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::MismatchedEndGroup {
+                expected: 1,
+                found: 2
+            }
+        );
+        assert_eq!(error.offset(), 1);
+        assert_eq!(error.path(), &[1]);
+
+        let (field, remainder) = Field::from_with_options(data, &lenient).unwrap();
+        assert!(remainder.is_empty());
+        let group = field.into_group().unwrap();
+        assert_eq!(group.get_fields().len(), 1);
+        assert_eq!(group.get_fields()[0].get_field_id(), 2);
+        assert!(matches!(
+            group.get_fields()[0].get_data(),
+            MessageObject::EGroup
+        ));
+
+        // 1: 1 3:EGROUP
+        let data = WireData::new(vec![0b00001000, 0b00000001, 0b00011100]);
+        let mut iter = Message(data.clone()).into_try_iter();
+        assert!(iter.next().unwrap().is_ok());
+        let error = iter.next().unwrap().err().unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::UnexpectedEndGroup(3));
+        assert_eq!(error.offset(), 2);
+
+        let mut iter = Message(data).into_try_iter_with_options(&lenient);
+        assert!(iter.next().unwrap().is_ok());
+        let field = iter.next().unwrap().unwrap();
+        assert_eq!(field.get_field_id(), 3);
+        assert!(matches!(field.get_data(), MessageObject::EGroup));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_message_lookup() {
+        let mut message = Message::new();
+        message.push(Field::new(1, MessageObject::Len(Len::new_string("first"))));
+        message.push(Field::new(2, MessageObject::Varint(Varint::new(7))));
+        message.push(Field::new(1, MessageObject::Len(Len::new_string("second"))));
+        message.push(Field::new(1, MessageObject::Len(Len::new_string("last"))));
+
+        fn get_str(field: Option<FieldRef<'_>>) -> &str {
+            field.unwrap().into_len().unwrap().as_str().unwrap()
+        }
+        assert_eq!(get_str(message.get(1).unwrap()), "last");
+        assert_eq!(get_str(message.first(1).unwrap()), "first");
+        assert_eq!(
+            message
+                .get(2)
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            7
+        );
+        assert!(message.get(3).unwrap().is_none());
+        assert!(message.contains(2).unwrap());
+        assert!(!message.contains(3).unwrap());
+
+        let all: Vec<&str> = message
+            .get_all(1)
+            .map(|field| field.unwrap().into_len().unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(all, ["first", "second", "last"]);
+
+        // lookups don't consume the message
+        assert_eq!(message.into_iter().count(), 4);
+
+        // errors after the last occurrence are still reported by get, but not by first
+        let data = complex_data();
+        let message = MessageRef::new(&data.as_ref()[..20]);
+        assert!(message.first(1).unwrap().is_some());
+        let error = message.get(1).err().unwrap();
+        assert_eq!(error.path(), &[2, 2]);
+        let mut all = message.get_all(3);
+        assert!(all.next().unwrap().is_err());
+        assert!(all.next().is_none());
+    }
+
+    #[test]
+    fn test_indexed_message() {
+        let mut message = Message::new();
+        for value in 0..100 {
+            message.push(Field::new(
+                value % 10 + 1,
+                MessageObject::Varint(Varint::new(value)),
+            ));
+        }
+        let mut indexed = IndexedMessage::new(message);
+
+        assert_eq!(
+            indexed
+                .get(1)
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            90
+        );
+        assert_eq!(
+            indexed
+                .first(1)
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            0
+        );
+        let values: Vec<u64> = indexed
+            .get_all(10)
+            .unwrap()
+            .map(|field| field.into_varint().unwrap().get())
+            .collect();
+        assert_eq!(values, [9, 19, 29, 39, 49, 59, 69, 79, 89, 99]);
+        assert!(!indexed.contains(11).unwrap());
+
+        // push keeps the index up to date
+        indexed.push(Field::new(11, MessageObject::Varint(Varint::new(1000))));
+        indexed.push(Field::new(1, MessageObject::Varint(Varint::new(1001))));
+        assert_eq!(
+            indexed
+                .get(11)
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            1000
+        );
+        assert_eq!(
+            indexed
+                .get(1)
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            1001
+        );
+        assert_eq!(indexed.get_all(1).unwrap().len(), 11);
+
+        // other mutation rebuilds the index
+        let message = indexed.message_mut();
+        *message = Message::new();
+        message.push(Field::new(1, MessageObject::Varint(Varint::new(5))));
+        assert_eq!(indexed.get_all(1).unwrap().len(), 1);
+        assert!(!indexed.contains(11).unwrap());
+
+        // malformed data is reported by every lookup
+        let data = complex_data();
+        let indexed = IndexedMessage::new(Message(WireData::new(data.as_ref()[..20].to_vec())));
+        assert!(indexed.get(1).is_err());
+        assert!(indexed.contains(1).is_err());
+    }
+
+    #[test]
+    fn test_field_paths() {
+        let data = complex_data();
+        let message = Message(data);
+
+        fn get_str(field: Option<FieldRef<'_>>) -> &str {
+            field.unwrap().into_len().unwrap().as_str().unwrap()
+        }
+        assert_eq!(get_str(message.get_path(&[3, 61, 1]).unwrap()), "world!");
+        assert_eq!(get_str(message.get_path(&[2, 2]).unwrap()), "hello, world!");
+        assert_eq!(
+            message
+                .get_path(&[3, 405])
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            10101
+        );
+
+        let path: FieldPath = "3.61.1[2]".parse().unwrap();
+        assert_eq!(path.to_string(), "3.61.1[2]");
+        assert_eq!(get_str(message.get_path(&path).unwrap()), " ");
+        let path: FieldPath = "3[0].61[0].1[0]".parse().unwrap();
+        assert_eq!(get_str(message.get_path(path).unwrap()), "hello");
+        let path: FieldPath = "3.61.1[4]".parse().unwrap();
+        assert!(message.get_path(path).unwrap().is_none());
+
+        let all: Vec<&str> = message
+            .get_all_path(&[3, 61, 1])
+            .unwrap()
+            .into_iter()
+            .map(|field| field.into_len().unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(all, ["hello", ",", " ", "world!"]);
+
+        // paths through scalars or to missing fields don't match
+        assert!(message.get_path(&[1, 1]).unwrap().is_none());
+        assert!(message.get_path(&[4]).unwrap().is_none());
+        assert!(message.get_all_path(&[3, 61, 2]).unwrap().is_empty());
+
+        assert_eq!(
+            "3.x".parse::<FieldPath>().err().unwrap(),
+            ParseFieldPathError { offset: 2 }
+        );
+        assert!("3.61[2".parse::<FieldPath>().is_err());
+
+        // errors in submessages are relative to the outer message
+        let mut inner = Len::new();
+        inner.set_bytes(&[0b00001010, 0b00000101, 0b01100001]);
+        let mut outer = Message::new();
+        outer.push(Field::new(3, MessageObject::Len(inner)));
+        let error = outer.get_path(&[3, 1]).err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::LengthOverrun {
+                length: 5,
+                available: 1
+            }
+        );
+        assert_eq!(error.offset(), 3);
+        assert_eq!(error.path(), &[3, 1]);
+    }
+
+    #[test]
+    fn test_set_path() {
+        fn strings(message: &Message, path: &[u64]) -> Vec<String> {
+            message
+                .get_all_path(path)
+                .unwrap()
+                .into_iter()
+                .map(|field| field.into_len().unwrap().as_str().unwrap().to_string())
+                .collect()
+        }
+        let original = complex_data();
+
+        // shrinking a nested string rewrites both enclosing prefixes and nothing else
+        let mut message = Message(complex_data());
+        let path: FieldPath = "3.61.1[0]".parse().unwrap();
+        message
+            .set_path(&path, MessageObject::Len(Len::new_string("hi")))
+            .unwrap();
+        assert_eq!(strings(&message, &[3, 61, 1]), ["hi", ",", " ", "world!"]);
+        assert_eq!(message.0.len(), original.len() - 3);
+        assert_eq!(message.0.as_ref()[..31], original.as_ref()[..31]);
+        assert_eq!(message.0.as_ref()[31..33], [0b00011010, 31 - 3]);
+
+        // growing it past 127 bytes widens both length prefixes
+        let long = "x".repeat(200);
+        message
+            .set_path(&path, MessageObject::Len(Len::new_string(&long)))
+            .unwrap();
+        assert_eq!(strings(&message, &[3, 61, 1]), [&long, ",", " ", "world!"]);
+        assert_eq!(message.0.len(), original.len() - 5 + 200 + 3);
+        assert_eq!(
+            message.get_path(&[3, 405]).unwrap().unwrap().as_bytes(),
+            [0b10101000, 0b00011001, 0b11110101, 0b01001110]
+        );
+        assert_eq!(message.0.as_ref()[..31], original.as_ref()[..31]);
+
+        // without an index every occurrence is replaced by one in place of the first
+        message
+            .set_path(&[3, 61, 1], MessageObject::Len(Len::new_string("only")))
+            .unwrap();
+        assert_eq!(strings(&message, &[3, 61, 1]), ["only"]);
+
+        // fields inside groups can be replaced too
+        message
+            .set_path(&[2, 1], MessageObject::Varint(Varint::new(7)))
+            .unwrap();
+        assert_eq!(
+            message
+                .get_path(&[2, 1])
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            7
+        );
+        assert_eq!(strings(&message, &[2, 2]), ["hello, world!"]);
+
+        // missing intermediate messages are created
+        message
+            .set_path(&[3, 7, 8], MessageObject::Len(Len::new_string("new")))
+            .unwrap();
+        assert_eq!(strings(&message, &[3, 7, 8]), ["new"]);
+        assert_eq!(strings(&message, &[3, 61, 1]), ["only"]);
+
+        let mut message = Message(complex_data());
+        let path: FieldPath = "3.61.1[1]".parse().unwrap();
+        assert_eq!(message.remove_path(&path).unwrap(), 1);
+        assert_eq!(strings(&message, &[3, 61, 1]), ["hello", " ", "world!"]);
+        assert_eq!(message.remove_path(&[3, 61, 1]).unwrap(), 3);
+        assert!(message
+            .get_path(&[3, 61])
+            .unwrap()
+            .unwrap()
+            .into_len()
+            .unwrap()
+            .get_data()
+            .is_empty());
+        assert_eq!(message.remove_path(&[4, 1]).unwrap(), 0);
+        assert_eq!(message.remove_path(&[3, 61, 1]).unwrap(), 0);
+        let mut fields = Message(message.0.clone()).into_iter();
+        assert_eq!(fields.next().unwrap().get_field_id(), 1);
+        assert_eq!(fields.next().unwrap().get_field_id(), 2);
+        assert_eq!(fields.next().unwrap().get_field_id(), 3);
+        assert!(fields.next().is_none());
+
+        assert_eq!(message.remove_path(FieldPath::new()), Err(PathError::Empty));
+        assert_eq!(
+            message.set_path(&[1, 1], MessageObject::Varint(Varint::new(1))),
+            Err(PathError::NotAContainer { depth: 0 })
+        );
+        let path: FieldPath = "3.61.1[9]".parse().unwrap();
+        assert_eq!(
+            message.set_path(&path, MessageObject::Varint(Varint::new(1))),
+            Err(PathError::IndexOutOfRange { depth: 2, index: 9 })
+        );
+    }
+
+    #[test]
+    fn test_message_edit() {
+        fn ids(message: &Message) -> Vec<u64> {
+            message
+                .view()
+                .into_iter()
+                .map(|field| field.get_field_id())
+                .collect()
+        }
+        let mut message = Message::new();
+        message.push(Field::new(1, MessageObject::Varint(Varint::new(1))));
+        message.push(Field::new(2, MessageObject::Len(Len::new_string("a"))));
+        message.push(Field::new(1, MessageObject::Varint(Varint::new(2))));
+        message.push(Field::new(3, MessageObject::I32(I32::new(3))));
+
+        message
+            .set(1, MessageObject::Varint(Varint::new(300)))
+            .unwrap();
+        assert_eq!(ids(&message), [1, 2, 3]);
+        assert_eq!(
+            message
+                .get(1)
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            300
+        );
+
+        // a field which isn't present is appended
+        message
+            .set(4, MessageObject::Len(Len::new_string("b")))
+            .unwrap();
+        assert_eq!(ids(&message), [1, 2, 3, 4]);
+
+        message
+            .insert_at(0, Field::new(5, MessageObject::Varint(Varint::new(5))))
+            .unwrap();
+        message
+            .insert_at(2, Field::new(2, MessageObject::Len(Len::new_string("c"))))
+            .unwrap();
+        message
+            .insert_at(6, Field::new(6, MessageObject::Varint(Varint::new(6))))
+            .unwrap();
+        assert_eq!(ids(&message), [5, 1, 2, 2, 3, 4, 6]);
+        let error = message
+            .insert_at(8, Field::new(8, MessageObject::Varint(Varint::new(8))))
+            .unwrap_err();
+        assert_eq!(error, EditError::IndexOutOfBounds { index: 8, len: 7 });
+        assert_eq!(ids(&message), [5, 1, 2, 2, 3, 4, 6]);
+
+        assert_eq!(message.remove(2).unwrap(), 2);
+        assert_eq!(message.remove(7).unwrap(), 0);
+        assert_eq!(ids(&message), [5, 1, 3, 4, 6]);
+
+        assert_eq!(
+            message.retain(|field| field.get_wire_type() == 0).unwrap(),
+            2
+        );
+        assert_eq!(ids(&message), [5, 1, 6]);
+        let mut expected = Message::new();
+        expected.push(Field::new(5, MessageObject::Varint(Varint::new(5))));
+        expected.push(Field::new(1, MessageObject::Varint(Varint::new(300))));
+        expected.push(Field::new(6, MessageObject::Varint(Varint::new(6))));
+        assert_eq!(message.0.as_ref(), expected.0.as_ref());
+
+        // a decode error leaves the message unchanged
+        let mut message = Message(WireData::new(vec![0b00001000, 0b00000001, 0b00010000]));
+        let error = message.remove(1).err().unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::TruncatedVarint);
+        assert_eq!(error.offset(), 3);
+        assert_eq!(message.0.len(), 3);
+    }
+
+    #[test]
+    fn test_message_tree() {
+        let original = complex_data();
+        let mut tree = MessageTree::from(Message(complex_data())).unwrap();
+        assert_eq!(tree.serialize().as_ref(), original.as_ref());
+
+        assert_eq!(
+            tree.get(1).unwrap().value(),
+            &Node::I32((-13.37f32).to_bits() as i32)
+        );
+        let Node::Group(group) = tree.get(2).unwrap().value() else {
+            panic!("field 2 should be a group");
+        };
+        assert_eq!(
+            group.get(2).unwrap().value(),
+            &Node::string("hello, world!")
+        );
+        let Node::Message(inner) = tree.get(3).unwrap().value() else {
+            panic!("field 3 should be a message");
+        };
+        assert_eq!(inner.get(405).unwrap().value(), &Node::Varint(10101));
+        let Node::Message(strings) = inner.get(61).unwrap().value() else {
+            panic!("field 3.61 should be a message");
+        };
+        assert_eq!(strings.get_all(1).count(), 4);
+
+        // edit a deeply nested string so that both enclosing prefixes grow
+        let long = "x".repeat(200);
+        let Node::Message(inner) = tree.get_mut(3).unwrap().value_mut() else {
+            unreachable!();
+        };
+        let Node::Message(strings) = inner.get_mut(61).unwrap().value_mut() else {
+            unreachable!();
+        };
+        strings.fields_mut()[0].set_value(Node::string(&long));
+        strings.push(1, Node::string("!"));
+        assert!(tree.get(3).unwrap().is_modified());
+        assert!(!tree.get(2).unwrap().is_modified());
+
+        let message = tree.to_message();
+        assert_eq!(message.0.len(), tree.byte_len());
+        assert_eq!(message.0.as_ref()[..31], original.as_ref()[..31]);
+        let all: Vec<&str> = message
+            .get_all_path(&[3, 61, 1])
+            .unwrap()
+            .into_iter()
+            .map(|field| field.into_len().unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(all, [long.as_str(), ",", " ", "world!", "!"]);
+        assert_eq!(
+            message
+                .get_path(&[3, 405])
+                .unwrap()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .get(),
+            10101
+        );
+
+        // untouched siblings keep non-canonical encodings byte for byte
+        let data = vec![
+            0b00001000, 0b10000001, 0b00000000, 0b00010010, 0b00000010, 0b00001000, 0b00000101,
+        ];
+        let mut tree = MessageTree::from(Message(WireData::new(data.clone()))).unwrap();
+        let Node::Message(inner) = tree.get_mut(2).unwrap().value_mut() else {
+            panic!("field 2 should be a message");
+        };
+        inner.set(1, Node::Varint(300));
+        inner.set(3, Node::PackedVarint(vec![1, 300]));
+        let data = tree.serialize();
+        assert_eq!(
+            data.as_ref(),
+            [
+                0b00001000, 0b10000001, 0b00000000, 0b00010010, 0b00001000, 0b00001000, 0b10101100,
+                0b00000010, 0b00011010, 0b00000011, 0b00000001, 0b10101100, 0b00000010,
+            ]
+        );
+
+        // a payload can be reinterpreted as packed values and back
+        let mut node = Node::Bytes(vec![0b00000001, 0b10101100, 0b00000010]);
+        node.expand_packed_varint().unwrap();
+        assert_eq!(node, Node::PackedVarint(vec![1, 300]));
+        node.collapse();
+        assert_eq!(node, Node::Bytes(vec![0b00000001, 0b10101100, 0b00000010]));
+        let mut node = Node::Bytes(vec![0b10000000]);
+        assert_eq!(
+            node.expand_packed_varint().err().unwrap().kind(),
+            &DecodeErrorKind::TruncatedVarint
+        );
+    }
+
+    #[test]
+    fn test_protoscope_printer() {
+        let message = Message(complex_data());
+        assert_eq!(
+            message.to_string(),
+            [
+                "1: -13.37i32",
+                "2: !{",
+                "  1: 13.37",
+                "  2: {\"hello, world!\"}",
+                "}",
+                "3: {",
+                "  405: 10101",
+                "  32: 9",
+                "  61: {",
+                "    1: {\"hello\"}",
+                "    1: {\",\"}",
+                "    1: {\" \"}",
+                "    1: {\"world!\"}",
+                "  }",
+                "}",
+            ]
+            .join("\n")
+        );
+        assert_eq!(format!("{message:?}"), message.to_string());
+
+        let options = PrintOptions {
+            max_depth: 1,
+            explicit_wire_types: true,
+            bytes_format: BytesFormat::Escaped,
+        };
+        assert_eq!(
+            message.protoscope_with_options(&options).to_string(),
+            [
+                "1:I32 -13.37i32",
+                "2:SGROUP",
+                "  1:I64 13.37",
+                "  2:LEN {\"hello, world!\"}",
+                "2:EGROUP",
+                "3:LEN {",
+                "  405:VARINT 10101",
+                "  32:VARINT 9",
+                "  61:LEN {\"\\n\\x05hello\\n\\x01,\\n\\x01 \\n\\x06world!\"}",
+                "}",
+            ]
+            .join("\n")
+        );
+
+        let mut bytes = Len::new();
+        bytes.set_bytes(&[0x00, 0xff]);
+        let field = Field::new(4, MessageObject::Len(bytes));
+        assert_eq!(field.to_string(), "4: {`00ff`}");
+        let field = Field::new(5, MessageObject::Varint(Varint::new_proto_int64(-2)));
+        assert_eq!(format!("{field:?}"), "5: -2");
+        let field = Field::new(6, MessageObject::I64(I64::new(7)));
+        assert_eq!(field.to_string(), "6: 7i64");
+
+        // padded varints are marked, and undecodable data is printed as hex
+        let data = WireData::new(vec![
+            0b00001000, 0b10000001, 0b00000000, 0b00010010, 0b00000101,
+        ]);
+        assert_eq!(Message(data).to_string(), "1: long-form:1 1\n`1205`");
+
+        // padded tags too, a padded end-group tag needing explicit group tags, and the
+        // parser reads them back
+        for (data, text) in [
+            (vec![0b10001000, 0b00000000, 0b00000001], "long-form:1 1: 1"),
+            (
+                vec![0b10001011, 0b00000000, 0b00010000, 0b00000010, 0b00001100],
+                "long-form:1 1: !{\n  2: 2\n}",
+            ),
+            (
+                vec![0b00001011, 0b10001100, 0b10000000, 0b00000000],
+                "1:SGROUP\nlong-form:2 1:EGROUP",
+            ),
+        ] {
+            let message = Message(WireData::new(data.clone()));
+            assert_eq!(message.to_string(), text);
+            assert_eq!(Message::from_protoscope(text).unwrap().0.as_ref(), data);
+        }
+    }
+
+    #[test]
+    fn test_protoscope_parser() {
+        let text = r#"
+            1: -13.37i32
+            2: !{
+                1: 13.37
+                2: {"hello, world!"}
+            }
+            3: {
+                405: 10101
+                32: -5z
+                61: {
+                    1: {"hello"}
+                    1: {","}
+                    1: {" "}
+                    1: {"world!"}
+                }
+            }
+        "#;
+        let message = Message::from_protoscope(text).unwrap();
+        assert_eq!(message.0.as_ref(), complex_data().as_ref());
+
+        // the printer's output parses back to the same bytes
+        let printed = message.to_string();
+        assert_eq!(
+            Message::from_protoscope(&printed).unwrap().0.as_ref(),
+            complex_data().as_ref()
+        );
+        let options = PrintOptions {
+            max_depth: 1,
+            explicit_wire_types: true,
+            bytes_format: BytesFormat::Escaped,
+        };
+        let printed = message.protoscope_with_options(&options).to_string();
+        assert_eq!(
+            WireData::from_protoscope(&printed).unwrap().as_ref(),
+            complex_data().as_ref()
+        );
+
+        let data = WireData::from_protoscope(
+            "1: long-form:1 1 # padded\n2:VARINT -1 3: 0x10i32 4: 2i64 5: true\n\
+             6: {`00ff` \"\\x01\\n\\101\"} 7:SGROUP 7:EGROUP 8: !{}",
+        )
+        .unwrap();
+        let mut expected = vec![0b00001000, 0b10000001, 0b00000000, 0b00010000];
+        expected.extend([0xff; 9]);
+        expected.extend([
+            0x01, 0b00011101, 0x10, 0, 0, 0, 0b00100001, 2, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        expected.extend([0b00101000, 1, 0b00110010, 5, 0x00, 0xff, 0x01, b'\n', b'A']);
+        expected.extend([0b00111011, 0b00111100, 0b01000011, 0b01000100]);
+        assert_eq!(data.as_ref(), expected);
+
+        let error = Message::from_protoscope("1: {\n  2: 3\n").err().unwrap();
+        assert_eq!(error.kind, ProtoscopeErrorKind::UnclosedBrace);
+        assert_eq!((error.line, error.column), (1, 4));
+        let error = Message::from_protoscope("1: 2\n  3: 4000000000i32x")
+            .err()
+            .unwrap();
+        assert_eq!(error.kind, ProtoscopeErrorKind::InvalidToken);
+        assert_eq!((error.line, error.column), (2, 6));
+        let error = Message::from_protoscope("1: 5000000000i32").err().unwrap();
+        assert_eq!(error.kind, ProtoscopeErrorKind::NumberOutOfRange);
+        assert_eq!(
+            Message::from_protoscope("{`abc`}").err().unwrap().kind,
+            ProtoscopeErrorKind::InvalidHex
+        );
+        assert_eq!(
+            Message::from_protoscope("1:LEN !{}").err().unwrap().kind,
+            ProtoscopeErrorKind::GroupWithoutTag
+        );
+
+        // padding can't make a varint longer than 10 bytes, and field ids must leave room
+        // for the wire type
+        for invalid in [
+            "1: long-form:18446744073709551615 5",
+            "1: long-form:10 0",
+            "1: long-form:9 128",
+            "2305843009213693952: 1",
+            "2305843009213693952: !{}",
+        ] {
+            let error = Message::from_protoscope(invalid).err().unwrap();
+            assert_eq!(
+                error.kind,
+                ProtoscopeErrorKind::NumberOutOfRange,
+                "{invalid}"
+            );
+        }
+        let largest = Message::from_protoscope("2305843009213693951: long-form:8 1").unwrap();
+        assert_eq!(largest.0.len(), 19);
+        assert_eq!(largest.to_string(), "2305843009213693951: long-form:8 1");
+        // a prefix only pads the token right after it
+        assert_eq!(
+            Message::from_protoscope("long-form:1 {1: 2}")
+                .unwrap()
+                .0
+                .as_ref(),
+            [0b10000010, 0b00000000, 0b00001000, 0b00000010]
+        );
+        assert_eq!(
+            Message::from_protoscope("1: !{2: 3 long-form:1}")
+                .unwrap()
+                .0
+                .as_ref(),
+            Message::from_protoscope("1: !{2: 3}").unwrap().0.as_ref()
+        );
+
+        // nesting is limited, groups and blocks alike
+        let nested = |depth: usize| "1: !{".repeat(depth / 2) + &"{".repeat(depth - depth / 2);
+        let closed = |depth: usize| nested(depth) + &"}".repeat(depth);
+        assert!(Message::from_protoscope(&closed(100)).is_ok());
+        let error = Message::from_protoscope(&closed(101)).err().unwrap();
+        assert_eq!(error.kind, ProtoscopeErrorKind::DepthLimitExceeded);
+        assert_eq!((error.line, error.column), (1, 301));
+    }
+
+    #[test]
+    fn test_classify() {
+        let message = Message(complex_data());
+        let raw = message.decode_raw();
+        assert!(raw.remainder().is_empty());
+        let fields = raw.fields();
+        assert!(matches!(fields[0].value(), RawValue::I32(_)));
+        let RawValue::Group(group) = fields[1].value() else {
+            panic!("field 2 should be a group");
+        };
+        assert!(matches!(
+            group.fields()[1].value(),
+            RawValue::String("hello, world!")
+        ));
+        let RawValue::Message(inner) = fields[2].value() else {
+            panic!("field 3 should be a message");
+        };
+        assert_eq!(fields[2].confidence(), Confidence::High);
+        let RawValue::Message(strings) = inner.fields()[2].value() else {
+            panic!("field 3.61 should be a message");
+        };
+        assert!(matches!(
+            strings.fields()[0].value(),
+            RawValue::String("hello")
+        ));
+        assert_eq!(strings.fields()[0].confidence(), Confidence::High);
+
+        fn classify(payload: &[u8]) -> (RawValue<'_>, Confidence) {
+            LenRef {
+                length: VarintRef(&[]),
+                inner: payload,
+            }
+            .classify()
+        }
+        // "hi" is also a valid message, but text wins
+        assert!(matches!(
+            classify(b"hi"),
+            (RawValue::String("hi"), Confidence::High)
+        ));
+        assert!(matches!(
+            classify(b""),
+            (RawValue::String(""), Confidence::Low)
+        ));
+        assert!(matches!(
+            classify(&[0xff, 0x00]),
+            (RawValue::Bytes(_), Confidence::Low)
+        ));
+
+        let (value, confidence) = classify(&[0x01, 0xac, 0x02, 0x05]);
+        assert!(matches!(value, RawValue::PackedVarint(values) if values == [1, 300, 5]));
+        assert_eq!(confidence, Confidence::Medium);
+        let mut doubles = 1.5f64.to_le_bytes().to_vec();
+        doubles.extend(2.25f64.to_le_bytes());
+        let (value, _) = classify(&doubles);
+        assert!(matches!(
+            value,
+            RawValue::PackedI64(values) if values == [1.5f64.to_bits() as i64, 2.25f64.to_bits() as i64]
+        ));
+        let float = (-13.37f32).to_le_bytes();
+        let (value, confidence) = classify(&float);
+        assert!(matches!(value, RawValue::PackedI32(_)));
+        assert_eq!(confidence, Confidence::Low);
+
+        // text renderings use the classifier, and packed values still round trip
+        let mut packed = Len::new();
+        packed.set_bytes(&[0x01, 0xac, 0x02, 0x05]);
+        let mut floats = Len::new();
+        floats.set_bytes(&doubles);
+        let mut message = Message::new();
+        message.push(Field::new(4, MessageObject::Len(packed)));
+        message.push(Field::new(5, MessageObject::Len(floats)));
+        assert_eq!(message.to_string(), "4: {1 300 5}\n5: {1.5 2.25}");
+        assert_eq!(
+            Message::from_protoscope(&message.to_string())
+                .unwrap()
+                .0
+                .as_ref(),
+            message.0.as_ref()
+        );
+        let tree = MessageTree::from(message).unwrap();
+        assert_eq!(
+            tree.get(4).unwrap().value(),
+            &Node::PackedVarint(vec![1, 300, 5])
+        );
+
+        // undecodable data ends up in the remainder
+        let message = Message(WireData::new(vec![
+            0b00001000, 0b00000001, 0b00010010, 0b00000101,
+        ]));
+        let raw = message.decode_raw();
+        assert_eq!(raw.fields().len(), 1);
+        assert_eq!(raw.remainder(), [0b00010010, 0b00000101]);
+    }
+
+    #[test]
+    fn test_annotate() {
+        let message = Message(complex_data());
+        let annotation = message.annotate();
+        assert!(annotation.error().is_none());
+        let ranges = annotation.ranges();
+        assert_eq!(ranges[0].range, 0..1);
+        assert_eq!(ranges[0].role, ByteRole::Tag);
+        assert_eq!(ranges[1].range, 1..5);
+        assert_eq!(ranges[1].description, "-13.37i32");
+        // the group's fields are indented and it is closed by an end-group tag
+        assert_eq!(ranges[3].depth, 1);
+        let end_group = ranges
+            .iter()
+            .find(|range| range.role == ByteRole::EndGroup)
+            .unwrap();
+        assert_eq!((end_group.depth, end_group.field_id), (0, Some(2)));
+        let hello = ranges
+            .iter()
+            .find(|range| range.role == ByteRole::Payload)
+            .unwrap();
+        assert_eq!(hello.description, "\"hello, world!\"");
+        // nested messages are annotated field by field
+        assert_eq!(ranges.iter().map(|range| range.depth).max(), Some(2));
+        assert_eq!(ranges.last().unwrap().range.end, 64);
+
+        // a length overrunning the buffer is marked from its prefix onwards
+        let message = Message(WireData::new(vec![
+            0b00001000, 0b10010110, 0b00000001, 0b00010010, 0b00000101, 0b01100001,
+        ]));
+        let annotation = message.annotate();
+        assert_eq!(annotation.error().unwrap().offset(), 4);
+        let invalid = annotation.ranges().last().unwrap();
+        assert_eq!(
+            (invalid.role, invalid.range.clone()),
+            (ByteRole::Invalid, 4..6)
+        );
+        assert_eq!(annotation.ranges()[1].description, "150");
+
+        // a group without an end-group tag is marked from its start tag onwards
+        let message = Message(WireData::new(vec![
+            0b00001000, 0b00000001, 0b00001011, 0b00001000, 0b00000001,
+        ]));
+        let annotation = message.annotate();
+        assert_eq!(
+            annotation.error().unwrap().kind(),
+            &DecodeErrorKind::UnterminatedGroup(1)
+        );
+        let roles: Vec<_> = annotation
+            .ranges()
+            .iter()
+            .map(|range| (range.role, range.range.clone(), range.depth))
+            .collect();
+        assert_eq!(
+            roles,
+            [
+                (ByteRole::Tag, 0..1, 0),
+                (ByteRole::Value, 1..2, 0),
+                (ByteRole::Invalid, 2..5, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_span() {
+        let message = Message(complex_data());
+        let fields: Vec<(FieldSpan, Field)> = message.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].0.tag, 0..1);
+        assert_eq!(fields[0].0.header, 0..1);
+        assert_eq!(fields[0].0.value, 1..5);
+
+        // the fields of a group have absolute spans too
+        let (span, group) = &fields[1];
+        assert_eq!((span.value.clone(), span.range.clone()), (6..30, 5..31));
+        let group = group.as_group().unwrap().get_fields();
+        assert_eq!(group[0].span().unwrap().range, 6..15);
+        let string = group[1].span().unwrap();
+        assert_eq!(
+            (string.header.clone(), string.value.clone()),
+            (15..17, 17..30)
+        );
+
+        let (span, len) = &fields[2];
+        assert_eq!(span.header, 31..33);
+        assert_eq!(span.value, 33..64);
+        let inner = len.as_len().unwrap().get_data();
+        let (inner_span, _) = Message(inner)
+            .iter_at(span.value.start)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(inner_span.tag, 33..35);
+        assert_eq!(inner_span.value, 35..37);
+
+        // the message is still usable, and fields built in code have no span
+        assert_eq!(message.iter().count(), 3);
+        assert!(Field::new(1, MessageObject::Varint(Varint::new(1)))
+            .span()
+            .is_none());
+
+        // a field which fails to parse is reported, along with the data from it on
+        let mut iter = Message(WireData::new(vec![0x08, 0x01, 0x12, 0x05, 0x01])).iter();
+        assert_eq!(iter.next().unwrap().unwrap().0.range, 0..2);
+        let error = iter.next().unwrap().unwrap_err();
+        assert_eq!(error.offset(), 3);
+        assert!(iter.next().is_none());
+        assert_eq!(iter.into_remainder().as_ref(), [0x12, 0x05, 0x01]);
+    }
+
+    #[test]
+    fn test_descriptor_proto() {
+        // descriptor.pb is descriptor.txtpb encoded, which this rewrites with
+        // PROTOWIRE_REGENERATE set
+        let pool = DescriptorPool::bootstrap();
+        let set = pool
+            .get_message_by_name("google.protobuf.FileDescriptorSet")
+            .unwrap();
+        let message = Message::from_text_format(set, include_str!("descriptor.txtpb")).unwrap();
+        if std::env::var_os("PROTOWIRE_REGENERATE").is_some() {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/descriptor.pb");
+            std::fs::write(path, message.0.as_ref()).unwrap();
+        } else {
+            assert_eq!(message.0.as_ref(), DescriptorPool::DESCRIPTOR_PROTO);
+        }
+    }
+
+    #[test]
+    fn test_descriptor_pool() {
+        let pool = DescriptorPool::bootstrap();
+        let file = pool
+            .get_file_by_name("google/protobuf/descriptor.proto")
+            .unwrap();
+        assert_eq!(file.package(), "google.protobuf");
+        assert_eq!(file.syntax(), Syntax::Proto2);
+        let field = pool
+            .get_message_by_name("google.protobuf.FieldDescriptorProto")
+            .unwrap();
+        let Kind::Enum(kind) = field.get_field_by_name("type").unwrap().kind() else {
+            panic!("FieldDescriptorProto.type should be an enum");
+        };
+        assert_eq!(
+            kind.full_name(),
+            "google.protobuf.FieldDescriptorProto.Type"
+        );
+        assert_eq!(kind.get_value(11).unwrap().name(), "TYPE_MESSAGE");
+        assert_eq!(kind.parent_message(), Some(field));
+        // the embedded descriptor describes itself
+        let set = pool
+            .get_message_by_name(".google.protobuf.FileDescriptorSet")
+            .unwrap();
+        let Kind::Message(file) = set.get_field(1).unwrap().kind() else {
+            panic!("FileDescriptorSet.file should be a message");
+        };
+        assert_eq!(file.name(), "FileDescriptorProto");
+
+        let pool = DescriptorPool::decode(&descriptor_set()).unwrap();
+        assert_eq!(pool.files().len(), 2);
+        let shop = pool.get_file_by_name("shop.proto").unwrap();
+        assert_eq!(shop.dependencies().next().unwrap().name(), "types.proto");
+        let order = pool.get_message_by_name("test.shop.Order").unwrap();
+        assert_eq!(order.nested_messages().len(), 2);
+        assert_eq!(order.fields().len(), 10);
+
+        // relative type names resolve from the innermost scope outwards
+        let items = order.get_field_by_name("items").unwrap();
+        assert!(items.is_list());
+        let Kind::Message(item) = items.kind() else {
+            panic!("items should be messages");
+        };
+        assert_eq!(item.full_name(), "test.shop.Order.Item");
+        assert_eq!(item.parent_message(), Some(order));
+        assert_eq!(item.get_field(3).unwrap().json_name(), "priceCents");
+
+        let tags = order.get_field(3).unwrap();
+        assert!(tags.is_map() && !tags.is_list());
+        let Kind::Enum(color) = order.get_field_by_json_name("color").unwrap().kind() else {
+            panic!("color should be an enum");
+        };
+        assert_eq!(color.full_name(), "test.types.Color");
+        assert_eq!(color.get_value_by_name("BLUE").unwrap().number(), 2);
+        assert_eq!(color.default_value().unwrap().name(), "COLOR_UNSPECIFIED");
+
+        let oneofs: Vec<OneofDescriptor<'_>> = order.oneofs().collect();
+        assert_eq!(oneofs[0].name(), "payment");
+        assert_eq!(
+            oneofs[0].fields().map(|f| f.number()).collect::<Vec<_>>(),
+            [5, 6]
+        );
+        assert!(!oneofs[0].is_synthetic() && oneofs[1].is_synthetic());
+        let discount = order.get_field(7).unwrap();
+        assert!(discount.is_proto3_optional() && discount.has_presence());
+        assert!(!order.get_field(1).unwrap().has_presence());
+        // proto3 packs repeated scalars by default
+        assert!(order.get_field_by_name("deltas").unwrap().is_packed());
+        assert_eq!(order.get_field(10).unwrap().kind(), Kind::Bytes);
+
+        let service = pool.get_service_by_name("test.shop.Shop").unwrap();
+        let place = service.get_method_by_name("Place").unwrap();
+        assert_eq!(place.input(), order);
+        assert_eq!(place.output().full_name(), "test.types.Point");
+
+        // a missing import fails and leaves the pool unchanged
+        let mut pool = DescriptorPool::new();
+        let shop_only = Message::from_protoscope("1: {1: {\"shop.proto\"} 3: {\"types.proto\"}}")
+            .unwrap()
+            .serialize();
+        assert_eq!(
+            pool.add_file_descriptor_set(shop_only.as_ref()),
+            Err(DescriptorError::MissingDependency {
+                file: "shop.proto".into(),
+                dependency: "types.proto".into(),
+            })
+        );
+        assert_eq!(pool.files().len(), 0);
+        let unresolved = Message::from_protoscope(
+            "1: {1: {\"a.proto\"} 4: {1: {\"A\"} 2: {1: {\"b\"} 3: 1 4: 1 6: {\"B\"}}}}",
+        )
+        .unwrap()
+        .serialize();
+        assert!(matches!(
+            pool.add_file_descriptor_set(unresolved.as_ref()),
+            Err(DescriptorError::UnresolvedType { .. })
+        ));
+
+        // messages nested deeper than the limit fail to decode
+        let nested_set = |depth: usize| {
+            let mut message = Message::new();
+            message.push(Field::new(1, MessageObject::Len(Len::new_string("M"))));
+            for _ in 1..depth {
+                let mut outer = Message::new();
+                outer.push(Field::new(1, MessageObject::Len(Len::new_string("M"))));
+                outer.push(Field::new(3, MessageObject::Len(Len::new_message(message))));
+                message = outer;
+            }
+            let mut file = Message::new();
+            file.push(Field::new(4, MessageObject::Len(Len::new_message(message))));
+            let mut set = Message::new();
+            set.push(Field::new(1, MessageObject::Len(Len::new_message(file))));
+            set
+        };
+        assert!(DescriptorPool::decode(nested_set(100).0.as_ref()).is_ok());
+        let Err(DescriptorError::Decode(error)) =
+            DescriptorPool::decode(nested_set(101).0.as_ref())
+        else {
+            panic!("101 nested messages should be too deep");
+        };
+        assert_eq!(
+            error.kind(),
+            &DecodeErrorKind::DepthLimitExceeded { limit: 100 }
+        );
+    }
+
+    #[test]
+    fn test_dynamic_message() {
+        let pool = DescriptorPool::decode(&descriptor_set()).unwrap();
+        let order = pool.get_message_by_name("test.shop.Order").unwrap();
+
+        // fields the schema doesn't know about are kept through edits
+        let mut message = Message::new();
+        message.push(Field::new(99, MessageObject::Varint(Varint::new(7))));
+        let mut dynamic = DynamicMessage::from(order, message);
+
+        // absent fields read as defaults
+        assert_eq!(dynamic.get("id").unwrap(), Value::String(String::new()));
+        assert_eq!(dynamic.get("location.x").unwrap(), Value::I32(0));
+        assert_eq!(
+            dynamic.get_enum_name("color").unwrap(),
+            Some("COLOR_UNSPECIFIED")
+        );
+        assert_eq!(dynamic.get("deltas").unwrap(), Value::List(Vec::new()));
+        assert!(!dynamic.has("discount").unwrap());
+        assert!(!dynamic.has("location.x").unwrap());
+
+        dynamic.set("id", Value::String("A-1".into())).unwrap();
+        dynamic.set("location.y", Value::I32(-4)).unwrap();
+        dynamic.set("color", Value::String("BLUE".into())).unwrap();
+        dynamic.set("discount", Value::F64(0.0)).unwrap();
+        dynamic
+            .set("deltas", Value::List(vec![Value::I32(-1), Value::I32(2)]))
+            .unwrap();
+        let mut tags = std::collections::BTreeMap::new();
+        tags.insert(MapKey::String("gift".into()), Value::I64(1));
+        tags.insert(MapKey::String("rush".into()), Value::I64(-2));
+        dynamic.set("tags", Value::Map(tags.clone())).unwrap();
+        let Kind::Message(item) = order.get_field_by_name("items").unwrap().kind() else {
+            panic!("items should be messages");
+        };
+        let mut first = DynamicMessage::new(item);
+        first.set("name", Value::String("tea".into())).unwrap();
+        first.set("price_cents", Value::U64(250)).unwrap();
+        dynamic
+            .set("items", Value::List(vec![Value::Message(first)]))
+            .unwrap();
+
+        assert_eq!(dynamic.get("id").unwrap(), Value::String("A-1".into()));
+        assert_eq!(dynamic.get("location.y").unwrap(), Value::I32(-4));
+        assert_eq!(dynamic.get("color").unwrap(), Value::Enum(2));
+        assert_eq!(dynamic.get_enum_name("color").unwrap(), Some("BLUE"));
+        // presence is tracked for proto3 optional fields even at their default
+        assert!(dynamic.has("discount").unwrap());
+        assert_eq!(dynamic.get("tags").unwrap(), Value::Map(tags));
+        let Value::List(items) = dynamic.get("items").unwrap() else {
+            panic!("items should be a list");
+        };
+        let Value::Message(first) = &items[0] else {
+            panic!("items should hold messages");
+        };
+        assert_eq!(first.get("price_cents").unwrap(), Value::U64(250));
+        assert_eq!(first.get("quantity").unwrap(), Value::U32(0));
+
+        // deltas are packed into a single field of zigzag varints
+        let deltas = dynamic.message().get(8).unwrap().unwrap();
+        assert_eq!(deltas.as_len().unwrap().get_data(), [0x01, 0x04]);
+        assert_eq!(
+            dynamic.get("deltas").unwrap(),
+            Value::List(vec![Value::I32(-1), Value::I32(2)])
+        );
+
+        // setting a member of a oneof clears the others
+        dynamic.set("card", Value::String("visa".into())).unwrap();
+        dynamic.set("voucher", Value::U64(5)).unwrap();
+        assert!(!dynamic.has("card").unwrap());
+        assert_eq!(dynamic.get("voucher").unwrap(), Value::U64(5));
+
+        dynamic.clear("location.y").unwrap();
+        assert!(dynamic.has("location").unwrap());
+        assert!(!dynamic.has("location.y").unwrap());
+        dynamic.clear("discount").unwrap();
+        assert!(!dynamic.has("discount").unwrap());
+
+        assert_eq!(
+            dynamic.set("id", Value::I32(1)),
+            Err(DynamicError::TypeMismatch("id".into()))
+        );
+        assert_eq!(
+            dynamic.get("missing"),
+            Err(DynamicError::UnknownField("missing".into()))
+        );
+        assert_eq!(
+            dynamic.get("id.x"),
+            Err(DynamicError::NotAMessage("id".into()))
+        );
+        let message = dynamic.into_message();
+        assert_eq!(message.get(99).unwrap().unwrap().get_field_id(), 99);
+
+        // proto2 defaults come from the schema
+        let pool = DescriptorPool::bootstrap();
+        let options = pool
+            .get_message_by_name("google.protobuf.FileOptions")
+            .unwrap();
+        let options = DynamicMessage::new(options);
+        assert_eq!(options.get("cc_enable_arenas").unwrap(), Value::Bool(true));
+        assert_eq!(
+            options.get_enum_name("optimize_for").unwrap(),
+            Some("SPEED")
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let mut pool = DescriptorPool::decode(&descriptor_set()).unwrap();
+        pool.add_file_descriptor_set(&json_descriptor_set())
+            .unwrap();
+        let order = pool.get_message_by_name("test.shop.Order").unwrap();
+        let event = pool.get_message_by_name("test.event.Event").unwrap();
+
+        // canonical output reads back to the same message
+        let json = concat!(
+            r#"{"id":"A-1","items":[{"name":"tea","priceCents":"250"}],"tags":{"gift":"1"},"#,
+            r#""color":"BLUE","voucher":"5","discount":0,"deltas":[-1,2],"location":{"y":-4},"#,
+            r#""blob":"AP8="}"#
+        );
+        let dynamic = DynamicMessage::from_json(order, json).unwrap();
+        assert_eq!(dynamic.get("blob").unwrap(), Value::Bytes(vec![0x00, 0xff]));
+        assert_eq!(dynamic.get("location.y").unwrap(), Value::I32(-4));
+        assert_eq!(dynamic.to_json().unwrap(), json);
+        let message = Message::from_json(order, json).unwrap();
+        assert_eq!(message.to_json(order).unwrap(), json);
+
+        // proto names, quoted numbers, exponents and enum numbers are accepted
+        let json = r#"{ "items": [{"price_cents": 1e2}], "color": 1, "voucher": "7", "id": "" }"#;
+        let dynamic = DynamicMessage::from_json(order, json).unwrap();
+        assert_eq!(
+            dynamic.to_json().unwrap(),
+            r#"{"items":[{"priceCents":"100"}],"color":"RED","voucher":"7"}"#
+        );
+        // a field without presence set to its default isn't encoded
+        assert!(!dynamic.has("id").unwrap());
+
+        // parsing is strict unless asked otherwise
+        assert_eq!(
+            Message::from_json(order, r#"{"id":"a","extra":1}"#).err(),
+            Some(JsonError::UnknownField("extra".into()))
+        );
+        let options = JsonOptions {
+            ignore_unknown_fields: true,
+        };
+        assert!(Message::from_json_with_options(order, r#"{"extra":[1]}"#, &options).is_ok());
+        // and so are enum value names the enum doesn't have, which may come from a newer schema
+        assert_eq!(
+            Message::from_json(order, r#"{"color":"GREEN"}"#).err(),
+            Some(JsonError::InvalidValue("color".into()))
+        );
+        let json = r#"{"id":"a","color":"GREEN"}"#;
+        let message = Message::from_json_with_options(order, json, &options).unwrap();
+        assert_eq!(message.to_json(order).unwrap(), r#"{"id":"a"}"#);
+        assert_eq!(
+            Message::from_json(order, r#"{"id":"a","id":"b"}"#).err(),
+            Some(JsonError::DuplicateField("id".into()))
+        );
+        assert_eq!(
+            Message::from_json(order, r#"{"card":"visa","voucher":"1"}"#).err(),
+            Some(JsonError::DuplicateField("payment".into()))
+        );
+        assert_eq!(
+            Message::from_json(order, r#"{"location":{"x":2147483648}}"#).err(),
+            Some(JsonError::InvalidValue("x".into()))
+        );
+        assert_eq!(
+            Message::from_json(order, r#"{"deltas":[1.5]}"#).err(),
+            Some(JsonError::InvalidValue("deltas".into()))
+        );
+        assert_eq!(
+            Message::from_json(order, r#"{"id":}"#).err(),
+            Some(JsonError::Syntax(6))
+        );
+
+        // well-known types have their own forms
+        let json = concat!(
+            r#"{"at":"1972-01-01T10:00:20.021Z","took":"-1.500s","retries":"3","note":"hi","#,
+            r#""attrs":{"a":[1,true,null,{"b":"c"}]},"updateMask":"id,items.priceCents","#,
+            r#""detail":{"@type":"type.googleapis.com/test.types.Point","x":1},"#,
+            r#""size":"-9007199254740993","ratio":"NaN"}"#
+        );
+        let dynamic = DynamicMessage::from_json(event, json).unwrap();
+        assert_eq!(dynamic.get("at.seconds").unwrap(), Value::I64(63_108_020));
+        assert_eq!(dynamic.get("at.nanos").unwrap(), Value::I32(21_000_000));
+        assert_eq!(dynamic.get("took.nanos").unwrap(), Value::I32(-500_000_000));
+        assert_eq!(
+            dynamic.get("update_mask.paths").unwrap(),
+            Value::List(vec![
+                Value::String("id".into()),
+                Value::String("items.price_cents".into())
+            ])
+        );
+        assert_eq!(
+            dynamic.get("size").unwrap(),
+            Value::I64(-9_007_199_254_740_993)
+        );
+        assert_eq!(dynamic.to_json().unwrap(), json);
+
+        // timestamps may carry an offset, and an Any of a well-known type holds a value
+        let json = concat!(
+            r#"{"at":"1972-01-01T05:00:20.021-05:00","#,
+            r#""detail":{"@type":"type.googleapis.com/google.protobuf.Duration","value":"1s"}}"#
+        );
+        let dynamic = DynamicMessage::from_json(event, json).unwrap();
+        assert_eq!(
+            dynamic.to_json().unwrap(),
+            concat!(
+                r#"{"at":"1972-01-01T10:00:20.021Z","#,
+                r#""detail":{"@type":"type.googleapis.com/google.protobuf.Duration","value":"1s"}}"#
+            )
+        );
+        assert_eq!(
+            Message::from_json(event, r#"{"at":"1972-02-30T00:00:00Z"}"#).err(),
+            Some(JsonError::InvalidValue("google.protobuf.Timestamp".into()))
+        );
+        assert_eq!(
+            Message::from_json(event, r#"{"detail":{"@type":"example.com/Nope"}}"#).err(),
+            Some(JsonError::UnknownType("example.com/Nope".into()))
+        );
+    }
+
+    #[test]
+    fn test_text_format() {
+        let mut pool = DescriptorPool::decode(&descriptor_set()).unwrap();
+        pool.add_file_descriptor_set(&json_descriptor_set())
+            .unwrap();
+        pool.add_file_descriptor_set(&config_descriptor_set())
+            .unwrap();
+        let config = pool.get_message_by_name("test.config.Config").unwrap();
+        let priority = pool.get_extension_by_name("test.config.priority").unwrap();
+        assert!(priority.is_extension());
+        assert_eq!(priority.containing_message(), config);
+        assert_eq!(config.get_extension(100), Some(priority));
+        assert_eq!(config.fields().len(), 6);
+
+        let text = r#"
+            # comments, lists, hex and adjacent strings are accepted
+            name: "edge " '"01"'
+            ports: [80, 443]
+            ports: 8080;
+            Limits < max: 0x10 >
+            extra {
+              [type.googleapis.com/test.types.Point] { x: 3 }
+            }
+            ratio: -inf
+            blob: "\000\377a"
+            [test.config.priority]: 7
+        "#;
+        let message = Message::from_text_format(config, text).unwrap();
+        let printed = message.to_text_format(config).unwrap();
+        assert_eq!(
+            printed,
+            concat!(
+                "name: \"edge \\\"01\\\"\"\n",
+                "ports: 80\n",
+                "ports: 443\n",
+                "ports: 8080\n",
+                "Limits {\n",
+                "  max: 16\n",
+                "}\n",
+                "extra {\n",
+                "  [type.googleapis.com/test.types.Point] {\n",
+                "    x: 3\n",
+                "  }\n",
+                "}\n",
+                "ratio: -inf\n",
+                "blob: \"\\000\\377a\"\n",
+                "[test.config.priority]: 7\n",
+            )
+        );
+        let reparsed = Message::from_text_format(config, &printed).unwrap();
+        assert_eq!(reparsed.0.as_ref(), message.0.as_ref());
+        let dynamic = DynamicMessage::from(config, reparsed);
+        assert_eq!(dynamic.get_field(priority).unwrap(), Value::I32(7));
+
+        // maps are printed as entries, and unknown fields by number
+        let order = pool.get_message_by_name("test.shop.Order").unwrap();
+        let mut message = Message::from_text_format(
+            order,
+            r#"tags { key: "gift" value: 1 } color: BLUE location { y: -4 }"#,
+        )
+        .unwrap();
+        message.push(Field::new(99, MessageObject::Varint(Varint::new(5))));
+        assert_eq!(
+            message.to_text_format(order).unwrap(),
+            "tags {\n  key: \"gift\"\n  value: 1\n}\ncolor: BLUE\nlocation {\n  y: -4\n}\n99: 5\n"
+        );
+
+        let error = |text| Message::from_text_format(config, text).err().unwrap();
+        assert_eq!(
+            error("name: \"a\"\n  nope: 1"),
+            TextFormatError {
+                kind: TextFormatErrorKind::UnknownField("nope".into()),
+                line: 2,
+                column: 3
+            }
+        );
+        assert_eq!(
+            error("name: \"a\" name: \"b\"").kind,
+            TextFormatErrorKind::DuplicateField("name".into())
+        );
+        assert_eq!(
+            error("name \"a\"").kind,
+            TextFormatErrorKind::Expected("':'")
+        );
+        assert_eq!(
+            error("ports: 2147483648").kind,
+            TextFormatErrorKind::InvalidValue("ports".into())
+        );
+        assert_eq!(
+            error("[test.config.missing]: 1").kind,
+            TextFormatErrorKind::UnknownExtension("test.config.missing".into())
+        );
+        assert_eq!(
+            error("Limits { max: 1").kind,
+            TextFormatErrorKind::Expected("'}'")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_view() {
+        let message = Message::from_protoscope(
+            r#"
+            1: 150
+            1: 2
+            2: -1i32
+            3: 2.5
+            4: {"hello"}
+            5: {`00ff`}
+            6: !{ 1: 7 }
+            7: { 1: 1 2: {"nested"} }
+            1: 3
+            8: {}
+            "#,
+        )
+        .unwrap();
+        let bytes = message.0.as_ref().to_vec();
+
+        // field 1 comes back at the end, so the fields are kept in order as entries
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"[["1",150],["1",2],["2:i32",-1],["3:i64",4612811918334230528],"#,
+                r#"["4","hello"],["5:bytes","AP8="],["6:group",{"1":7}],"#,
+                r#"["7:bytes","CAESBm5lc3RlZA=="],["1",3],["8",""]]"#
+            )
+        );
+        let parsed: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.0.as_ref(), bytes.as_slice());
+
+        let options = SerdeOptions {
+            expand_messages: true,
+            ..SerdeOptions::new()
+        };
+        let json = serde_json::to_string(&message.serde_view_with_options(&options)).unwrap();
+        assert!(json.contains(r#"["7",{"1":1,"2":"nested"}]"#));
+        let parsed: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.0.as_ref(), bytes.as_slice());
+
+        // payloads nested deeper than the limit are left as bytes
+        let options = SerdeOptions {
+            expand_messages: true,
+            max_depth: 0,
+        };
+        let json = serde_json::to_string(&message.serde_view_with_options(&options)).unwrap();
+        assert!(json.contains(r#"["7:bytes","CAESBm5lc3RlZA=="]"#));
+
+        // otherwise each key appears once, a nested message needing entries needs them
+        // all the way up, and the order survives a `serde_json::Value`
+        for (protoscope, expected) in [
+            ("1: 1 1: 2 2: !{3: 4}", r#"{"1":[1,2],"2:group":{"3":4}}"#),
+            ("1: 1 2: 2 1: 3", r#"[["1",1],["2",2],["1",3]]"#),
+            (
+                "1: 1 2: !{3: 4 5: 6 3: 7}",
+                r#"[["1",1],["2:group",[["3",4],["5",6],["3",7]]]]"#,
+            ),
+        ] {
+            let message = Message::from_protoscope(protoscope).unwrap();
+            let value = serde_json::to_value(&message).unwrap();
+            assert_eq!(value.to_string(), expected);
+            let parsed: Message = serde_json::from_value(value).unwrap();
+            assert_eq!(parsed.0.as_ref(), message.0.as_ref());
+        }
+
+        // unsigned fixed width values and negative varints are accepted too
+        let parsed: Message = serde_json::from_str(r#"{"1":-1,"2:i32":4294967295}"#).unwrap();
+        assert_eq!(
+            parsed.0.as_ref(),
+            Message::from_protoscope("1: -1 2: -1i32")
+                .unwrap()
+                .0
+                .as_ref()
+        );
+
+        for invalid in [
+            r#"{"0":1}"#,
+            r#"{"1:float":1}"#,
+            r#"{"1:i32":4294967296}"#,
+            r#"{"1:bytes":"!"}"#,
+            r#"{"1:group":1}"#,
+            r#"{"1":[[1]]}"#,
+            r#"[["1"]]"#,
+            r#"[["1",1,2]]"#,
+            r#"[["1:i32",[["1",1]]]]"#,
+            r#"[1]"#,
+            r#"{"1":true}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<Message>(invalid).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        let message = Message::from_protoscope(
+            r#"
+            1: 5
+            1: {"wrong wire type"}
+            1: 6
+            2: {"a"}
+            2: {"b"}
+            3: 1 3: {`0203`} 3: 4
+            4: -1i32 4: {`feffffff`}
+            5: { 1: 1 }
+            5: !{ 2: 2 }
+            6: { 1: {"only"} }
+            7: {`ff`}
+            "#,
+        )
+        .unwrap();
+        let message = Message::from(WireData::new(message.serialize().as_ref().to_vec()));
+
+        assert_eq!(message.get_varint(1).unwrap().unwrap().get(), 6);
+        assert!(message.get_i32(1).unwrap().is_none());
+        assert!(message.get_varint(9).unwrap().is_none());
+        assert_eq!(message.get_str(2).unwrap(), Some("b"));
+        assert_eq!(message.get_all_strs(2).unwrap(), ["a", "b"]);
+        assert_eq!(message.get_all_varints(3).unwrap(), [1, 2, 3, 4]);
+        assert_eq!(message.get_all_i32s(4).unwrap(), [-1, -2]);
+
+        // occurrences of a message are merged, whether encoded as a Len or a group
+        let merged = message.get_message(5).unwrap().unwrap();
+        assert_eq!(merged.get_varint(1).unwrap().unwrap().get(), 1);
+        assert_eq!(merged.get_varint(2).unwrap().unwrap().get(), 2);
+        assert_eq!(message.get_all_messages(5).unwrap().len(), 2);
+        let only = message.get_message(6).unwrap().unwrap();
+        assert_eq!(only.get_str(1).unwrap(), Some("only"));
+
+        let error = message.get_str(7).err().unwrap();
+        assert_eq!(error.kind(), &DecodeErrorKind::InvalidUtf8);
+        assert_eq!(error.path(), &[7]);
+        assert_eq!(&message.0.as_ref()[error.offset()..], &[0xff]);
+    }
+
     fn impl_complex_test(data: WireData) {
         // get 1: -13.37.i32
         let (view, remainder) = Field::from(data).unwrap();
@@ -319,6 +2150,10 @@ mod tests {
 
     #[test]
     fn test_complex() {
+        impl_complex_test(complex_data());
+    }
+
+    fn complex_data() -> WireData {
         /* Test based off the following protoscope:
         1: -13.37i32
         2: !{
@@ -336,7 +2171,7 @@ mod tests {
                 }
         }
                     */
-        let data = WireData::new(vec![
+        WireData::new(vec![
             0b00001101, 0b10000101, 0b11101011, 0b01010101, 0b11000001, 0b00010011, 0b00001001,
             0b00111101, 0b00001010, 0b11010111, 0b10100011, 0b01110000, 0b10111101, 0b00101010,
             0b01000000, 0b00010010, 0b00001101, 0b01101000, 0b01100101, 0b01101100, 0b01101100,
@@ -347,8 +2182,256 @@ mod tests {
             0b01101111, 0b00001010, 0b00000001, 0b00101100, 0b00001010, 0b00000001, 0b00100000,
             0b00001010, 0b00000110, 0b01110111, 0b01101111, 0b01110010, 0b01101100, 0b01100100,
             0b00100001,
-        ]);
+        ])
+    }
+
+    /// a `FileDescriptorSet` of two files, as protoc would write it for:
+    ///
+    /// ```proto
+    /// // types.proto
+    /// syntax = "proto3";
+    /// package test.types;
+    /// enum Color { COLOR_UNSPECIFIED = 0; RED = 1; BLUE = 2; }
+    /// message Point { int32 x = 1; int32 y = 2; }
+    ///
+    /// // shop.proto
+    /// syntax = "proto3";
+    /// package test.shop;
+    /// import "types.proto";
+    /// message Order {
+    ///   message Item { string name = 1; uint32 quantity = 2; fixed64 price_cents = 3; }
+    ///   string id = 1;
+    ///   repeated Item items = 2;
+    ///   map<string, int64> tags = 3;
+    ///   test.types.Color color = 4;
+    ///   oneof payment { string card = 5; uint64 voucher = 6; }
+    ///   optional double discount = 7;
+    ///   repeated sint32 deltas = 8;
+    ///   test.types.Point location = 9;
+    ///   bytes blob = 10;
+    /// }
+    /// service Shop { rpc Place(Order) returns (types.Point); }
+    /// ```
+    ///
+    /// except that some type names are left relative, to exercise name resolution
+    fn descriptor_set() -> Vec<u8> {
+        let text = r#"
+            1: {
+              1: {"types.proto"} 2: {"test.types"} 12: {"proto3"}
+              5: {
+                1: {"Color"}
+                2: {1: {"COLOR_UNSPECIFIED"} 2: 0}
+                2: {1: {"RED"} 2: 1}
+                2: {1: {"BLUE"} 2: 2}
+              }
+              4: {
+                1: {"Point"}
+                2: {1: {"x"} 3: 1 4: 1 5: 5 10: {"x"}}
+                2: {1: {"y"} 3: 2 4: 1 5: 5 10: {"y"}}
+              }
+            }
+            1: {
+              1: {"shop.proto"} 2: {"test.shop"} 3: {"types.proto"} 12: {"proto3"}
+              4: {
+                1: {"Order"}
+                2: {1: {"id"} 3: 1 4: 1 5: 9 10: {"id"}}
+                2: {1: {"items"} 3: 2 4: 3 5: 11 6: {"Item"} 10: {"items"}}
+                2: {1: {"tags"} 3: 3 4: 3 5: 11 6: {".test.shop.Order.TagsEntry"} 10: {"tags"}}
+                2: {1: {"color"} 3: 4 4: 1 5: 14 6: {".test.types.Color"} 10: {"color"}}
+                2: {1: {"card"} 3: 5 4: 1 5: 9 9: 0 10: {"card"}}
+                2: {1: {"voucher"} 3: 6 4: 1 5: 4 9: 0 10: {"voucher"}}
+                2: {1: {"discount"} 3: 7 4: 1 5: 1 9: 1 10: {"discount"} 17: 1}
+                2: {1: {"deltas"} 3: 8 4: 3 5: 17 10: {"deltas"}}
+                2: {1: {"location"} 3: 9 4: 1 5: 11 6: {"test.types.Point"} 10: {"location"}}
+                2: {1: {"blob"} 3: 10 4: 1 5: 12 10: {"blob"}}
+                3: {
+                  1: {"Item"}
+                  2: {1: {"name"} 3: 1 4: 1 5: 9 10: {"name"}}
+                  2: {1: {"quantity"} 3: 2 4: 1 5: 13 10: {"quantity"}}
+                  2: {1: {"price_cents"} 3: 3 4: 1 5: 6 10: {"priceCents"}}
+                }
+                3: {
+                  1: {"TagsEntry"}
+                  2: {1: {"key"} 3: 1 4: 1 5: 9 10: {"key"}}
+                  2: {1: {"value"} 3: 2 4: 1 5: 3 10: {"value"}}
+                  7: {7: 1}
+                }
+                8: {1: {"payment"}}
+                8: {1: {"_discount"}}
+              }
+              6: {
+                1: {"Shop"}
+                2: {1: {"Place"} 2: {".test.shop.Order"} 3: {"types.Point"}}
+              }
+            }
+        "#;
+        Message::from_protoscope(text)
+            .unwrap()
+            .serialize()
+            .as_ref()
+            .to_vec()
+    }
+
+    fn json_descriptor_set() -> Vec<u8> {
+        let text = r#"
+            1: {
+              1: {"google/protobuf/wkt.proto"} 2: {"google.protobuf"} 12: {"proto3"}
+              4: {
+                1: {"Timestamp"}
+                2: {1: {"seconds"} 3: 1 4: 1 5: 3 10: {"seconds"}}
+                2: {1: {"nanos"} 3: 2 4: 1 5: 5 10: {"nanos"}}
+              }
+              4: {
+                1: {"Duration"}
+                2: {1: {"seconds"} 3: 1 4: 1 5: 3 10: {"seconds"}}
+                2: {1: {"nanos"} 3: 2 4: 1 5: 5 10: {"nanos"}}
+              }
+              4: {1: {"Int64Value"} 2: {1: {"value"} 3: 1 4: 1 5: 3 10: {"value"}}}
+              4: {1: {"StringValue"} 2: {1: {"value"} 3: 1 4: 1 5: 9 10: {"value"}}}
+              4: {1: {"FieldMask"} 2: {1: {"paths"} 3: 1 4: 3 5: 9 10: {"paths"}}}
+              4: {
+                1: {"Any"}
+                2: {1: {"type_url"} 3: 1 4: 1 5: 9 10: {"typeUrl"}}
+                2: {1: {"value"} 3: 2 4: 1 5: 12 10: {"value"}}
+              }
+              4: {
+                1: {"Struct"}
+                2: {1: {"fields"} 3: 1 4: 3 5: 11 6: {".google.protobuf.Struct.FieldsEntry"} 10: {"fields"}}
+                3: {
+                  1: {"FieldsEntry"}
+                  2: {1: {"key"} 3: 1 4: 1 5: 9 10: {"key"}}
+                  2: {1: {"value"} 3: 2 4: 1 5: 11 6: {".google.protobuf.Value"} 10: {"value"}}
+                  7: {7: 1}
+                }
+              }
+              4: {
+                1: {"Value"}
+                2: {1: {"null_value"} 3: 1 4: 1 5: 14 6: {".google.protobuf.NullValue"} 9: 0 10: {"nullValue"}}
+                2: {1: {"number_value"} 3: 2 4: 1 5: 1 9: 0 10: {"numberValue"}}
+                2: {1: {"string_value"} 3: 3 4: 1 5: 9 9: 0 10: {"stringValue"}}
+                2: {1: {"bool_value"} 3: 4 4: 1 5: 8 9: 0 10: {"boolValue"}}
+                2: {1: {"struct_value"} 3: 5 4: 1 5: 11 6: {".google.protobuf.Struct"} 9: 0 10: {"structValue"}}
+                2: {1: {"list_value"} 3: 6 4: 1 5: 11 6: {".google.protobuf.ListValue"} 9: 0 10: {"listValue"}}
+                8: {1: {"kind"}}
+              }
+              4: {1: {"ListValue"} 2: {1: {"values"} 3: 1 4: 3 5: 11 6: {".google.protobuf.Value"} 10: {"values"}}}
+              5: {1: {"NullValue"} 2: {1: {"NULL_VALUE"} 2: 0}}
+            }
+            1: {
+              1: {"event.proto"} 2: {"test.event"} 3: {"google/protobuf/wkt.proto"} 12: {"proto3"}
+              4: {
+                1: {"Event"}
+                2: {1: {"at"} 3: 1 4: 1 5: 11 6: {".google.protobuf.Timestamp"} 10: {"at"}}
+                2: {1: {"took"} 3: 2 4: 1 5: 11 6: {".google.protobuf.Duration"} 10: {"took"}}
+                2: {1: {"retries"} 3: 3 4: 1 5: 11 6: {".google.protobuf.Int64Value"} 10: {"retries"}}
+                2: {1: {"note"} 3: 4 4: 1 5: 11 6: {".google.protobuf.StringValue"} 10: {"note"}}
+                2: {1: {"attrs"} 3: 5 4: 1 5: 11 6: {".google.protobuf.Struct"} 10: {"attrs"}}
+                2: {1: {"update_mask"} 3: 6 4: 1 5: 11 6: {".google.protobuf.FieldMask"} 10: {"updateMask"}}
+                2: {1: {"detail"} 3: 7 4: 1 5: 11 6: {".google.protobuf.Any"} 10: {"detail"}}
+                2: {1: {"size"} 3: 8 4: 1 5: 3 10: {"size"}}
+                2: {1: {"ratio"} 3: 9 4: 1 5: 2 10: {"ratio"}}
+              }
+            }
+        "#;
+        Message::from_protoscope(text)
+            .unwrap()
+            .serialize()
+            .as_ref()
+            .to_vec()
+    }
+
+    fn config_descriptor_set() -> Vec<u8> {
+        let text = r#"
+            1: {
+              1: {"config.proto"} 2: {"test.config"} 3: {"google/protobuf/wkt.proto"}
+              4: {
+                1: {"Config"}
+                2: {1: {"name"} 3: 1 4: 1 5: 9}
+                2: {1: {"ports"} 3: 2 4: 3 5: 5}
+                2: {1: {"limits"} 3: 3 4: 1 5: 10 6: {".test.config.Config.Limits"}}
+                2: {1: {"extra"} 3: 4 4: 1 5: 11 6: {".google.protobuf.Any"}}
+                2: {1: {"ratio"} 3: 5 4: 1 5: 1}
+                2: {1: {"blob"} 3: 6 4: 1 5: 12}
+                3: {1: {"Limits"} 2: {1: {"max"} 3: 1 4: 1 5: 13}}
+              }
+              7: {1: {"priority"} 2: {".test.config.Config"} 3: 100 4: 1 5: 5}
+            }
+        "#;
+        Message::from_protoscope(text)
+            .unwrap()
+            .serialize()
+            .as_ref()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_borrowed_views() {
+        let data = complex_data();
+        let message = MessageRef::new(data.as_ref());
 
-        impl_complex_test(data);
+        let mut fields = message.into_iter();
+        let field = fields.next().unwrap();
+        assert_eq!(field.get_field_id(), 1);
+        assert_eq!(field.as_i32().unwrap().get_float(), -13.37);
+        assert_eq!(field.as_bytes(), &data.as_ref()[..5]);
+
+        let field = fields.next().unwrap();
+        assert_eq!(field.get_field_id(), 2);
+        let group = field.as_group().unwrap();
+        assert_eq!(group.get_fields().len(), 2);
+        assert_eq!(group.get_fields()[0].as_i64().unwrap().get_double(), 13.37);
+        assert_eq!(
+            group.get_fields()[1].as_len().unwrap().as_str().unwrap(),
+            "hello, world!"
+        );
+
+        // converting to owned yields the same bytes
+        let owned = field.clone().into_owned();
+        assert_eq!(owned.serialize().as_ref(), field.as_bytes());
+
+        let field = fields.next().unwrap();
+        assert_eq!(field.get_field_id(), 3);
+        assert!(fields.next().is_none());
+
+        let mut inner = field.into_len().unwrap().into_message().into_iter();
+        assert_eq!(inner.next().unwrap().into_varint().unwrap().get(), 10101);
+        assert_eq!(
+            inner
+                .next()
+                .unwrap()
+                .into_varint()
+                .unwrap()
+                .as_proto_sint64(),
+            -5
+        );
+        let repeated: Vec<&str> = inner
+            .next()
+            .unwrap()
+            .into_len()
+            .unwrap()
+            .into_message()
+            .into_iter()
+            .map(|field| field.into_len().unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(repeated, ["hello", ",", " ", "world!"]);
+
+        // the owned and borrowed parsers agree on errors
+        let truncated = &data.as_ref()[..20];
+        let mut iter = MessageRef::new(truncated).into_try_iter();
+        assert!(iter.next().unwrap().is_ok());
+        let error = iter.next().unwrap().err().unwrap();
+        let mut owned_iter = Message(WireData::new(truncated.to_vec())).into_try_iter();
+        owned_iter.next();
+        assert_eq!(owned_iter.next().unwrap().err().unwrap(), error);
+        assert_eq!(iter.into_remainder(), &truncated[5..]);
+
+        // owned types can be viewed without copying
+        let message = MessageRef::new(data.as_ref()).into_owned();
+        assert_eq!(
+            message.view().as_bytes().as_ptr(),
+            message.0.as_ref().as_ptr()
+        );
+        let len = Len::new_string("hello");
+        assert_eq!(len.view().as_str().unwrap(), "hello");
     }
 }
@@ -1,14 +1,37 @@
+use alloc::vec::Vec;
+
+use crate::borrowed::{FieldIdIter, FieldRef, MessageRef};
+use crate::decode_options::DecodeOptions;
+use crate::error::{DecodeError, DecodeErrorKind};
 use crate::field::Field;
+use crate::field_path::FieldPath;
+use crate::field_span::FieldSpan;
 use crate::wire_data::WireData;
 
+#[derive(Clone)]
 pub struct Message(pub(crate) WireData);
 
-impl std::default::Default for Message {
+impl core::default::Default for Message {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// messages are equal when their wire data is, byte for byte
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_ref() == other.0.as_ref()
+    }
+}
+
+impl Eq for Message {}
+
+impl From<WireData> for Message {
+    fn from(data: WireData) -> Self {
+        Self(data)
+    }
+}
+
 impl Message {
     pub fn new() -> Self {
         Message(WireData::Mut(bytes::BytesMut::new()))
@@ -25,16 +48,114 @@ impl Message {
     pub fn serialize(self) -> WireData {
         self.0
     }
+
+    /// borrow the message as a `MessageRef`, without copying or reference counting
+    pub fn view(&self) -> MessageRef<'_> {
+        MessageRef(self.0.as_ref())
+    }
+
+    /// the last occurrence of `field_id`, see `MessageRef::get`
+    pub fn get(&self, field_id: u64) -> Result<Option<FieldRef<'_>>, DecodeError> {
+        self.view().get(field_id)
+    }
+
+    /// the first occurrence of `field_id`, see `MessageRef::first`
+    pub fn first(&self, field_id: u64) -> Result<Option<FieldRef<'_>>, DecodeError> {
+        self.view().first(field_id)
+    }
+
+    /// every occurrence of `field_id`, in the order they appear in the message
+    pub fn get_all(&self, field_id: u64) -> FieldIdIter<'_> {
+        self.view().get_all(field_id)
+    }
+
+    pub fn contains(&self, field_id: u64) -> Result<bool, DecodeError> {
+        self.view().contains(field_id)
+    }
+
+    /// the field at the end of `path`, see `MessageRef::get_path`
+    pub fn get_path(
+        &self,
+        path: impl Into<FieldPath>,
+    ) -> Result<Option<FieldRef<'_>>, DecodeError> {
+        self.view().get_path(path)
+    }
+
+    /// every field matching `path`, see `MessageRef::get_all_path`
+    pub fn get_all_path(
+        &self,
+        path: impl Into<FieldPath>,
+    ) -> Result<Vec<FieldRef<'_>>, DecodeError> {
+        self.view().get_all_path(path)
+    }
+
+    /// checks applied by the message iterators before each field, `remaining` being the
+    /// bytes left to parse and `offset` how many have been parsed so far
+    pub(crate) fn check_limits(
+        remaining: usize,
+        offset: usize,
+        fields: usize,
+        options: &DecodeOptions,
+    ) -> Result<(), DecodeError> {
+        if offset == 0 && remaining > options.max_message_size {
+            Err(DecodeError::new(
+                DecodeErrorKind::MessageTooLarge {
+                    size: remaining,
+                    limit: options.max_message_size,
+                },
+                0,
+            ))
+        } else if fields >= options.max_fields {
+            // offset is added by the iterator along with any other error
+            Err(DecodeError::new(
+                DecodeErrorKind::TooManyFields {
+                    limit: options.max_fields,
+                },
+                0,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn into_try_iter(self) -> MessageTryIter {
+        self.into_try_iter_with_options(&DecodeOptions::default())
+    }
+
+    pub fn into_try_iter_with_options(self, options: &DecodeOptions) -> MessageTryIter {
+        MessageTryIter {
+            data: self.0.into_const(),
+            options: *options,
+            base: 0,
+            offset: 0,
+            fields: 0,
+            failed: false,
+        }
+    }
+
+    /// iterates over clones of the fields along with their spans, without consuming the
+    /// message. Iteration stops after the error of the first field which fails to parse
+    pub fn iter(&self) -> MessageSpanIter {
+        self.iter_at(0)
+    }
+
+    /// like `iter`, but with spans starting at `offset`, for a message parsed out of a
+    /// larger buffer, e.g. `inner.iter_at(span.value.start)` for the payload of a `Len`
+    pub fn iter_at(&self, offset: usize) -> MessageSpanIter {
+        let mut iter = Message(self.0.clone()).into_try_iter();
+        iter.base = offset;
+        MessageSpanIter(iter)
+    }
 }
 
-pub struct MessageIter(Message);
+pub struct MessageIter(MessageTryIter);
 
 impl IntoIterator for Message {
     type IntoIter = MessageIter;
     type Item = <Self::IntoIter as Iterator>::Item;
 
     fn into_iter(self) -> Self::IntoIter {
-        MessageIter(self)
+        MessageIter(self.into_try_iter())
     }
 }
 
@@ -42,19 +163,88 @@ impl Iterator for MessageIter {
     type Item = Field;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.0 .0.is_empty() {
-            return None;
-        }
+        // parse the field or return None, the error is only visible through MessageTryIter
+        self.0.next()?.ok()
+    }
+}
+
+/// Iterator over the fields of a `Message` along with where each of them was parsed from
+///
+/// Like `MessageTryIter`, iteration stops after the first error and `into_remainder`
+/// returns the data starting at the field that failed to parse.
+pub struct MessageSpanIter(MessageTryIter);
+
+impl MessageSpanIter {
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> WireData {
+        self.0.into_remainder()
+    }
+}
+
+impl Iterator for MessageSpanIter {
+    type Item = Result<(FieldSpan, Field), DecodeError>;
 
-        // replace self.0 with empty data to pass ownership of data to the next field
-        let data = std::mem::replace(&mut self.0 .0, WireData::new(vec![]));
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.map(|field| {
+            // fields are always parsed with their span
+            let span = field.span.clone().unwrap();
+            (span, field)
+        }))
+    }
+}
+
+/// Iterator over the fields of a `Message` which reports decode errors
+///
+/// Iteration stops after the first error, at which point `into_remainder` returns the
+/// data starting at the field that failed to parse.
+pub struct MessageTryIter {
+    data: WireData,
+    options: DecodeOptions,
+    /// where the message starts in the buffer the spans of its fields are relative to
+    base: usize,
+    offset: usize,
+    fields: usize,
+    failed: bool,
+}
+
+impl MessageTryIter {
+    /// byte offset of the next field to be parsed, relative to the start of the message
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
 
-        // parse the field or return None
-        let (field, remainder) = Field::from(data).ok()?;
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> WireData {
+        self.data
+    }
+}
 
-        // restore self.0.0 to whatever data remains after the field was parsed
-        self.0 .0 = remainder;
+impl Iterator for MessageTryIter {
+    type Item = Result<Field, DecodeError>;
 
-        Some(field)
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
+
+        // data is always Const here so this clone is only a reference count, and lets
+        // us keep the unparsed data around if the field fails to parse
+        let result =
+            Message::check_limits(self.data.len(), self.offset, self.fields, &self.options)
+                .and_then(|_| {
+                    Field::parse(self.data.clone(), &self.options, 0, self.base + self.offset)
+                });
+        match result {
+            Ok((field, remainder)) => {
+                self.offset += self.data.len() - remainder.len();
+                self.fields += 1;
+                self.data = remainder;
+                Some(Ok(field))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.shift(self.offset)))
+            }
+        }
     }
 }
@@ -0,0 +1,371 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::borrowed::{range_of, FieldRef, MessageObjectRef, MessageRef};
+use crate::error::DecodeError;
+use crate::field::Field;
+use crate::field_path::{FieldPath, PathError, PathSegment};
+use crate::len::Len;
+use crate::message::Message;
+use crate::message_object::MessageObject;
+use crate::varint::Varint;
+
+/// An error raised by `Message::insert_at`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// the message could not be decoded
+    Decode(DecodeError),
+    /// `index` is past the end of a message of `len` fields
+    IndexOutOfBounds { index: usize, len: usize },
+}
+
+impl From<DecodeError> for EditError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl core::fmt::Display for EditError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "{e}"),
+            Self::IndexOutOfBounds { index, len } => {
+                write!(f, "index {index} is past the end of {len} fields")
+            }
+        }
+    }
+}
+
+impl core::error::Error for EditError {}
+
+/// A replacement of the bytes in `range` of the message, offsets being absolute
+struct Edit {
+    range: Range<usize>,
+    bytes: Vec<u8>,
+}
+
+/// A message or group reached by walking a path, as byte ranges in the outermost message
+struct Container {
+    /// the encoded fields of the message or group
+    content: Range<usize>,
+    /// the length prefix of every `Len` enclosing the container, outermost first
+    prefixes: Vec<Range<usize>>,
+    /// the field ids walked to reach the container, so errors can report their path
+    field_ids: Vec<u64>,
+}
+
+/// Where the walk along the parent segments of a path ended
+enum Location {
+    /// every segment was found, the container is the parent of the final segment
+    Found(Container),
+    /// the segment at `depth` has no (matching) occurrence in `container`
+    Missing { container: Container, depth: usize },
+}
+
+impl Message {
+    /// replaces the field at the end of `path` with `object`, editing the encoded message
+    /// in place
+    ///
+    /// Only the bytes of the replaced field and the length prefixes of the submessages
+    /// enclosing it are rewritten, every other field keeps its exact encoding. A final
+    /// segment without an index replaces every occurrence of the field with a single one,
+    /// placed where the first occurrence was, or appended to its parent if there were
+    /// none. Missing intermediate messages are created as long as their segments have no
+    /// index.
+    pub fn set_path(
+        &mut self,
+        path: impl Into<FieldPath>,
+        object: MessageObject,
+    ) -> Result<(), PathError> {
+        let path = path.into();
+        let Some((last, parents)) = path.segments().split_last() else {
+            return Err(PathError::Empty);
+        };
+
+        let data = self.0.as_ref();
+        let (container, edits) = match locate(data, parents)? {
+            Location::Found(container) => {
+                let fields = container.fields(data, last.field_id)?;
+                let field = bytes_of(Field::new(last.field_id, object));
+                let edits = match last.index {
+                    Some(index) => {
+                        let Some(existing) = fields.get(index) else {
+                            return Err(PathError::IndexOutOfRange {
+                                depth: parents.len(),
+                                index,
+                            });
+                        };
+                        Vec::from([Edit {
+                            range: range_of(data, existing.raw),
+                            bytes: field,
+                        }])
+                    }
+                    None => replace_all(
+                        fields.iter().map(|existing| range_of(data, existing.raw)),
+                        container.content.end,
+                        field,
+                    ),
+                };
+                (container, edits)
+            }
+            Location::Missing { container, depth } => {
+                if let Some(index) = path.segments()[depth].index {
+                    return Err(PathError::IndexOutOfRange { depth, index });
+                }
+
+                // build the missing messages from the inside out
+                let mut field = Field::new(last.field_id, object);
+                for segment in parents[depth..].iter().rev() {
+                    let mut message = Message::new();
+                    message.push(field);
+                    field = Field::new(
+                        segment.field_id,
+                        MessageObject::Len(Len::new_message(message)),
+                    );
+                }
+                let edits = Vec::from([Edit {
+                    range: container.content.end..container.content.end,
+                    bytes: bytes_of(field),
+                }]);
+                (container, edits)
+            }
+        };
+
+        self.apply(&container, edits);
+        Ok(())
+    }
+
+    /// removes the field at the end of `path`, returning how many occurrences were removed
+    ///
+    /// A final segment without an index removes every occurrence of the field. As with
+    /// `set_path` only the length prefixes of the enclosing submessages are rewritten, and
+    /// a path which leads nowhere removes nothing.
+    pub fn remove_path(&mut self, path: impl Into<FieldPath>) -> Result<usize, PathError> {
+        let path = path.into();
+        let Some((last, parents)) = path.segments().split_last() else {
+            return Err(PathError::Empty);
+        };
+
+        let data = self.0.as_ref();
+        let Location::Found(container) = locate(data, parents)? else {
+            return Ok(0);
+        };
+        let fields = container.fields(data, last.field_id)?;
+        let removed: Vec<&FieldRef<'_>> = match last.index {
+            Some(index) => fields.get(index).into_iter().collect(),
+            None => fields.iter().collect(),
+        };
+        let edits: Vec<Edit> = removed
+            .into_iter()
+            .map(|field| Edit {
+                range: range_of(data, field.raw),
+                bytes: Vec::new(),
+            })
+            .collect();
+
+        let removed = edits.len();
+        self.apply(&container, edits);
+        Ok(removed)
+    }
+
+    /// replaces every occurrence of `field_id` with a single field holding `object`,
+    /// placed where the first occurrence was, or appended if there were none
+    ///
+    /// The message is edited in place, every other field keeps its exact encoding.
+    pub fn set(&mut self, field_id: u64, object: MessageObject) -> Result<(), DecodeError> {
+        let data = self.0.as_ref();
+        let mut ranges = Vec::new();
+        for field in self.get_all(field_id) {
+            ranges.push(range_of(data, field?.raw));
+        }
+
+        let edits = replace_all(ranges, data.len(), bytes_of(Field::new(field_id, object)));
+        self.splice_edits(edits);
+        Ok(())
+    }
+
+    /// removes every occurrence of `field_id`, returning how many there were
+    pub fn remove(&mut self, field_id: u64) -> Result<usize, DecodeError> {
+        self.retain(|field| field.get_field_id() != field_id)
+    }
+
+    /// inserts `f` so that it becomes the field at position `index`, counting every field
+    /// in the message regardless of its id
+    ///
+    /// Fails with `EditError::IndexOutOfBounds` if `index` is greater than the number of
+    /// fields in the message, leaving it unchanged.
+    pub fn insert_at(&mut self, index: usize, f: Field) -> Result<(), EditError> {
+        let mut iter = self.view().into_try_iter();
+        let mut count = 0;
+        while count < index {
+            match iter.next() {
+                Some(field) => field?,
+                None => return Err(EditError::IndexOutOfBounds { index, len: count }),
+            };
+            count += 1;
+        }
+
+        let at = iter.offset();
+        self.splice_edits(Vec::from([Edit {
+            range: at..at,
+            bytes: bytes_of(f),
+        }]));
+        Ok(())
+    }
+
+    /// keeps only the fields for which `keep` returns true, returning how many were
+    /// removed
+    ///
+    /// The whole message is parsed before anything is removed, so on a decode error the
+    /// message is left unchanged.
+    pub fn retain(
+        &mut self,
+        mut keep: impl FnMut(&FieldRef<'_>) -> bool,
+    ) -> Result<usize, DecodeError> {
+        let data = self.0.as_ref();
+        let mut edits = Vec::new();
+        for field in self.view().into_try_iter() {
+            let field = field?;
+            if !keep(&field) {
+                edits.push(Edit {
+                    range: range_of(data, field.raw),
+                    bytes: Vec::new(),
+                });
+            }
+        }
+
+        let removed = edits.len();
+        self.splice_edits(edits);
+        Ok(removed)
+    }
+
+    /// splices `edits`, which must be in order and not overlap, into the message and
+    /// returns how much it grew (or shrank) by
+    fn splice_edits(&mut self, edits: Vec<Edit>) -> isize {
+        let mut delta = 0isize;
+        // working backwards keeps the ranges of the remaining edits valid
+        for edit in edits.into_iter().rev() {
+            delta += edit.bytes.len() as isize - edit.range.len() as isize;
+            self.0.splice(edit.range, &edit.bytes);
+        }
+        delta
+    }
+
+    /// splices `edits` (which are in order and all inside `container`) into the message,
+    /// then rewrites the enclosing length prefixes for the change in size
+    fn apply(&mut self, container: &Container, edits: Vec<Edit>) {
+        let mut delta = self.splice_edits(edits);
+
+        // prefixes come before the edits, and the innermost prefix after the ones enclosing
+        // it, so splicing innermost first doesn't move the prefixes still to be rewritten
+        for prefix in container.prefixes.iter().rev() {
+            if delta == 0 {
+                break;
+            }
+            let length = Varint::decode(&self.0.as_ref()[prefix.clone()]);
+            let length = Varint::new((length as isize + delta) as u64);
+            delta += length.0.len() as isize - prefix.len() as isize;
+            self.0.splice(prefix.clone(), length.0.as_ref());
+        }
+    }
+}
+
+impl Container {
+    /// the occurrences of `field_id` directly inside the container, in order
+    fn fields<'a>(&self, data: &'a [u8], field_id: u64) -> Result<Vec<FieldRef<'a>>, PathError> {
+        let mut fields = Vec::new();
+        for field in MessageRef(&data[self.content.clone()]).into_try_iter() {
+            let field = field.map_err(|e| self.translate(e))?;
+            if field.get_field_id() == field_id {
+                fields.push(field);
+            }
+        }
+        Ok(fields)
+    }
+
+    /// the container holding the fields of `field`, which was found at `depth` of the path
+    fn descend(
+        mut self,
+        data: &[u8],
+        field: &FieldRef<'_>,
+        depth: usize,
+    ) -> Result<Self, PathError> {
+        let range = range_of(data, field.raw);
+        let value = range.start + field.tag.0.len();
+        self.content = match &field.data {
+            MessageObjectRef::Len(len) => {
+                let payload = value + len.length.0.len();
+                self.prefixes.push(value..payload);
+                payload..range.end
+            }
+            MessageObjectRef::Group(group) => value..range.end - group.end_field_id.0.len(),
+            _ => return Err(PathError::NotAContainer { depth }),
+        };
+        self.field_ids.push(field.get_field_id());
+
+        Ok(self)
+    }
+
+    /// moves an error from parsing the container's content into the outermost message
+    fn translate(&self, e: DecodeError) -> PathError {
+        let e = e.shift(self.content.start);
+        PathError::Decode(
+            self.field_ids
+                .iter()
+                .rev()
+                .fold(e, |e, field_id| e.within(*field_id)),
+        )
+    }
+}
+
+/// walks `parents` from the outermost message, following the selected occurrence of each
+/// segment or the last one when it has no index
+fn locate(data: &[u8], parents: &[PathSegment]) -> Result<Location, PathError> {
+    let mut container = Container {
+        content: 0..data.len(),
+        prefixes: Vec::new(),
+        field_ids: Vec::new(),
+    };
+    for (depth, segment) in parents.iter().enumerate() {
+        let mut fields = container.fields(data, segment.field_id)?;
+        let field = match segment.index {
+            Some(index) if index < fields.len() => Some(fields.swap_remove(index)),
+            Some(_) => None,
+            None => fields.pop(),
+        };
+        let Some(field) = field else {
+            return Ok(Location::Missing { container, depth });
+        };
+        container = container.descend(data, &field, depth)?;
+    }
+
+    Ok(Location::Found(container))
+}
+
+/// the edits replacing the fields at `ranges` with a single `field`, which takes the place
+/// of the first one or is inserted at `end` when there are none
+fn replace_all(
+    ranges: impl IntoIterator<Item = Range<usize>>,
+    end: usize,
+    field: Vec<u8>,
+) -> Vec<Edit> {
+    let mut field = Some(field);
+    let mut edits: Vec<Edit> = ranges
+        .into_iter()
+        .map(|range| Edit {
+            range,
+            bytes: field.take().unwrap_or_default(),
+        })
+        .collect();
+    if let Some(bytes) = field {
+        edits.push(Edit {
+            range: end..end,
+            bytes,
+        });
+    }
+    edits
+}
+
+fn bytes_of(field: Field) -> Vec<u8> {
+    field.serialize().as_ref().to_vec()
+}
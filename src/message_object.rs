@@ -22,7 +22,7 @@ impl MessageObject {
         WireData::Mut(dest)
     }
 
-    pub(crate) fn serialize_into(self, dest: &mut bytes::BytesMut) {
+    pub(crate) fn serialize_into(&self, dest: &mut bytes::BytesMut) {
         match self {
             MessageObject::Varint(value) => dest.extend_from_slice(value.0.as_ref()),
             MessageObject::I64(value) => dest.extend_from_slice(value.0.as_ref()),
@@ -0,0 +1,434 @@
+use alloc::vec::Vec;
+
+use bytes::{Bytes, BytesMut};
+
+use crate::borrowed::{
+    range_of, MessageRef, PackedRepeatedI32Ref, PackedRepeatedI64Ref, PackedRepeatedVarintRef,
+};
+use crate::classify::{RawField, RawMessage, RawValue};
+use crate::decode_options::DecodeOptions;
+use crate::error::DecodeError;
+use crate::message::Message;
+use crate::varint::Varint;
+use crate::wire_data::WireData;
+
+/// A fully expanded, editable tree of the fields in a message
+///
+/// Every field becomes a `FieldNode`, and `Len` payloads which decode as a message become
+/// nested trees. Each node remembers the bytes it was parsed from until it is modified,
+/// and `serialize` writes untouched nodes back out exactly as they were, so only the nodes
+/// on the way to an edit are re-encoded, with their length prefixes recomputed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageTree {
+    fields: Vec<FieldNode>,
+}
+
+/// One field of a `MessageTree`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldNode {
+    field_id: u64,
+    value: Node,
+    /// the encoding the node was parsed from, tag included, dropped by any modification
+    original: Option<Bytes>,
+}
+
+/// The value of a `FieldNode`
+///
+/// The wire format can't tell strings, bytes and packed arrays apart from each other, or
+/// reliably from messages, so when a tree is built a `Len` payload becomes whatever
+/// `LenRef::classify` takes it for, with strings becoming `Bytes`. The `expand_*` and
+/// `collapse` methods switch between interpretations.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Varint(u64),
+    I64(i64),
+    I32(i32),
+    Bytes(Vec<u8>),
+    Message(MessageTree),
+    Group(MessageTree),
+    PackedVarint(Vec<u64>),
+    PackedI64(Vec<i64>),
+    PackedI32(Vec<i32>),
+    EGroup,
+}
+
+impl MessageTree {
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    pub fn from(message: Message) -> Result<Self, DecodeError> {
+        Self::from_with_options(message, &DecodeOptions::default())
+    }
+
+    /// expands `message`, `options` applying to the outermost message, with `max_depth`
+    /// also bounding how deep nested messages are expanded
+    pub fn from_with_options(
+        message: Message,
+        options: &DecodeOptions,
+    ) -> Result<Self, DecodeError> {
+        let data = message.0.into_bytes();
+        let mut fields = Vec::new();
+        for field in MessageRef(&data).into_try_iter_with_options(options) {
+            let field = RawField::classify(field?, options.max_depth);
+            fields.push(FieldNode::expand(&data, field));
+        }
+
+        Ok(Self { fields })
+    }
+
+    pub fn fields(&self) -> &[FieldNode] {
+        &self.fields
+    }
+
+    /// the fields themselves, to insert, remove or reorder them freely
+    pub fn fields_mut(&mut self) -> &mut Vec<FieldNode> {
+        &mut self.fields
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn push(&mut self, field_id: u64, value: Node) {
+        self.fields.push(FieldNode::new(field_id, value));
+    }
+
+    /// the last occurrence of `field_id`, following protobuf's last-one-wins semantics
+    pub fn get(&self, field_id: u64) -> Option<&FieldNode> {
+        self.get_all(field_id).last()
+    }
+
+    pub fn get_mut(&mut self, field_id: u64) -> Option<&mut FieldNode> {
+        self.fields
+            .iter_mut()
+            .rev()
+            .find(|field| field.field_id == field_id)
+    }
+
+    /// every occurrence of `field_id`, in order
+    pub fn get_all(&self, field_id: u64) -> impl DoubleEndedIterator<Item = &FieldNode> {
+        self.fields
+            .iter()
+            .filter(move |field| field.field_id == field_id)
+    }
+
+    /// replaces every occurrence of `field_id` with a single field, placed where the first
+    /// occurrence was or appended if there were none
+    pub fn set(&mut self, field_id: u64, value: Node) {
+        match self
+            .fields
+            .iter()
+            .position(|field| field.field_id == field_id)
+        {
+            Some(first) => {
+                let mut position = 0;
+                self.fields.retain(|field| {
+                    let keep = position <= first || field.field_id != field_id;
+                    position += 1;
+                    keep
+                });
+                self.fields[first] = FieldNode::new(field_id, value);
+            }
+            None => self.push(field_id, value),
+        }
+    }
+
+    /// removes every occurrence of `field_id`, returning how many there were
+    pub fn remove(&mut self, field_id: u64) -> usize {
+        let len = self.fields.len();
+        self.fields.retain(|field| field.field_id != field_id);
+        len - self.fields.len()
+    }
+
+    pub fn retain(&mut self, keep: impl FnMut(&FieldNode) -> bool) {
+        self.fields.retain(keep);
+    }
+
+    /// how many bytes `serialize` will produce
+    pub fn byte_len(&self) -> usize {
+        self.fields.iter().map(FieldNode::byte_len).sum()
+    }
+
+    pub fn serialize(&self) -> WireData {
+        let mut dest = BytesMut::with_capacity(self.byte_len());
+        self.serialize_into(&mut dest);
+
+        WireData::Mut(dest)
+    }
+
+    pub fn to_message(&self) -> Message {
+        Message(self.serialize())
+    }
+
+    fn serialize_into(&self, dest: &mut BytesMut) {
+        for field in &self.fields {
+            field.serialize_into(dest);
+        }
+    }
+
+    fn expand(root: &Bytes, message: RawMessage<'_>) -> Self {
+        Self {
+            fields: message
+                .fields
+                .into_iter()
+                .map(|field| FieldNode::expand(root, field))
+                .collect(),
+        }
+    }
+}
+
+impl FieldNode {
+    pub fn new(field_id: u64, value: Node) -> Self {
+        Self {
+            field_id,
+            value,
+            original: None,
+        }
+    }
+
+    pub fn field_id(&self) -> u64 {
+        self.field_id
+    }
+
+    pub fn set_field_id(&mut self, field_id: u64) {
+        self.original = None;
+        self.field_id = field_id;
+    }
+
+    pub fn value(&self) -> &Node {
+        &self.value
+    }
+
+    /// mutable access to the value, after which the node is re-encoded when serialized
+    pub fn value_mut(&mut self) -> &mut Node {
+        self.original = None;
+        &mut self.value
+    }
+
+    pub fn set_value(&mut self, value: Node) {
+        self.original = None;
+        self.value = value;
+    }
+
+    pub fn into_value(self) -> Node {
+        self.value
+    }
+
+    /// whether the node will be re-encoded by `serialize`, which is the case for new nodes
+    /// and for parsed ones once they (or anything beneath them) have been modified
+    pub fn is_modified(&self) -> bool {
+        self.original.is_none()
+    }
+
+    /// the bytes the node was parsed from, if it hasn't been modified since
+    pub fn original_bytes(&self) -> Option<&[u8]> {
+        self.original.as_deref()
+    }
+
+    pub fn byte_len(&self) -> usize {
+        match &self.original {
+            Some(original) => original.len(),
+            None => Varint::encoded_len(self.tag()) + self.value.byte_len(self.field_id),
+        }
+    }
+
+    fn tag(&self) -> u64 {
+        (self.field_id << 3) | self.value.wire_type()
+    }
+
+    fn serialize_into(&self, dest: &mut BytesMut) {
+        match &self.original {
+            Some(original) => dest.extend_from_slice(original),
+            None => {
+                Varint::encode_into(self.tag(), dest);
+                self.value.serialize_into(self.field_id, dest);
+            }
+        }
+    }
+
+    /// the node for a classified `field`, which was decoded from `root`
+    fn expand(root: &Bytes, field: RawField<'_>) -> Self {
+        let field_id = field.field.get_field_id();
+        let original = root.slice(range_of(root, field.field.raw));
+        let value = match field.value {
+            RawValue::Varint(value) => Node::Varint(value),
+            RawValue::I64(value) => Node::I64(value),
+            RawValue::I32(value) => Node::I32(value),
+            RawValue::Group(group) => Node::Group(MessageTree::expand(root, group)),
+            RawValue::EGroup => Node::EGroup,
+            RawValue::Message(message) => Node::Message(MessageTree::expand(root, message)),
+            RawValue::PackedVarint(values) => Node::PackedVarint(values),
+            RawValue::PackedI64(values) => Node::PackedI64(values),
+            RawValue::PackedI32(values) => Node::PackedI32(values),
+            RawValue::String(s) => Node::string(s),
+            RawValue::Bytes(bytes) => Node::Bytes(bytes.to_vec()),
+        };
+
+        Self {
+            field_id,
+            value,
+            original: Some(original),
+        }
+    }
+}
+
+impl Node {
+    pub fn string(s: &str) -> Self {
+        Self::Bytes(s.as_bytes().to_vec())
+    }
+
+    pub fn wire_type(&self) -> u64 {
+        match self {
+            Node::Varint(_) => 0,
+            Node::I64(_) => 1,
+            Node::Bytes(_)
+            | Node::Message(_)
+            | Node::PackedVarint(_)
+            | Node::PackedI64(_)
+            | Node::PackedI32(_) => 2,
+            Node::Group(_) => 3,
+            Node::EGroup => 4,
+            Node::I32(_) => 5,
+        }
+    }
+
+    /// reinterprets a `Len` payload as a message, leaving any other node unchanged
+    pub fn expand_message(&mut self) -> Result<(), DecodeError> {
+        if let Some(payload) = self.len_payload() {
+            let tree = MessageTree::from(Message(WireData::new(payload)))?;
+            *self = Node::Message(tree);
+        }
+        Ok(())
+    }
+
+    /// reinterprets a `Len` payload as packed varints, leaving any other node unchanged
+    pub fn expand_packed_varint(&mut self) -> Result<(), DecodeError> {
+        if let Some(payload) = self.len_payload() {
+            let values = PackedRepeatedVarintRef(&payload)
+                .into_try_iter()
+                .map(|value| value.map(|value| value.get()))
+                .collect::<Result<_, _>>()?;
+            *self = Node::PackedVarint(values);
+        }
+        Ok(())
+    }
+
+    /// reinterprets a `Len` payload as packed 64 bit values, leaving any other node
+    /// unchanged
+    pub fn expand_packed_i64(&mut self) -> Result<(), DecodeError> {
+        if let Some(payload) = self.len_payload() {
+            let values = PackedRepeatedI64Ref(&payload)
+                .into_try_iter()
+                .map(|value| value.map(|value| value.get()))
+                .collect::<Result<_, _>>()?;
+            *self = Node::PackedI64(values);
+        }
+        Ok(())
+    }
+
+    /// reinterprets a `Len` payload as packed 32 bit values, leaving any other node
+    /// unchanged
+    pub fn expand_packed_i32(&mut self) -> Result<(), DecodeError> {
+        if let Some(payload) = self.len_payload() {
+            let values = PackedRepeatedI32Ref(&payload)
+                .into_try_iter()
+                .map(|value| value.map(|value| value.get()))
+                .collect::<Result<_, _>>()?;
+            *self = Node::PackedI32(values);
+        }
+        Ok(())
+    }
+
+    /// turns any `Len` interpretation back into plain `Bytes`
+    pub fn collapse(&mut self) {
+        if let Some(payload) = self.len_payload() {
+            *self = Node::Bytes(payload);
+        }
+    }
+
+    /// the encoded payload of a `Len` node
+    fn len_payload(&self) -> Option<Vec<u8>> {
+        match self {
+            Node::Bytes(bytes) => Some(bytes.clone()),
+            Node::Message(_) | Node::PackedVarint(_) | Node::PackedI64(_) | Node::PackedI32(_) => {
+                let mut dest = BytesMut::with_capacity(self.payload_len());
+                self.serialize_payload(&mut dest);
+                Some(dest.to_vec())
+            }
+            _ => None,
+        }
+    }
+
+    /// the size of the value of a field with this node, `field_id` being needed for the
+    /// end-group tag of a group
+    fn byte_len(&self, field_id: u64) -> usize {
+        match self {
+            Node::Varint(value) => Varint::encoded_len(*value),
+            Node::I64(_) => 8,
+            Node::I32(_) => 4,
+            Node::Group(tree) => tree.byte_len() + Varint::encoded_len((field_id << 3) | 4),
+            Node::EGroup => 0,
+            _ => {
+                let len = self.payload_len();
+                Varint::encoded_len(len as u64) + len
+            }
+        }
+    }
+
+    /// the size of a `Len` node without its length prefix
+    fn payload_len(&self) -> usize {
+        match self {
+            Node::Bytes(bytes) => bytes.len(),
+            Node::Message(tree) => tree.byte_len(),
+            Node::PackedVarint(values) => values.iter().map(|v| Varint::encoded_len(*v)).sum(),
+            Node::PackedI64(values) => values.len() * 8,
+            Node::PackedI32(values) => values.len() * 4,
+            _ => 0,
+        }
+    }
+
+    fn serialize_into(&self, field_id: u64, dest: &mut BytesMut) {
+        match self {
+            Node::Varint(value) => Varint::encode_into(*value, dest),
+            Node::I64(value) => dest.extend_from_slice(&value.to_le_bytes()),
+            Node::I32(value) => dest.extend_from_slice(&value.to_le_bytes()),
+            Node::Group(tree) => {
+                tree.serialize_into(dest);
+                Varint::encode_into((field_id << 3) | 4, dest);
+            }
+            Node::EGroup => {}
+            _ => {
+                Varint::encode_into(self.payload_len() as u64, dest);
+                self.serialize_payload(dest);
+            }
+        }
+    }
+
+    fn serialize_payload(&self, dest: &mut BytesMut) {
+        match self {
+            Node::Bytes(bytes) => dest.extend_from_slice(bytes),
+            Node::Message(tree) => tree.serialize_into(dest),
+            Node::PackedVarint(values) => {
+                for value in values {
+                    Varint::encode_into(*value, dest);
+                }
+            }
+            Node::PackedI64(values) => {
+                for value in values {
+                    dest.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            Node::PackedI32(values) => {
+                for value in values {
+                    dest.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::decode_options::DecodeOptions;
+use crate::error::DecodeError;
 use crate::i32::I32;
 use crate::i64::I64;
 use crate::varint::Varint;
@@ -5,7 +7,7 @@ use crate::wire_data::WireData;
 
 pub struct PackedRepeatedVarint(pub(crate) WireData);
 
-impl std::default::Default for PackedRepeatedVarint {
+impl core::default::Default for PackedRepeatedVarint {
     fn default() -> Self {
         Self::new()
     }
@@ -20,19 +22,39 @@ impl PackedRepeatedVarint {
         PackedRepeatedVarint(WireData::Mut(bytes::BytesMut::with_capacity(capacity)))
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn push(&mut self, value: Varint) {
         self.0.get_mut().extend_from_slice(value.0.as_ref());
     }
+
+    pub fn into_try_iter(self) -> PackedRepeatedVarintTryIter {
+        self.into_try_iter_with_options(&DecodeOptions::default())
+    }
+
+    pub fn into_try_iter_with_options(
+        self,
+        options: &DecodeOptions,
+    ) -> PackedRepeatedVarintTryIter {
+        PackedRepeatedVarintTryIter {
+            data: self.0.into_const(),
+            options: *options,
+            offset: 0,
+            failed: false,
+        }
+    }
 }
 
-pub struct PackedRepeatedVarintIter(PackedRepeatedVarint);
+pub struct PackedRepeatedVarintIter(PackedRepeatedVarintTryIter);
 
 impl IntoIterator for PackedRepeatedVarint {
     type IntoIter = PackedRepeatedVarintIter;
     type Item = <Self::IntoIter as Iterator>::Item;
 
     fn into_iter(self) -> Self::IntoIter {
-        PackedRepeatedVarintIter(self)
+        PackedRepeatedVarintIter(self.into_try_iter())
     }
 }
 
@@ -40,26 +62,57 @@ impl Iterator for PackedRepeatedVarintIter {
     type Item = Varint;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.0 .0.is_empty() {
-            return None;
-        }
+        // parse the value or return None, the error is only visible through the TryIter
+        self.0.next()?.ok()
+    }
+}
+
+/// Iterator over packed `Varint` values which reports decode errors, stopping after the first
+pub struct PackedRepeatedVarintTryIter {
+    data: WireData,
+    options: DecodeOptions,
+    offset: usize,
+    failed: bool,
+}
+
+impl PackedRepeatedVarintTryIter {
+    /// byte offset of the next value to be parsed, relative to the start of the packed data
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
 
-        // replace self.0 with empty data to pass ownership of data to the next value
-        let data = std::mem::replace(&mut self.0 .0, WireData::new(vec![]));
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> WireData {
+        self.data
+    }
+}
 
-        // parse the value or return None
-        let (value, remainder) = Varint::from(data).ok()?;
+impl Iterator for PackedRepeatedVarintTryIter {
+    type Item = Result<Varint, DecodeError>;
 
-        // restore self.0 to whatever data remains after the value was parsed
-        self.0 .0 = remainder;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
 
-        Some(value)
+        // data is always Const here so this clone is only a reference count
+        match Varint::from_with_options(self.data.clone(), &self.options) {
+            Ok((value, remainder)) => {
+                self.offset += self.data.len() - remainder.len();
+                self.data = remainder;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.shift(self.offset)))
+            }
+        }
     }
 }
 
 pub struct PackedRepeatedI64(pub(crate) WireData);
 
-impl std::default::Default for PackedRepeatedI64 {
+impl core::default::Default for PackedRepeatedI64 {
     fn default() -> Self {
         Self::new()
     }
@@ -74,19 +127,31 @@ impl PackedRepeatedI64 {
         PackedRepeatedI64(WireData::Mut(bytes::BytesMut::with_capacity(capacity)))
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn push(&mut self, value: I64) {
         self.0.get_mut().extend_from_slice(value.0.as_ref());
     }
+
+    pub fn into_try_iter(self) -> PackedRepeatedI64TryIter {
+        PackedRepeatedI64TryIter {
+            data: self.0.into_const(),
+            offset: 0,
+            failed: false,
+        }
+    }
 }
 
-pub struct PackedRepeatedI64Iter(PackedRepeatedI64);
+pub struct PackedRepeatedI64Iter(PackedRepeatedI64TryIter);
 
 impl IntoIterator for PackedRepeatedI64 {
     type IntoIter = PackedRepeatedI64Iter;
     type Item = <Self::IntoIter as Iterator>::Item;
 
     fn into_iter(self) -> Self::IntoIter {
-        PackedRepeatedI64Iter(self)
+        PackedRepeatedI64Iter(self.into_try_iter())
     }
 }
 
@@ -94,26 +159,56 @@ impl Iterator for PackedRepeatedI64Iter {
     type Item = I64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.0 .0.is_empty() {
-            return None;
-        }
+        // parse the value or return None, the error is only visible through the TryIter
+        self.0.next()?.ok()
+    }
+}
 
-        // replace self.0 with empty data to pass ownership of data to the next value
-        let data = std::mem::replace(&mut self.0 .0, WireData::new(vec![]));
+/// Iterator over packed `I64` values which reports decode errors, stopping after the first
+pub struct PackedRepeatedI64TryIter {
+    data: WireData,
+    offset: usize,
+    failed: bool,
+}
+
+impl PackedRepeatedI64TryIter {
+    /// byte offset of the next value to be parsed, relative to the start of the packed data
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
 
-        // parse the value or return None
-        let (value, remainder) = I64::from(data).ok()?;
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> WireData {
+        self.data
+    }
+}
 
-        // restore self.0 to whatever data remains after the value was parsed
-        self.0 .0 = remainder;
+impl Iterator for PackedRepeatedI64TryIter {
+    type Item = Result<I64, DecodeError>;
 
-        Some(value)
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
+
+        // data is always Const here so this clone is only a reference count
+        match I64::from(self.data.clone()) {
+            Ok((value, remainder)) => {
+                self.offset += self.data.len() - remainder.len();
+                self.data = remainder;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.shift(self.offset)))
+            }
+        }
     }
 }
 
 pub struct PackedRepeatedI32(pub(crate) WireData);
 
-impl std::default::Default for PackedRepeatedI32 {
+impl core::default::Default for PackedRepeatedI32 {
     fn default() -> Self {
         Self::new()
     }
@@ -128,19 +223,31 @@ impl PackedRepeatedI32 {
         PackedRepeatedI32(WireData::Mut(bytes::BytesMut::with_capacity(capacity)))
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn push(&mut self, value: I32) {
         self.0.get_mut().extend_from_slice(value.0.as_ref());
     }
+
+    pub fn into_try_iter(self) -> PackedRepeatedI32TryIter {
+        PackedRepeatedI32TryIter {
+            data: self.0.into_const(),
+            offset: 0,
+            failed: false,
+        }
+    }
 }
 
-pub struct PackedRepeatedI32Iter(PackedRepeatedI32);
+pub struct PackedRepeatedI32Iter(PackedRepeatedI32TryIter);
 
 impl IntoIterator for PackedRepeatedI32 {
     type IntoIter = PackedRepeatedI32Iter;
     type Item = <Self::IntoIter as Iterator>::Item;
 
     fn into_iter(self) -> Self::IntoIter {
-        PackedRepeatedI32Iter(self)
+        PackedRepeatedI32Iter(self.into_try_iter())
     }
 }
 
@@ -148,19 +255,49 @@ impl Iterator for PackedRepeatedI32Iter {
     type Item = I32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.0 .0.is_empty() {
-            return None;
-        }
+        // parse the value or return None, the error is only visible through the TryIter
+        self.0.next()?.ok()
+    }
+}
+
+/// Iterator over packed `I32` values which reports decode errors, stopping after the first
+pub struct PackedRepeatedI32TryIter {
+    data: WireData,
+    offset: usize,
+    failed: bool,
+}
+
+impl PackedRepeatedI32TryIter {
+    /// byte offset of the next value to be parsed, relative to the start of the packed data
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
 
-        // replace self.0 with empty data to pass ownership of data to the next value
-        let data = std::mem::replace(&mut self.0 .0, WireData::new(vec![]));
+    /// the data which has not been parsed yet
+    pub fn into_remainder(self) -> WireData {
+        self.data
+    }
+}
 
-        // parse the value or return None
-        let (value, remainder) = I32::from(data).ok()?;
+impl Iterator for PackedRepeatedI32TryIter {
+    type Item = Result<I32, DecodeError>;
 
-        // restore self.0 to whatever data remains after the value was parsed
-        self.0 .0 = remainder;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
 
-        Some(value)
+        // data is always Const here so this clone is only a reference count
+        match I32::from(self.data.clone()) {
+            Ok((value, remainder)) => {
+                self.offset += self.data.len() - remainder.len();
+                self.data = remainder;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.shift(self.offset)))
+            }
+        }
     }
 }
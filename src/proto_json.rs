@@ -0,0 +1,904 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::base64;
+use crate::descriptor::{FieldDescriptor, Kind, MessageDescriptor};
+use crate::dynamic_message::{DynamicError, DynamicMessage, MapKey, Value};
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::json::Json;
+use crate::message::Message;
+use crate::wire_data::WireData;
+
+/// Options for parsing proto3 JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// skip object members which don't name a field of the message instead of failing with
+    /// `JsonError::UnknownField`, and enum values named after no value of their enum
+    pub ignore_unknown_fields: bool,
+}
+
+impl core::default::Default for JsonOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonOptions {
+    pub const fn new() -> Self {
+        Self {
+            ignore_unknown_fields: false,
+        }
+    }
+}
+
+/// An error raised while converting between a message and proto3 JSON
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonError {
+    /// the text is not valid JSON, the first problem being at this byte offset
+    Syntax(usize),
+    /// the fields of the message could not be read or written
+    Dynamic(DynamicError),
+    /// an object has a member which is not a field of the message type
+    UnknownField(String),
+    /// a field appears more than once, or more than one field of a oneof is set
+    DuplicateField(String),
+    /// the value of a field doesn't fit its type, or has no JSON form, such as a
+    /// `google.protobuf.Timestamp` outside the years 1 to 9999
+    InvalidValue(String),
+    /// a `google.protobuf.Any` holds a message type the pool doesn't have
+    UnknownType(String),
+}
+
+impl From<DynamicError> for JsonError {
+    fn from(e: DynamicError) -> Self {
+        Self::Dynamic(e)
+    }
+}
+
+impl core::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Syntax(offset) => write!(f, "invalid JSON at byte {offset}"),
+            Self::Dynamic(e) => write!(f, "{e}"),
+            Self::UnknownField(name) => write!(f, "no field named {name}"),
+            Self::DuplicateField(name) => write!(f, "field {name} is set more than once"),
+            Self::InvalidValue(name) => write!(f, "invalid value for {name}"),
+            Self::UnknownType(url) => write!(f, "no message type for {url}"),
+        }
+    }
+}
+
+impl core::error::Error for JsonError {}
+
+/// How deeply messages may be nested while printing, bounding recursion on wire data
+const MAX_DEPTH: usize = 100;
+
+impl<'a> DynamicMessage<'a> {
+    /// the message as canonical proto3 JSON
+    ///
+    /// Fields are written under their JSON name in declaration order, fields without
+    /// presence only when they differ from their default, 64-bit integers as strings and
+    /// bytes as base64. Unknown fields have no JSON form and are dropped.
+    pub fn to_json(&self) -> Result<String, JsonError> {
+        let mut out = String::new();
+        message_json(self, 0)?.write(&mut out);
+        Ok(out)
+    }
+
+    pub fn from_json(descriptor: MessageDescriptor<'a>, json: &str) -> Result<Self, JsonError> {
+        Self::from_json_with_options(descriptor, json, &JsonOptions::default())
+    }
+
+    /// parses proto3 JSON into a message of type `descriptor`
+    ///
+    /// Fields may be named by their JSON name or their proto name. Fields set to `null` are
+    /// left absent, as are fields without presence set to their default.
+    pub fn from_json_with_options(
+        descriptor: MessageDescriptor<'a>,
+        json: &str,
+        options: &JsonOptions,
+    ) -> Result<Self, JsonError> {
+        let json = Json::parse(json).map_err(JsonError::Syntax)?;
+        parse_message(descriptor, &json, options)
+    }
+}
+
+impl Message {
+    /// the message as canonical proto3 JSON, read as type `descriptor`
+    pub fn to_json(&self, descriptor: MessageDescriptor<'_>) -> Result<String, JsonError> {
+        let message = DynamicMessage::from(descriptor, Message(self.0.clone()));
+        message.to_json()
+    }
+
+    pub fn from_json(descriptor: MessageDescriptor<'_>, json: &str) -> Result<Self, JsonError> {
+        Self::from_json_with_options(descriptor, json, &JsonOptions::default())
+    }
+
+    pub fn from_json_with_options(
+        descriptor: MessageDescriptor<'_>,
+        json: &str,
+        options: &JsonOptions,
+    ) -> Result<Self, JsonError> {
+        DynamicMessage::from_json_with_options(descriptor, json, options)
+            .map(DynamicMessage::into_message)
+    }
+}
+
+fn invalid(name: &str) -> JsonError {
+    JsonError::InvalidValue(name.to_string())
+}
+
+/// a field of a well-known type, which the descriptor is expected to declare
+fn wkt_field<'a>(
+    descriptor: MessageDescriptor<'a>,
+    name: &str,
+) -> Result<FieldDescriptor<'a>, JsonError> {
+    descriptor
+        .get_field_by_name(name)
+        .ok_or_else(|| invalid(descriptor.full_name()))
+}
+
+/// whether the well-known type has a JSON form other than an object of its fields, which an
+/// `Any` holds under a `value` member
+fn is_special(full_name: &str) -> bool {
+    matches!(
+        full_name,
+        "google.protobuf.Any"
+            | "google.protobuf.Timestamp"
+            | "google.protobuf.Duration"
+            | "google.protobuf.FieldMask"
+            | "google.protobuf.Struct"
+            | "google.protobuf.Value"
+            | "google.protobuf.ListValue"
+    ) || is_wrapper(full_name)
+}
+
+fn is_wrapper(full_name: &str) -> bool {
+    matches!(
+        full_name,
+        "google.protobuf.DoubleValue"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.BoolValue"
+            | "google.protobuf.StringValue"
+            | "google.protobuf.BytesValue"
+    )
+}
+
+fn message_json(message: &DynamicMessage<'_>, depth: usize) -> Result<Json, JsonError> {
+    if depth > MAX_DEPTH {
+        let e = DecodeError::new(DecodeErrorKind::DepthLimitExceeded { limit: MAX_DEPTH }, 0);
+        return Err(JsonError::Dynamic(DynamicError::Decode(e)));
+    }
+    let descriptor = message.descriptor();
+    if is_special(descriptor.full_name()) {
+        return well_known_json(message, depth);
+    }
+
+    let mut members = Vec::new();
+    for field in descriptor.fields() {
+        if let Some(value) = message.get_present_field(field)? {
+            let json = field_json(field, value, depth)?;
+            members.push((field.json_name().to_string(), json));
+        }
+    }
+    Ok(Json::Object(members))
+}
+
+fn field_json(
+    field: FieldDescriptor<'_>,
+    value: Value<'_>,
+    depth: usize,
+) -> Result<Json, JsonError> {
+    match value {
+        Value::List(values) => values
+            .into_iter()
+            .map(|value| value_json(field.name(), field.kind(), value, depth))
+            .collect::<Result<_, _>>()
+            .map(Json::Array),
+        Value::Map(map) => {
+            let Kind::Message(entry) = field.kind() else {
+                return Err(invalid(field.name()));
+            };
+            let value_kind = wkt_field(entry, "value")?.kind();
+            let mut members = Vec::with_capacity(map.len());
+            for (key, value) in map {
+                let key = match key {
+                    MapKey::Bool(key) => key.to_string(),
+                    MapKey::I32(key) => key.to_string(),
+                    MapKey::I64(key) => key.to_string(),
+                    MapKey::U32(key) => key.to_string(),
+                    MapKey::U64(key) => key.to_string(),
+                    MapKey::String(key) => key,
+                };
+                members.push((key, value_json(field.name(), value_kind, value, depth)?));
+            }
+            Ok(Json::Object(members))
+        }
+        value => value_json(field.name(), field.kind(), value, depth),
+    }
+}
+
+/// a single value of field `name`
+fn value_json(
+    name: &str,
+    kind: Kind<'_>,
+    value: Value<'_>,
+    depth: usize,
+) -> Result<Json, JsonError> {
+    Ok(match value {
+        Value::Bool(value) => Json::Bool(value),
+        Value::I32(value) => Json::Number(value.to_string()),
+        Value::U32(value) => Json::Number(value.to_string()),
+        Value::I64(value) => Json::String(value.to_string()),
+        Value::U64(value) => Json::String(value.to_string()),
+        Value::F32(value) => float_json(value.is_nan(), value.is_infinite(), value < 0.0, || {
+            format!("{value:?}")
+        }),
+        Value::F64(value) => float_json(value.is_nan(), value.is_infinite(), value < 0.0, || {
+            format!("{value:?}")
+        }),
+        Value::String(value) => Json::String(value),
+        Value::Bytes(value) => Json::String(base64::encode(&value)),
+        Value::Enum(number) => match kind {
+            Kind::Enum(e) if e.full_name() == "google.protobuf.NullValue" => Json::Null,
+            Kind::Enum(e) => match e.get_value(number) {
+                Some(value) => Json::String(value.name().to_string()),
+                None => Json::Number(number.to_string()),
+            },
+            _ => return Err(invalid(name)),
+        },
+        Value::Message(message) => message_json(&message, depth + 1)?,
+        Value::List(_) | Value::Map(_) => return Err(invalid(name)),
+    })
+}
+
+/// a float, which JSON has no literal for when it isn't finite
+fn float_json(nan: bool, infinite: bool, negative: bool, text: impl Fn() -> String) -> Json {
+    match (nan, infinite, negative) {
+        (true, _, _) => Json::String("NaN".into()),
+        (_, true, false) => Json::String("Infinity".into()),
+        (_, true, true) => Json::String("-Infinity".into()),
+        _ => {
+            // the shortest text that reads back as the same value, without a trailing `.0`
+            let text = text();
+            Json::Number(match text.strip_suffix(".0") {
+                Some(text) => text.to_string(),
+                None => text,
+            })
+        }
+    }
+}
+
+fn well_known_json(message: &DynamicMessage<'_>, depth: usize) -> Result<Json, JsonError> {
+    let descriptor = message.descriptor();
+    let full_name = descriptor.full_name();
+    let get = |name: &str| Ok::<_, JsonError>(message.get_field(wkt_field(descriptor, name)?)?);
+
+    match full_name {
+        "google.protobuf.Timestamp" => {
+            let (Value::I64(seconds), Value::I32(nanos)) = (get("seconds")?, get("nanos")?) else {
+                return Err(invalid(full_name));
+            };
+            format_timestamp(seconds, nanos)
+                .map(Json::String)
+                .ok_or_else(|| invalid(full_name))
+        }
+        "google.protobuf.Duration" => {
+            let (Value::I64(seconds), Value::I32(nanos)) = (get("seconds")?, get("nanos")?) else {
+                return Err(invalid(full_name));
+            };
+            format_duration(seconds, nanos)
+                .map(Json::String)
+                .ok_or_else(|| invalid(full_name))
+        }
+        "google.protobuf.FieldMask" => {
+            let Value::List(paths) = get("paths")? else {
+                return Err(invalid(full_name));
+            };
+            let mut mask = Vec::with_capacity(paths.len());
+            for path in paths {
+                let Value::String(path) = path else {
+                    return Err(invalid(full_name));
+                };
+                // paths which wouldn't read back the same have no JSON form
+                let camel = camel_case(&path);
+                if snake_case(&camel).as_deref() != Some(path.as_str()) {
+                    return Err(invalid(full_name));
+                }
+                mask.push(camel);
+            }
+            Ok(Json::String(mask.join(",")))
+        }
+        "google.protobuf.Struct" => {
+            let field = wkt_field(descriptor, "fields")?;
+            field_json(field, message.get_field(field)?, depth)
+        }
+        "google.protobuf.ListValue" => {
+            let field = wkt_field(descriptor, "values")?;
+            field_json(field, message.get_field(field)?, depth)
+        }
+        "google.protobuf.Value" => {
+            for field in descriptor.fields() {
+                if !message.has(field.name())? {
+                    continue;
+                }
+                return match message.get_field(field)? {
+                    Value::F64(value) if !value.is_finite() => Err(invalid(full_name)),
+                    value => field_json(field, value, depth),
+                };
+            }
+            // a Value must hold one of its kinds
+            Err(invalid(full_name))
+        }
+        "google.protobuf.Any" => {
+            let (Value::String(type_url), Value::Bytes(value)) = (get("type_url")?, get("value")?)
+            else {
+                return Err(invalid(full_name));
+            };
+            if type_url.is_empty() && value.is_empty() {
+                return Ok(Json::Object(Vec::new()));
+            }
+            let inner = any_type(descriptor, &type_url)?;
+            let inner =
+                DynamicMessage::from(inner, Message(WireData::new(bytes::Bytes::from(value))));
+            let type_member = (String::from("@type"), Json::String(type_url));
+            match (
+                is_special(inner.descriptor().full_name()),
+                message_json(&inner, depth + 1)?,
+            ) {
+                (false, Json::Object(mut members)) => {
+                    members.insert(0, type_member);
+                    Ok(Json::Object(members))
+                }
+                (_, json) => Ok(Json::Object(Vec::from([
+                    type_member,
+                    (String::from("value"), json),
+                ]))),
+            }
+        }
+        // the wrappers
+        _ => {
+            let field = wkt_field(descriptor, "value")?;
+            value_json(full_name, field.kind(), message.get_field(field)?, depth)
+        }
+    }
+}
+
+/// the message type an `Any` type URL names, found in the pool `any` belongs to
+fn any_type<'a>(
+    any: MessageDescriptor<'a>,
+    type_url: &str,
+) -> Result<MessageDescriptor<'a>, JsonError> {
+    let name = type_url.rsplit_once('/').map_or("", |(_, name)| name);
+    any.parent_pool()
+        .get_message_by_name(name)
+        .ok_or_else(|| JsonError::UnknownType(type_url.to_string()))
+}
+
+fn parse_message<'a>(
+    descriptor: MessageDescriptor<'a>,
+    json: &Json,
+    options: &JsonOptions,
+) -> Result<DynamicMessage<'a>, JsonError> {
+    if is_special(descriptor.full_name()) {
+        return parse_well_known(descriptor, json, options);
+    }
+    let Json::Object(members) = json else {
+        return Err(invalid(descriptor.full_name()));
+    };
+
+    let mut message = DynamicMessage::new(descriptor);
+    let mut seen = Vec::new();
+    let mut oneofs = Vec::new();
+    for (name, json) in members {
+        let field = descriptor
+            .get_field_by_json_name(name)
+            .or_else(|| descriptor.get_field_by_name(name));
+        let Some(field) = field else {
+            match options.ignore_unknown_fields {
+                true => continue,
+                false => return Err(JsonError::UnknownField(name.clone())),
+            }
+        };
+        if seen.contains(&field.number()) {
+            return Err(JsonError::DuplicateField(name.clone()));
+        }
+        seen.push(field.number());
+
+        if *json == Json::Null && !accepts_null(field.kind()) {
+            continue;
+        }
+        if let Some(oneof) = field.containing_oneof() {
+            if oneofs.contains(&oneof) {
+                return Err(JsonError::DuplicateField(oneof.name().to_string()));
+            }
+            oneofs.push(oneof);
+        }
+        let Some(value) = parse_field(field, json, options)? else {
+            continue;
+        };
+        message.set_present_field(field, value)?;
+    }
+    Ok(message)
+}
+
+/// whether `null` is a value of the type rather than marking the field absent
+fn accepts_null(kind: Kind<'_>) -> bool {
+    match kind {
+        Kind::Message(descriptor) => descriptor.full_name() == "google.protobuf.Value",
+        Kind::Enum(e) => e.full_name() == "google.protobuf.NullValue",
+        _ => false,
+    }
+}
+
+/// the value of `field`, or `None` if it is skipped, see `parse_value`
+fn parse_field<'a>(
+    field: FieldDescriptor<'a>,
+    json: &Json,
+    options: &JsonOptions,
+) -> Result<Option<Value<'a>>, JsonError> {
+    let name = field.name();
+    if field.is_map() {
+        let (Json::Object(members), Kind::Message(entry)) = (json, field.kind()) else {
+            return Err(invalid(name));
+        };
+        let key_kind = wkt_field(entry, "key")?.kind();
+        let value_kind = wkt_field(entry, "value")?.kind();
+        let mut map = BTreeMap::new();
+        for (key, json) in members {
+            let key = parse_key(name, key_kind, key)?;
+            let Some(value) = parse_value(name, value_kind, json, options)? else {
+                continue;
+            };
+            if map.insert(key, value).is_some() {
+                return Err(JsonError::DuplicateField(name.to_string()));
+            }
+        }
+        return Ok(Some(Value::Map(map)));
+    }
+    if field.is_list() {
+        let Json::Array(values) = json else {
+            return Err(invalid(name));
+        };
+        let mut list = Vec::new();
+        for json in values {
+            list.extend(parse_value(name, field.kind(), json, options)?);
+        }
+        return Ok(Some(Value::List(list)));
+    }
+    parse_value(name, field.kind(), json, options)
+}
+
+/// a map key, which JSON always writes as a string
+fn parse_key(name: &str, kind: Kind<'_>, key: &str) -> Result<MapKey, JsonError> {
+    let bad = || invalid(name);
+    Ok(match kind {
+        Kind::Bool => match key {
+            "true" => MapKey::Bool(true),
+            "false" => MapKey::Bool(false),
+            _ => return Err(bad()),
+        },
+        Kind::String => MapKey::String(key.to_string()),
+        _ => {
+            let number = integer(&Json::String(key.to_string())).ok_or_else(bad)?;
+            match parse_integer(name, kind, number)? {
+                Value::I32(value) => MapKey::I32(value),
+                Value::I64(value) => MapKey::I64(value),
+                Value::U32(value) => MapKey::U32(value),
+                Value::U64(value) => MapKey::U64(value),
+                _ => return Err(bad()),
+            }
+        }
+    })
+}
+
+/// a single value of field `name`, or `None` for an enum value name the enum doesn't have,
+/// which `ignore_unknown_fields` skips like an unknown field
+fn parse_value<'a>(
+    name: &str,
+    kind: Kind<'a>,
+    json: &Json,
+    options: &JsonOptions,
+) -> Result<Option<Value<'a>>, JsonError> {
+    let bad = || invalid(name);
+    Ok(Some(match kind {
+        Kind::Bool => match json {
+            Json::Bool(value) => Value::Bool(*value),
+            _ => return Err(bad()),
+        },
+        Kind::Double => Value::F64(float(json).ok_or_else(bad)?),
+        Kind::Float => {
+            let value = float(json).ok_or_else(bad)?;
+            if value.is_finite() && (value as f32).is_infinite() {
+                return Err(bad());
+            }
+            Value::F32(value as f32)
+        }
+        Kind::String => match json {
+            Json::String(value) => Value::String(value.clone()),
+            _ => return Err(bad()),
+        },
+        Kind::Bytes => match json {
+            Json::String(value) => Value::Bytes(base64::decode(value).ok_or_else(bad)?),
+            _ => return Err(bad()),
+        },
+        Kind::Enum(e) => match json {
+            Json::Null if e.full_name() == "google.protobuf.NullValue" => Value::Enum(0),
+            Json::String(value) => match e.get_value_by_name(value) {
+                Some(value) => Value::Enum(value.number()),
+                None if options.ignore_unknown_fields => return Ok(None),
+                None => return Err(bad()),
+            },
+            Json::Number(_) => {
+                let number = integer(json).ok_or_else(bad)?;
+                Value::Enum(i32::try_from(number).map_err(|_| bad())?)
+            }
+            _ => return Err(bad()),
+        },
+        Kind::Message(descriptor) | Kind::Group(descriptor) => {
+            Value::Message(parse_message(descriptor, json, options)?)
+        }
+        _ => parse_integer(name, kind, integer(json).ok_or_else(bad)?)?,
+    }))
+}
+
+fn parse_integer<'a>(name: &str, kind: Kind<'_>, number: i128) -> Result<Value<'a>, JsonError> {
+    let bad = |_| invalid(name);
+    Ok(match kind {
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(number.try_into().map_err(bad)?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(number.try_into().map_err(bad)?),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(number.try_into().map_err(bad)?),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(number.try_into().map_err(bad)?),
+        _ => return Err(invalid(name)),
+    })
+}
+
+/// the text of a JSON number, which may also be written as a string
+fn number_text(json: &Json) -> Option<&str> {
+    match json {
+        Json::Number(text) => Some(text),
+        Json::String(text) if !text.starts_with(|c: char| c.is_ascii_whitespace()) => {
+            match Json::parse(text) {
+                Ok(Json::Number(_)) if !text.ends_with(|c: char| c.is_ascii_whitespace()) => {
+                    Some(text)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// an integer, which may be written with a fraction or exponent as long as it is whole
+fn integer(json: &Json) -> Option<i128> {
+    let text = number_text(json)?;
+    if let Ok(value) = text.parse() {
+        return Some(value);
+    }
+    let value: f64 = text.parse().ok()?;
+    // whole numbers survive the round trip through i128, which saturates on anything larger
+    let whole = value as i128;
+    (whole as f64 == value && value.abs() < 1e20).then_some(whole)
+}
+
+fn float(json: &Json) -> Option<f64> {
+    match json {
+        Json::String(text) if text == "NaN" => Some(f64::NAN),
+        Json::String(text) if text == "Infinity" => Some(f64::INFINITY),
+        Json::String(text) if text == "-Infinity" => Some(f64::NEG_INFINITY),
+        _ => {
+            let value: f64 = number_text(json)?.parse().ok()?;
+            // a literal too large for a double is out of range rather than infinite
+            value.is_finite().then_some(value)
+        }
+    }
+}
+
+fn parse_well_known<'a>(
+    descriptor: MessageDescriptor<'a>,
+    json: &Json,
+    options: &JsonOptions,
+) -> Result<DynamicMessage<'a>, JsonError> {
+    let full_name = descriptor.full_name();
+    let bad = || invalid(full_name);
+    let mut message = DynamicMessage::new(descriptor);
+
+    match (full_name, json) {
+        ("google.protobuf.Timestamp", Json::String(text)) => {
+            let (seconds, nanos) = parse_timestamp(text).ok_or_else(bad)?;
+            message.set_present_field(wkt_field(descriptor, "seconds")?, Value::I64(seconds))?;
+            message.set_present_field(wkt_field(descriptor, "nanos")?, Value::I32(nanos))?;
+        }
+        ("google.protobuf.Duration", Json::String(text)) => {
+            let (seconds, nanos) = parse_duration(text).ok_or_else(bad)?;
+            message.set_present_field(wkt_field(descriptor, "seconds")?, Value::I64(seconds))?;
+            message.set_present_field(wkt_field(descriptor, "nanos")?, Value::I32(nanos))?;
+        }
+        ("google.protobuf.FieldMask", Json::String(text)) => {
+            let mut paths = Vec::new();
+            for path in text.split(',').filter(|path| !path.is_empty()) {
+                paths.push(Value::String(snake_case(path).ok_or_else(bad)?));
+            }
+            message.set_present_field(wkt_field(descriptor, "paths")?, Value::List(paths))?;
+        }
+        ("google.protobuf.Struct", Json::Object(_)) => {
+            let field = wkt_field(descriptor, "fields")?;
+            if let Some(value) = parse_field(field, json, options)? {
+                message.set_present_field(field, value)?;
+            }
+        }
+        ("google.protobuf.ListValue", Json::Array(_)) => {
+            let field = wkt_field(descriptor, "values")?;
+            if let Some(value) = parse_field(field, json, options)? {
+                message.set_present_field(field, value)?;
+            }
+        }
+        ("google.protobuf.Value", json) => {
+            let name = match json {
+                Json::Null => "null_value",
+                Json::Number(_) => "number_value",
+                Json::String(_) => "string_value",
+                Json::Bool(_) => "bool_value",
+                Json::Object(_) => "struct_value",
+                Json::Array(_) => "list_value",
+            };
+            let field = wkt_field(descriptor, name)?;
+            if let Some(value) = parse_value(full_name, field.kind(), json, options)? {
+                message.set_field(field, value)?;
+            }
+        }
+        ("google.protobuf.Any", Json::Object(members)) => {
+            let type_url = members.iter().find(|(name, _)| name == "@type");
+            let Some((_, Json::String(type_url))) = type_url else {
+                // only an empty Any can leave out its type
+                return match members.is_empty() {
+                    true => Ok(message),
+                    false => Err(bad()),
+                };
+            };
+            let inner = any_type(descriptor, type_url)?;
+            let rest: Vec<_> = members
+                .iter()
+                .filter(|(name, _)| name != "@type")
+                .cloned()
+                .collect();
+            let inner = match is_special(inner.full_name()) {
+                true => match rest.as_slice() {
+                    [(name, json)] if name == "value" => parse_message(inner, json, options)?,
+                    _ => return Err(bad()),
+                },
+                false => parse_message(inner, &Json::Object(rest), options)?,
+            };
+            let value = inner.into_message().0.as_ref().to_vec();
+            let type_url = Value::String(type_url.clone());
+            message.set_present_field(wkt_field(descriptor, "type_url")?, type_url)?;
+            message.set_present_field(wkt_field(descriptor, "value")?, Value::Bytes(value))?;
+        }
+        (full_name, json) if is_wrapper(full_name) => {
+            let field = wkt_field(descriptor, "value")?;
+            if let Some(value) = parse_value(full_name, field.kind(), json, options)? {
+                message.set_present_field(field, value)?;
+            }
+        }
+        _ => return Err(bad()),
+    }
+    Ok(message)
+}
+
+/// `foo_bar.baz` as `fooBar.baz`
+fn camel_case(path: &str) -> String {
+    let mut camel = String::with_capacity(path.len());
+    let mut upper = false;
+    for c in path.chars() {
+        match (c, upper) {
+            ('_', _) => upper = true,
+            (c, true) => {
+                camel.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            (c, false) => camel.push(c),
+        }
+    }
+    camel
+}
+
+/// `fooBar.baz` as `foo_bar.baz`, or `None` for a path holding an underscore, which
+/// camel case can't contain
+fn snake_case(path: &str) -> Option<String> {
+    let mut snake = String::with_capacity(path.len() + 4);
+    for c in path.chars() {
+        match c {
+            '_' => return None,
+            c if c.is_ascii_uppercase() => {
+                snake.push('_');
+                snake.push(c.to_ascii_lowercase());
+            }
+            c => snake.push(c),
+        }
+    }
+    Some(snake)
+}
+
+/// the earliest and latest seconds a Timestamp may hold, 0001-01-01T00:00:00Z and
+/// 9999-12-31T23:59:59Z
+const TIMESTAMP_SECONDS: core::ops::RangeInclusive<i64> = -62_135_596_800..=253_402_300_799;
+
+/// the most seconds a Duration may hold, about 10000 years
+const DURATION_SECONDS: i64 = 315_576_000_000;
+
+/// the days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// the date of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// nanoseconds as the shortest of 0, 3, 6 or 9 fractional digits
+fn fraction(nanos: u32) -> String {
+    match nanos {
+        0 => String::new(),
+        nanos if nanos % 1_000_000 == 0 => format!(".{:03}", nanos / 1_000_000),
+        nanos if nanos % 1_000 == 0 => format!(".{:06}", nanos / 1_000),
+        nanos => format!(".{nanos:09}"),
+    }
+}
+
+/// parses the digits after a decimal point as nanoseconds
+fn parse_fraction(digits: &str) -> Option<i32> {
+    if digits.is_empty() || digits.len() > 9 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value: i32 = digits.parse().ok()?;
+    Some(value * 10i32.pow(9 - digits.len() as u32))
+}
+
+fn format_timestamp(seconds: i64, nanos: i32) -> Option<String> {
+    if !TIMESTAMP_SECONDS.contains(&seconds) || !(0..1_000_000_000).contains(&nanos) {
+        return None;
+    }
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    Some(format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        fraction(nanos as u32)
+    ))
+}
+
+/// parses an RFC 3339 timestamp, e.g. `1972-01-01T10:00:20.021-05:00`
+fn parse_timestamp(text: &str) -> Option<(i64, i32)> {
+    let bytes = text.as_bytes();
+    let number = |range: core::ops::Range<usize>| -> Option<i64> {
+        let digits = bytes.get(range)?;
+        match digits.iter().all(u8::is_ascii_digit) {
+            true => core::str::from_utf8(digits).ok()?.parse().ok(),
+            false => None,
+        }
+    };
+    let separators = [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')];
+    if separators.iter().any(|(i, c)| bytes.get(*i) != Some(c)) {
+        return None;
+    }
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+
+    let rest = &text[19..];
+    let (nanos, zone) = match rest.strip_prefix('.') {
+        Some(rest) => {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            (parse_fraction(&rest[..end])?, &rest[end..])
+        }
+        None => (0, rest),
+    };
+    let offset = match zone.as_bytes() {
+        b"Z" => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let digits = [*h1, *h2, *m1, *m2];
+            if !digits.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+            let hours = ((h1 - b'0') * 10 + (h2 - b'0')) as i64;
+            let minutes = ((m1 - b'0') * 10 + (m2 - b'0')) as i64;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if *sign == b'-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => return None,
+    };
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+    let seconds =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    TIMESTAMP_SECONDS
+        .contains(&seconds)
+        .then_some((seconds, nanos))
+}
+
+fn format_duration(seconds: i64, nanos: i32) -> Option<String> {
+    let same_sign = (seconds >= 0 && nanos >= 0) || (seconds <= 0 && nanos <= 0);
+    if seconds.abs() > DURATION_SECONDS || nanos.abs() >= 1_000_000_000 || !same_sign {
+        return None;
+    }
+    let sign = if seconds < 0 || nanos < 0 { "-" } else { "" };
+    Some(format!(
+        "{sign}{}{}s",
+        seconds.unsigned_abs(),
+        fraction(nanos.unsigned_abs())
+    ))
+}
+
+/// parses a duration such as `-1.5s`
+fn parse_duration(text: &str) -> Option<(i64, i32)> {
+    let text = text.strip_suffix('s')?;
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let (whole, nanos) = match text.split_once('.') {
+        Some((whole, digits)) => (whole, parse_fraction(digits)?),
+        None => (text, 0),
+    };
+    if whole.is_empty() || !whole.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let seconds: i64 = whole.parse().ok()?;
+    if seconds > DURATION_SECONDS {
+        return None;
+    }
+    match negative {
+        true => Some((-seconds, -nanos)),
+        false => Some((seconds, nanos)),
+    }
+}
@@ -0,0 +1,73 @@
+//! Traits mapping Rust types to protobuf messages, enums and oneofs, as implemented by
+//! `#[derive(ProtoWire)]` with the `derive` feature
+
+use crate::borrowed::{FieldRef, MessageRef};
+use crate::error::DecodeError;
+use crate::message::Message;
+
+/// A Rust type stored as a protobuf message
+///
+/// Decoding follows protobuf's rules: a singular field takes its last occurrence, except
+/// for messages whose occurrences are merged, like `Message::get_message` does. Repeated
+/// scalars accept packed and unpacked occurrences, and occurrences a field can't hold,
+/// like those with another wire type, are treated as unknown fields. Messages and groups
+/// nested deeper than `DecodeOptions::max_depth` fail with `DepthLimitExceeded`.
+pub trait ProtoWire: Sized {
+    /// appends the fields of `self` to `message`
+    fn encode(&self, message: &mut Message);
+
+    /// reads `Self` from the fields of `message`
+    fn decode(message: MessageRef<'_>) -> Result<Self, DecodeError> {
+        Self::decode_nested(message, 0)
+    }
+
+    /// reads the fields of `message` into `self`, as if they followed the fields `self`
+    /// was decoded from
+    fn merge(&mut self, message: MessageRef<'_>) -> Result<(), DecodeError> {
+        self.merge_nested(message, 0)
+    }
+
+    /// see `decode`, `depth` being how many messages enclose `message`
+    fn decode_nested(message: MessageRef<'_>, depth: usize) -> Result<Self, DecodeError>;
+
+    /// see `merge`, `depth` being how many messages enclose `message`
+    fn merge_nested(&mut self, message: MessageRef<'_>, depth: usize) -> Result<(), DecodeError>;
+
+    /// `self` encoded as a new message
+    fn to_message(&self) -> Message {
+        let mut message = Message::new();
+        self.encode(&mut message);
+        message
+    }
+
+    /// see `decode`
+    fn from_message(message: &Message) -> Result<Self, DecodeError> {
+        Self::decode(message.view())
+    }
+}
+
+/// A Rust enum stored as a protobuf enum
+pub trait ProtoEnum: Sized {
+    /// the number of the value
+    fn to_i32(&self) -> i32;
+
+    /// the value numbered `value`, `None` if there is none
+    fn from_i32(value: i32) -> Option<Self>;
+}
+
+/// A Rust enum stored as a protobuf oneof, each variant holding one field
+pub trait ProtoOneof: Sized {
+    /// appends the field held by the variant to `message`
+    fn encode(&self, message: &mut Message);
+
+    /// reads `field`, a field of `message`, into `value` if it is one of the fields of
+    /// the oneof, returning whether it is. A message merges into `value` if it already
+    /// holds the same variant, anything else replaces it. `depth` is how many messages
+    /// enclose `message`
+    fn merge(
+        value: &mut Option<Self>,
+        message: MessageRef<'_>,
+        field: &FieldRef<'_>,
+        depth: usize,
+    ) -> Result<bool, DecodeError>;
+}
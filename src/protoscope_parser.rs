@@ -0,0 +1,474 @@
+use alloc::vec::Vec;
+
+use bytes::BytesMut;
+
+use crate::message::Message;
+use crate::varint::Varint;
+use crate::wire_data::WireData;
+
+/// Why protoscope text could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtoscopeErrorKind {
+    /// a character which can't start any token
+    UnexpectedChar(char),
+    /// a word which isn't a tag, number or keyword
+    InvalidToken,
+    /// a number that doesn't fit the width it is encoded with
+    NumberOutOfRange,
+    /// a tag naming a wire type protoscope doesn't know
+    UnknownWireType,
+    /// a string or hex literal without its closing quote
+    UnterminatedLiteral,
+    /// a backslash escape in a string which isn't supported
+    InvalidEscape,
+    /// a hex literal with a non-hex character or an odd number of digits
+    InvalidHex,
+    /// a `{` without a matching `}`
+    UnclosedBrace,
+    /// a `}` without a matching `{`
+    UnmatchedBrace,
+    /// a `!{` group which doesn't directly follow a tag without an explicit wire type
+    GroupWithoutTag,
+    /// blocks and groups nested deeper than 100
+    DepthLimitExceeded,
+}
+
+/// An error raised while parsing protoscope text, with the position it was found at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtoscopeError {
+    pub kind: ProtoscopeErrorKind,
+    /// 1-based line of the error
+    pub line: usize,
+    /// 1-based column, in characters, of the error
+    pub column: usize,
+}
+
+impl core::fmt::Display for ProtoscopeErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedChar(c) => write!(f, "unexpected character {c:?}"),
+            Self::InvalidToken => write!(f, "invalid token"),
+            Self::NumberOutOfRange => write!(f, "number out of range"),
+            Self::UnknownWireType => write!(f, "unknown wire type"),
+            Self::UnterminatedLiteral => write!(f, "unterminated literal"),
+            Self::InvalidEscape => write!(f, "invalid escape sequence"),
+            Self::InvalidHex => write!(f, "invalid hex literal"),
+            Self::UnclosedBrace => write!(f, "'{{' has no matching '}}'"),
+            Self::UnmatchedBrace => write!(f, "'}}' has no matching '{{'"),
+            Self::GroupWithoutTag => write!(f, "'!{{' must directly follow a tag"),
+            Self::DepthLimitExceeded => write!(f, "blocks nested too deeply"),
+        }
+    }
+}
+
+impl core::fmt::Display for ProtoscopeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.kind, self.line, self.column
+        )
+    }
+}
+
+impl core::error::Error for ProtoscopeError {}
+
+impl Message {
+    /// encodes protoscope text, see `WireData::from_protoscope`
+    pub fn from_protoscope(text: &str) -> Result<Self, ProtoscopeError> {
+        WireData::from_protoscope(text).map(Message)
+    }
+}
+
+impl WireData {
+    /// encodes protoscope text, the language `Message`'s `Display` implementation prints
+    ///
+    /// Supported are tags with or without a wire type (`1:`, `1:VARINT`, `1:LEN`, ...),
+    /// integers with an optional `i32`, `i64` or `z` (zigzag) suffix, floats (an `I64`
+    /// double unless suffixed with `i32`), `true` and `false`, string literals, hex
+    /// literals in backticks, `{}` length-prefixed blocks, `!{}` groups, the `long-form:N`
+    /// prefix which pads the following varint with `N` extra bytes, and `#` comments.
+    /// Without an explicit wire type a tag takes its type from the value after it.
+    pub fn from_protoscope(text: &str) -> Result<Self, ProtoscopeError> {
+        let mut parser = Parser {
+            text,
+            position: 0,
+            padding: 0,
+            padding_at: 0,
+            depth: 0,
+        };
+        let mut dest = BytesMut::new();
+        parser.block(&mut dest, None)?;
+
+        Ok(WireData::Mut(dest))
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+    /// extra bytes requested by `long-form:` for the next varint
+    padding: usize,
+    /// where the `long-form:` prefix setting `padding` starts
+    padding_at: usize,
+    /// how many blocks are open
+    depth: usize,
+}
+
+/// How deeply blocks and groups may be nested, bounding recursion
+const MAX_DEPTH: usize = 100;
+
+/// The most bytes a varint can take, padding included
+const MAX_VARINT_LEN: usize = 10;
+
+/// characters which end a word token
+const DELIMITERS: &[char] = &['{', '}', '!', '"', '`', '#'];
+
+impl<'a> Parser<'a> {
+    /// parses tokens until the end of the text, or the `}` closing the block opened at
+    /// `open` (the position of its `{`)
+    fn block(&mut self, dest: &mut BytesMut, open: Option<usize>) -> Result<(), ProtoscopeError> {
+        if let Some(open) = open {
+            if self.depth == MAX_DEPTH {
+                return Err(self.error_at(ProtoscopeErrorKind::DepthLimitExceeded, open));
+            }
+            self.depth += 1;
+        }
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            // a `long-form:` prefix applies to the next token only, if that is a varint
+            let padding = core::mem::take(&mut self.padding);
+            let Some(c) = self.peek() else {
+                return match open {
+                    Some(open) => Err(self.error_at(ProtoscopeErrorKind::UnclosedBrace, open)),
+                    None => Ok(()),
+                };
+            };
+
+            match c {
+                '}' => {
+                    self.position += 1;
+                    return match open {
+                        Some(_) => {
+                            self.depth -= 1;
+                            Ok(())
+                        }
+                        None => Err(self.error_at(ProtoscopeErrorKind::UnmatchedBrace, start)),
+                    };
+                }
+                '{' => {
+                    self.position += 1;
+                    let mut inner = BytesMut::new();
+                    self.block(&mut inner, Some(start))?;
+                    self.padding = padding;
+                    self.varint(inner.len() as u64, dest)?;
+                    dest.extend_from_slice(&inner);
+                }
+                '!' => return Err(self.error_at(ProtoscopeErrorKind::GroupWithoutTag, start)),
+                '"' => self.string(dest)?,
+                '`' => self.hex(dest)?,
+                _ => {
+                    let word = self.word();
+                    if word.is_empty() {
+                        return Err(self.error_at(ProtoscopeErrorKind::UnexpectedChar(c), start));
+                    }
+                    self.padding = padding;
+                    self.word_token(word, start, dest)?;
+                }
+            }
+        }
+    }
+
+    /// a tag, `long-form:` prefix or number
+    fn word_token(
+        &mut self,
+        word: &str,
+        start: usize,
+        dest: &mut BytesMut,
+    ) -> Result<(), ProtoscopeError> {
+        if let Some(padding) = word.strip_prefix("long-form:") {
+            let padding: u64 = padding
+                .parse()
+                .map_err(|_| self.error_at(ProtoscopeErrorKind::InvalidToken, start))?;
+            // no varint is longer than 10 bytes, the checks in `varint` are exact
+            if padding >= MAX_VARINT_LEN as u64 {
+                return Err(self.error_at(ProtoscopeErrorKind::NumberOutOfRange, start));
+            }
+            self.padding = padding as usize;
+            self.padding_at = start;
+            return Ok(());
+        }
+
+        let Some((field_id, wire_type)) = word.split_once(':') else {
+            return self.number(word, start, dest);
+        };
+        let field_id: u64 = field_id
+            .parse()
+            .map_err(|_| self.error_at(ProtoscopeErrorKind::InvalidToken, start))?;
+        // the wire type takes the low 3 bits of the tag
+        if field_id > u64::MAX >> 3 {
+            return Err(self.error_at(ProtoscopeErrorKind::NumberOutOfRange, start));
+        }
+        let wire_type = match wire_type {
+            "" => return self.inferred_tag(field_id, dest),
+            "VARINT" => 0,
+            "I64" => 1,
+            "LEN" => 2,
+            "SGROUP" => 3,
+            "EGROUP" => 4,
+            "I32" => 5,
+            other => match other.parse::<u64>() {
+                Ok(wire_type) if wire_type < 8 => wire_type,
+                _ => return Err(self.error_at(ProtoscopeErrorKind::UnknownWireType, start)),
+            },
+        };
+        self.varint((field_id << 3) | wire_type, dest)?;
+        Ok(())
+    }
+
+    /// a tag without a wire type, which is taken from the token after it. A `!{` group is
+    /// encoded here, as its end-group tag needs the field id
+    fn inferred_tag(&mut self, field_id: u64, dest: &mut BytesMut) -> Result<(), ProtoscopeError> {
+        self.skip_whitespace();
+        let rest = &self.text[self.position..];
+        let wire_type = if rest.starts_with('{') {
+            2
+        } else if rest.starts_with("!{") {
+            let open = self.position + 1;
+            self.position += 2;
+            self.varint((field_id << 3) | 3, dest)?;
+            self.block(dest, Some(open))?;
+            self.varint((field_id << 3) | 4, dest)?;
+            return Ok(());
+        } else {
+            let mut word = rest.split(|c: char| c.is_whitespace() || DELIMITERS.contains(&c));
+            match word.next().unwrap_or_default() {
+                word if word.ends_with("i32") => 5,
+                word if word.ends_with("i64") || is_float(word) => 1,
+                _ => 0,
+            }
+        };
+        self.varint((field_id << 3) | wire_type, dest)?;
+        Ok(())
+    }
+
+    fn number(
+        &mut self,
+        word: &str,
+        start: usize,
+        dest: &mut BytesMut,
+    ) -> Result<(), ProtoscopeError> {
+        let invalid = |parser: &Self| parser.error_at(ProtoscopeErrorKind::InvalidToken, start);
+        let range = |parser: &Self| parser.error_at(ProtoscopeErrorKind::NumberOutOfRange, start);
+
+        if let Some(value) = match word {
+            "true" => Some(1),
+            "false" => Some(0),
+            _ => None,
+        } {
+            self.varint(value, dest)?;
+            return Ok(());
+        }
+
+        let (number, suffix) = if let Some(number) = word.strip_suffix("i32") {
+            (number, Some(32))
+        } else if let Some(number) = word.strip_suffix("i64") {
+            (number, Some(64))
+        } else if let Some(number) = word.strip_suffix('z') {
+            (number, Some(0))
+        } else {
+            (word, None)
+        };
+
+        if is_float(number) {
+            match suffix {
+                Some(32) => {
+                    let value: f32 = number.parse().map_err(|_| invalid(self))?;
+                    dest.extend_from_slice(&value.to_le_bytes());
+                }
+                None | Some(64) => {
+                    let value: f64 = number.parse().map_err(|_| invalid(self))?;
+                    dest.extend_from_slice(&value.to_le_bytes());
+                }
+                _ => return Err(invalid(self)),
+            }
+            return Ok(());
+        }
+
+        let value = parse_integer(number).ok_or_else(|| invalid(self))?;
+        match suffix {
+            Some(32) if (i32::MIN as i128..=u32::MAX as i128).contains(&value) => {
+                dest.extend_from_slice(&(value as u32).to_le_bytes())
+            }
+            Some(64) | None if (i64::MIN as i128..=u64::MAX as i128).contains(&value) => {
+                match suffix {
+                    Some(_) => dest.extend_from_slice(&(value as u64).to_le_bytes()),
+                    None => self.varint(value as u64, dest)?,
+                }
+            }
+            Some(0) if (i64::MIN as i128..=i64::MAX as i128).contains(&value) => {
+                let value = value as i64;
+                self.varint(((value << 1) ^ (value >> 63)) as u64, dest)?
+            }
+            _ => return Err(range(self)),
+        }
+        Ok(())
+    }
+
+    fn string(&mut self, dest: &mut BytesMut) -> Result<(), ProtoscopeError> {
+        let start = self.position;
+        self.position += 1;
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error_at(ProtoscopeErrorKind::UnterminatedLiteral, start));
+            };
+            let escape = self.position;
+            self.position += c.len_utf8();
+            match c {
+                '"' => return Ok(()),
+                '\\' => {
+                    let byte = self
+                        .escape()
+                        .ok_or_else(|| self.error_at(ProtoscopeErrorKind::InvalidEscape, escape))?;
+                    dest.extend_from_slice(&[byte]);
+                }
+                c => {
+                    let mut buf = [0; 4];
+                    dest.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+    }
+
+    /// the byte of the escape sequence following a backslash
+    fn escape(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        match c {
+            'n' => Some(b'\n'),
+            'r' => Some(b'\r'),
+            't' => Some(b'\t'),
+            '"' | '\'' | '\\' => Some(c as u8),
+            'x' => {
+                let digits = self.text.get(self.position..self.position + 2)?;
+                self.position += 2;
+                u8::from_str_radix(digits, 16).ok()
+            }
+            '0'..='7' => {
+                // up to three octal digits, the first of which was already consumed
+                let mut value = c.to_digit(8)?;
+                for _ in 0..2 {
+                    match self.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            self.position += 1;
+                        }
+                        None => break,
+                    }
+                }
+                u8::try_from(value).ok()
+            }
+            _ => None,
+        }
+    }
+
+    fn hex(&mut self, dest: &mut BytesMut) -> Result<(), ProtoscopeError> {
+        let start = self.position;
+        let rest = &self.text[start + 1..];
+        let Some(end) = rest.find('`') else {
+            return Err(self.error_at(ProtoscopeErrorKind::UnterminatedLiteral, start));
+        };
+        let digits = &rest[..end];
+        if !digits.len().is_multiple_of(2) {
+            return Err(self.error_at(ProtoscopeErrorKind::InvalidHex, start));
+        }
+        let bytes = (0..digits.len())
+            .step_by(2)
+            .map(|i| {
+                digits
+                    .get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| self.error_at(ProtoscopeErrorKind::InvalidHex, start))?;
+        dest.extend_from_slice(&bytes);
+        self.position += end + 2;
+        Ok(())
+    }
+
+    /// encodes `value`, padded by any pending `long-form:` prefix
+    fn varint(&mut self, value: u64, dest: &mut BytesMut) -> Result<(), ProtoscopeError> {
+        let len = Varint::encoded_len(value);
+        let mut bytes = Varint::encode(value).to_vec();
+        bytes.truncate(len);
+        let padding = core::mem::take(&mut self.padding);
+        if len + padding > MAX_VARINT_LEN {
+            return Err(self.error_at(ProtoscopeErrorKind::NumberOutOfRange, self.padding_at));
+        }
+        if padding > 0 {
+            bytes[len - 1] |= 0b1000_0000;
+            bytes.resize(len + padding - 1, 0b1000_0000);
+            bytes.push(0);
+        }
+        dest.extend_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn word(&mut self) -> &'a str {
+        let text = self.text;
+        let rest = &text[self.position..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || DELIMITERS.contains(&c))
+            .unwrap_or(rest.len());
+        self.position += len;
+        &rest[..len]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.text[self.position..];
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if !trimmed.starts_with('#') {
+                return;
+            }
+            self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn error_at(&self, kind: ProtoscopeErrorKind, position: usize) -> ProtoscopeError {
+        let before = &self.text[..position];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        ProtoscopeError {
+            kind,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// whether a number token (without its suffix) is written as a float
+fn is_float(number: &str) -> bool {
+    let digits = number.trim_start_matches(['-', '+']);
+    matches!(digits, "inf" | "nan")
+        || (digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+            && !digits.starts_with("0x")
+            && digits.contains(['.', 'e', 'E']))
+}
+
+fn parse_integer(number: &str) -> Option<i128> {
+    let (negative, digits) = match number.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, number.strip_prefix('+').unwrap_or(number)),
+    };
+    let value = match digits.strip_prefix("0x") {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i128>().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
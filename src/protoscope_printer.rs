@@ -0,0 +1,344 @@
+use core::fmt::{Formatter, Result, Write};
+
+use crate::borrowed::{FieldRef, MessageObjectRef, MessageRef, VarintRef};
+use crate::classify::{nice_float, RawField, RawMessage, RawValue};
+use crate::field::Field;
+use crate::message::Message;
+use crate::varint::Varint;
+
+/// How `Len` payloads which are neither a message nor text are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BytesFormat {
+    /// a protoscope hex literal, e.g. `` `00ff` ``
+    Hex,
+    /// a quoted string with every non-printable byte escaped, e.g. `"a\x00\xff"`
+    Escaped,
+}
+
+/// Options for printing wire data as protoscope text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintOptions {
+    /// how many `Len` payloads to expand nested inside each other. Payloads nested deeper
+    /// are printed as text or bytes, while groups are always expanded
+    pub max_depth: usize,
+    /// print every tag with its wire type, e.g. `1:VARINT 150`, and groups as a
+    /// `SGROUP`/`EGROUP` pair instead of `!{ ... }`
+    pub explicit_wire_types: bool,
+    pub bytes_format: BytesFormat,
+}
+
+impl core::default::Default for PrintOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrintOptions {
+    pub const fn new() -> Self {
+        Self {
+            max_depth: 100,
+            explicit_wire_types: false,
+            bytes_format: BytesFormat::Hex,
+        }
+    }
+}
+
+/// Wire data formatted as protoscope text by its `Display` implementation
+///
+/// `Len` payloads are printed as whatever `LenRef::classify` takes them for, and packed
+/// values as a space separated list, e.g. `{1 2 3}`. Data which fails to decode is printed
+/// up to the first field that can't be parsed, followed by the rest of the data as a hex
+/// literal, so printing never fails.
+pub struct Protoscope<'a> {
+    data: &'a [u8],
+    options: PrintOptions,
+}
+
+impl Message {
+    pub fn protoscope(&self) -> Protoscope<'_> {
+        self.view().protoscope()
+    }
+
+    pub fn protoscope_with_options(&self, options: &PrintOptions) -> Protoscope<'_> {
+        self.view().protoscope_with_options(options)
+    }
+}
+
+impl<'a> MessageRef<'a> {
+    pub fn protoscope(&self) -> Protoscope<'a> {
+        self.protoscope_with_options(&PrintOptions::default())
+    }
+
+    pub fn protoscope_with_options(&self, options: &PrintOptions) -> Protoscope<'a> {
+        Protoscope {
+            data: self.0,
+            options: *options,
+        }
+    }
+}
+
+impl<'a> FieldRef<'a> {
+    pub fn protoscope(&self) -> Protoscope<'a> {
+        MessageRef(self.raw).protoscope()
+    }
+
+    pub fn protoscope_with_options(&self, options: &PrintOptions) -> Protoscope<'a> {
+        // a single field is also a valid message
+        MessageRef(self.raw).protoscope_with_options(options)
+    }
+}
+
+impl core::fmt::Display for Protoscope<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = RawMessage::decode(self.data, self.options.max_depth);
+        Printer {
+            f,
+            options: &self.options,
+        }
+        .message(&message, 0)
+    }
+}
+
+impl core::fmt::Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.protoscope())
+    }
+}
+
+impl core::fmt::Debug for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.protoscope())
+    }
+}
+
+impl core::fmt::Display for MessageRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.protoscope())
+    }
+}
+
+impl core::fmt::Display for FieldRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.protoscope())
+    }
+}
+
+impl core::fmt::Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut data = bytes::BytesMut::with_capacity(self.tag.0.len() + self.data.byte_len());
+        self.serialize_into(&mut data);
+        write!(f, "{}", MessageRef(&data).protoscope())
+    }
+}
+
+impl core::fmt::Debug for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{self}")
+    }
+}
+
+struct Printer<'f, 'b, 'o> {
+    f: &'f mut Formatter<'b>,
+    options: &'o PrintOptions,
+}
+
+impl Printer<'_, '_, '_> {
+    /// prints the fields of `message` one per line, followed by anything which couldn't be
+    /// decoded, `depth` being the number of messages and groups enclosing them
+    fn message(&mut self, message: &RawMessage<'_>, depth: usize) -> Result {
+        for (index, field) in message.fields().iter().enumerate() {
+            if index > 0 {
+                writeln!(self.f)?;
+            }
+            self.indent(depth)?;
+            self.field(field, depth)?;
+        }
+
+        if !message.remainder().is_empty() {
+            if !message.fields().is_empty() {
+                writeln!(self.f)?;
+            }
+            self.indent(depth)?;
+            self.hex(message.remainder())?;
+        }
+        Ok(())
+    }
+
+    fn field(&mut self, field: &RawField<'_>, depth: usize) -> Result {
+        let field_id = field.get_field_id();
+        self.long_form(&field.field().tag)?;
+        // a group closed by a padded end-group tag is printed with explicit tags, which
+        // `!{}` has no room for
+        let end_long_form = match &field.field().data {
+            MessageObjectRef::Group(group) => long_form_extra(&group.end_field_id) > 0,
+            _ => false,
+        };
+        match (field.value(), &field.field().data) {
+            (_, MessageObjectRef::Varint(value)) => {
+                self.tag(field_id, "VARINT")?;
+                self.long_form(value)?;
+                // values with the sign bit set are most likely negative int32 or int64
+                write!(self.f, "{}", value.get() as i64)
+            }
+            (_, MessageObjectRef::I64(value)) => {
+                self.tag(field_id, "I64")?;
+                self.fixed64(value.get())
+            }
+            (_, MessageObjectRef::I32(value)) => {
+                self.tag(field_id, "I32")?;
+                self.fixed32(value.get())
+            }
+            (RawValue::Group(group), MessageObjectRef::Group(data))
+                if self.options.explicit_wire_types || end_long_form =>
+            {
+                writeln!(self.f, "{field_id}:SGROUP")?;
+                if !group.fields().is_empty() {
+                    self.message(group, depth + 1)?;
+                    writeln!(self.f)?;
+                }
+                self.indent(depth)?;
+                self.long_form(&data.end_field_id)?;
+                write!(self.f, "{field_id}:EGROUP")
+            }
+            (RawValue::Group(group), _) if group.fields().is_empty() => {
+                write!(self.f, "{field_id}: !{{}}")
+            }
+            (RawValue::Group(group), _) => {
+                writeln!(self.f, "{field_id}: !{{")?;
+                self.message(group, depth + 1)?;
+                writeln!(self.f)?;
+                self.indent(depth)?;
+                write!(self.f, "}}")
+            }
+            (RawValue::EGroup, _) => write!(self.f, "{field_id}:EGROUP"),
+            (value, _) => {
+                self.tag(field_id, "LEN")?;
+                self.len(value, depth)
+            }
+        }
+    }
+
+    /// `long-form:N` for a varint padded with `N` more bytes than it needs, before a tag
+    /// or a value
+    fn long_form(&mut self, varint: &VarintRef<'_>) -> Result {
+        match long_form_extra(varint) {
+            0 => Ok(()),
+            extra => write!(self.f, "long-form:{extra} "),
+        }
+    }
+
+    fn tag(&mut self, field_id: u64, wire_type: &str) -> Result {
+        match self.options.explicit_wire_types {
+            true => write!(self.f, "{field_id}:{wire_type} "),
+            false => write!(self.f, "{field_id}: "),
+        }
+    }
+
+    /// a classified `Len` payload in braces
+    fn len(&mut self, value: &RawValue<'_>, depth: usize) -> Result {
+        write!(self.f, "{{")?;
+        match value {
+            RawValue::Message(message) => {
+                writeln!(self.f)?;
+                self.message(message, depth + 1)?;
+                writeln!(self.f)?;
+                self.indent(depth)?;
+            }
+            RawValue::String(s) if !s.is_empty() => self.quoted(s.as_bytes())?,
+            RawValue::Bytes(bytes) => match self.options.bytes_format {
+                BytesFormat::Hex => self.hex(bytes)?,
+                BytesFormat::Escaped => self.quoted(bytes)?,
+            },
+            RawValue::PackedVarint(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    self.separator(index)?;
+                    write!(self.f, "{value}")?;
+                }
+            }
+            RawValue::PackedI64(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    self.separator(index)?;
+                    self.fixed64(*value)?;
+                }
+            }
+            RawValue::PackedI32(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    self.separator(index)?;
+                    self.fixed32(*value)?;
+                }
+            }
+            _ => {}
+        }
+        write!(self.f, "}}")
+    }
+
+    fn separator(&mut self, index: usize) -> Result {
+        match index {
+            0 => Ok(()),
+            _ => write!(self.f, " "),
+        }
+    }
+
+    fn fixed64(&mut self, value: i64) -> Result {
+        let float = f64::from_bits(value as u64);
+        match nice_float(float) {
+            true => write!(self.f, "{float:?}"),
+            false => write!(self.f, "{value}i64"),
+        }
+    }
+
+    fn fixed32(&mut self, value: i32) -> Result {
+        let float = f32::from_bits(value as u32);
+        match nice_float(float as f64) {
+            true => write!(self.f, "{float:?}i32"),
+            false => write!(self.f, "{value}i32"),
+        }
+    }
+
+    fn indent(&mut self, depth: usize) -> Result {
+        for _ in 0..depth {
+            write!(self.f, "  ")?;
+        }
+        Ok(())
+    }
+
+    fn hex(&mut self, data: &[u8]) -> Result {
+        write!(self.f, "`")?;
+        for byte in data {
+            write!(self.f, "{byte:02x}")?;
+        }
+        write!(self.f, "`")
+    }
+
+    /// a quoted string, keeping valid UTF-8 as it is and escaping everything else
+    fn quoted(&mut self, data: &[u8]) -> Result {
+        self.f.write_char('"')?;
+        for chunk in data.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                match c {
+                    '"' => write!(self.f, "\\\"")?,
+                    '\\' => write!(self.f, "\\\\")?,
+                    '\n' => write!(self.f, "\\n")?,
+                    '\r' => write!(self.f, "\\r")?,
+                    '\t' => write!(self.f, "\\t")?,
+                    c if c.is_control() => {
+                        let mut buf = [0; 4];
+                        for byte in c.encode_utf8(&mut buf).bytes() {
+                            write!(self.f, "\\x{byte:02x}")?;
+                        }
+                    }
+                    c => self.f.write_char(c)?,
+                }
+            }
+            for byte in chunk.invalid() {
+                write!(self.f, "\\x{byte:02x}")?;
+            }
+        }
+        self.f.write_char('"')
+    }
+}
+
+/// how many more bytes `varint` takes than its value needs
+fn long_form_extra(varint: &VarintRef<'_>) -> usize {
+    varint.0.len() - Varint::encoded_len(varint.get())
+}
@@ -0,0 +1,605 @@
+//! A schema-less `serde` representation of wire data, keyed by field number
+//!
+//! A message is a map with one entry per field, in wire order. The key is the field
+//! number. A suffix is added whenever the value alone wouldn't tell the wire type apart:
+//!
+//! - `"1"`: a varint as an unsigned number, a `Len` holding text as a string, or a `Len`
+//!   holding a message as a nested map, see `SerdeOptions::expand_messages`
+//! - `"2:i32"` / `"3:i64"`: a fixed width value as a signed number
+//! - `"4:bytes"`: any other `Len` as padded standard base64
+//! - `"5:group"`: a group as a nested map
+//!
+//! Consecutive occurrences of a field with the same key are merged into an array, e.g.
+//! `{"1": [1, 2, 3]}`. Keys are never repeated: if a field appears again after another
+//! one, or a message nested in it has to be, the message is instead an array of
+//! `[key, value]` entries in wire order, one per field, e.g. `[["1", 1], ["2", 2],
+//! ["1", 3]]`. Deserializing the output gives the same bytes back, as long as the data
+//! was canonically encoded.
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use hashbrown::HashSet;
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::base64;
+use crate::borrowed::{FieldRef, MessageObjectRef, MessageRef};
+use crate::classify::is_text;
+use crate::decode_options::DecodeOptions;
+use crate::error::DecodeError;
+use crate::field::Field;
+use crate::group::Group;
+use crate::i32::I32;
+use crate::i64::I64;
+use crate::len::Len;
+use crate::message::Message;
+use crate::message_object::MessageObject;
+use crate::varint::Varint;
+
+/// Options for the `serde` representation of wire data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerdeOptions {
+    /// serialize `Len` payloads which decode completely as a message, with strict
+    /// validation, as nested maps rather than as bytes. Text is always kept as a string
+    pub expand_messages: bool,
+    /// how many `Len` payloads to expand nested inside each other. Payloads nested deeper
+    /// are serialized as bytes, while groups are always expanded
+    pub max_depth: usize,
+}
+
+impl core::default::Default for SerdeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SerdeOptions {
+    pub const fn new() -> Self {
+        Self {
+            expand_messages: false,
+            max_depth: 100,
+        }
+    }
+}
+
+/// Wire data which implements `serde::Serialize`, see `MessageRef::serde_view`
+#[derive(Debug, Clone, Copy)]
+pub struct SerdeView<'a> {
+    data: &'a [u8],
+    options: SerdeOptions,
+    /// how many more `Len` payloads may be expanded
+    depth: usize,
+}
+
+impl Message {
+    pub fn serde_view(&self) -> SerdeView<'_> {
+        self.view().serde_view()
+    }
+
+    pub fn serde_view_with_options(&self, options: &SerdeOptions) -> SerdeView<'_> {
+        self.view().serde_view_with_options(options)
+    }
+}
+
+impl<'a> MessageRef<'a> {
+    pub fn serde_view(&self) -> SerdeView<'a> {
+        self.serde_view_with_options(&SerdeOptions::default())
+    }
+
+    pub fn serde_view_with_options(&self, options: &SerdeOptions) -> SerdeView<'a> {
+        SerdeView {
+            data: self.0,
+            options: *options,
+            depth: options.max_depth,
+        }
+    }
+}
+
+/// How a field is represented, which decides the suffix of its key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Varint,
+    I32,
+    I64,
+    Text,
+    Message,
+    Bytes,
+    Group,
+}
+
+impl Shape {
+    fn suffix(self) -> Option<Suffix> {
+        match self {
+            Shape::Varint | Shape::Text | Shape::Message => None,
+            Shape::I32 => Some(Suffix::I32),
+            Shape::I64 => Some(Suffix::I64),
+            Shape::Bytes => Some(Suffix::Bytes),
+            Shape::Group => Some(Suffix::Group),
+        }
+    }
+}
+
+/// The suffix of a key, naming a wire type the value alone wouldn't tell apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Suffix {
+    I32,
+    I64,
+    Bytes,
+    Group,
+}
+
+impl Suffix {
+    fn name(self) -> &'static str {
+        match self {
+            Suffix::I32 => "i32",
+            Suffix::I64 => "i64",
+            Suffix::Bytes => "bytes",
+            Suffix::Group => "group",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "i32" => Some(Suffix::I32),
+            "i64" => Some(Suffix::I64),
+            "bytes" => Some(Suffix::Bytes),
+            "group" => Some(Suffix::Group),
+            _ => None,
+        }
+    }
+}
+
+impl SerdeView<'_> {
+    fn shape(&self, field: &FieldRef<'_>) -> Shape {
+        match field.get_data() {
+            MessageObjectRef::Varint(_) => Shape::Varint,
+            MessageObjectRef::I32(_) => Shape::I32,
+            MessageObjectRef::I64(_) => Shape::I64,
+            MessageObjectRef::Group(_) | MessageObjectRef::EGroup => Shape::Group,
+            MessageObjectRef::Len(len) if is_text(len.inner) => Shape::Text,
+            MessageObjectRef::Len(len) => {
+                let message = self.options.expand_messages
+                    && self.depth > 0
+                    && MessageRef(len.inner)
+                        .into_try_iter_with_options(&DecodeOptions::strict())
+                        .all(|field| field.is_ok());
+                match message {
+                    true => Shape::Message,
+                    false => Shape::Bytes,
+                }
+            }
+        }
+    }
+
+    fn nested<'d>(&self, data: &'d [u8], depth: usize) -> SerdeView<'d> {
+        SerdeView {
+            data,
+            options: self.options,
+            depth,
+        }
+    }
+}
+
+/// A message with the shape of every field worked out and its nested messages parsed,
+/// so it is only parsed once however deeply it is nested
+struct Parsed<'a> {
+    fields: Vec<ParsedField<'a>>,
+    /// whether the message is an array of entries rather than a map, because a key comes
+    /// back after another one, or a nested message is one
+    entries: bool,
+}
+
+struct ParsedField<'a> {
+    shape: Shape,
+    field: FieldRef<'a>,
+    /// the message held by a `Message` or `Group` field
+    nested: Option<Parsed<'a>>,
+}
+
+impl ParsedField<'_> {
+    fn key(&self) -> (u64, Option<Suffix>) {
+        (self.field.get_field_id(), self.shape.suffix())
+    }
+}
+
+impl<'a> SerdeView<'a> {
+    fn parse(&self) -> Result<Parsed<'a>, DecodeError> {
+        let mut fields: Vec<ParsedField<'a>> = Vec::new();
+        let mut keys = HashSet::new();
+        let mut entries = false;
+        for field in MessageRef(self.data).into_try_iter() {
+            let field = field?;
+            let shape = self.shape(&field);
+            let nested = match (shape, field.get_data()) {
+                (Shape::Message, MessageObjectRef::Len(len)) => {
+                    Some(self.nested(len.inner, self.depth - 1).parse()?)
+                }
+                (Shape::Group, MessageObjectRef::Group(_)) => {
+                    Some(self.nested(group_content(&field), self.depth).parse()?)
+                }
+                _ => None,
+            };
+            let field = ParsedField {
+                shape,
+                field,
+                nested,
+            };
+            let continues = fields.last().is_some_and(|last| last.key() == field.key());
+            entries |= !continues && !keys.insert(field.key());
+            entries |= field.nested.as_ref().is_some_and(|nested| nested.entries);
+            fields.push(field);
+        }
+        Ok(Parsed { fields, entries })
+    }
+}
+
+fn key(field: &ParsedField<'_>) -> alloc::string::String {
+    match field.shape.suffix() {
+        Some(suffix) => format!("{}:{}", field.field.get_field_id(), suffix.name()),
+        None => format!("{}", field.field.get_field_id()),
+    }
+}
+
+/// the fields between the tags starting and ending a group
+fn group_content<'a>(field: &FieldRef<'a>) -> &'a [u8] {
+    match field.get_data() {
+        MessageObjectRef::Group(group) => {
+            &field.raw[field.tag.0.len()..field.raw.len() - group.end_field_id.0.len()]
+        }
+        _ => &[],
+    }
+}
+
+impl Serialize for SerdeView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.parse()
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for Parsed<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.entries {
+            let mut seq = serializer.serialize_seq(Some(self.fields.len()))?;
+            for field in &self.fields {
+                seq.serialize_element(&(key(field).as_str(), field))?;
+            }
+            return seq.end();
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        for run in self.fields.chunk_by(|a, b| a.key() == b.key()) {
+            let key = key(&run[0]);
+            match run {
+                [field] => map.serialize_entry(key.as_str(), field)?,
+                _ => map.serialize_entry(key.as_str(), &FieldRun(run))?,
+            }
+        }
+        map.end()
+    }
+}
+
+impl Serialize for ParsedField<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(nested) = &self.nested {
+            return nested.serialize(serializer);
+        }
+        match self.field.get_data() {
+            MessageObjectRef::Varint(value) => serializer.serialize_u64(value.get()),
+            MessageObjectRef::I32(value) => serializer.serialize_i32(value.get()),
+            MessageObjectRef::I64(value) => serializer.serialize_i64(value.get()),
+            MessageObjectRef::Len(len) => match self.shape {
+                // safety: the shape is only `Text` for valid UTF-8
+                Shape::Text => serializer.serialize_str(core::str::from_utf8(len.inner).unwrap()),
+                _ => serializer.serialize_str(&base64::encode(len.inner)),
+            },
+            _ => Err(ser::Error::custom(format!(
+                "unmatched end group for field {}",
+                self.field.get_field_id()
+            ))),
+        }
+    }
+}
+
+struct FieldRun<'v, 'a>(&'v [ParsedField<'a>]);
+
+impl Serialize for FieldRun<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for field in self.0 {
+            seq.serialize_element(field)?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serde_view().serialize(serializer)
+    }
+}
+
+impl Serialize for MessageRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serde_view().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MessageVisitor)
+    }
+}
+
+struct MessageVisitor;
+
+impl<'de> Visitor<'de> for MessageVisitor {
+    type Value = Message;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("a map from field numbers to values, or an array of [key, value] entries")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Message, A::Error> {
+        let mut message = Message::new();
+        while let Some(key) = map.next_key::<Key>()? {
+            map.next_value_seed(FieldSeed {
+                key,
+                message: &mut message,
+                position: Position::Map,
+            })?;
+        }
+        Ok(message)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Message, A::Error> {
+        let mut message = Message::new();
+        while seq
+            .next_element_seed(EntrySeed {
+                message: &mut message,
+            })?
+            .is_some()
+        {}
+        Ok(message)
+    }
+}
+
+const ENTRY: &str = "a [key, value] entry";
+
+/// Pushes the field of one `[key, value]` entry onto `message`
+struct EntrySeed<'m> {
+    message: &'m mut Message,
+}
+
+impl<'de> DeserializeSeed<'de> for EntrySeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntrySeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(ENTRY)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let key = seq
+            .next_element::<Key>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &ENTRY))?;
+        let seed = FieldSeed {
+            key,
+            message: self.message,
+            position: Position::Entry,
+        };
+        if seq.next_element_seed(seed)?.is_none() {
+            return Err(de::Error::invalid_length(1, &ENTRY));
+        }
+        if seq.next_element::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(3, &ENTRY));
+        }
+        Ok(())
+    }
+}
+
+/// A field number with an optional suffix, e.g. `"1"` or `"2:i32"`
+#[derive(Debug, Clone, Copy)]
+struct Key {
+    field_id: u64,
+    suffix: Option<Suffix>,
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
+
+struct KeyVisitor;
+
+impl KeyVisitor {
+    fn key<E: de::Error>(field_id: u64, suffix: Option<Suffix>) -> Result<Key, E> {
+        Field::validate_field_id(field_id).map_err(E::custom)?;
+        Ok(Key { field_id, suffix })
+    }
+}
+
+impl<'de> Visitor<'de> for KeyVisitor {
+    type Value = Key;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("a field number, optionally followed by :i32, :i64, :bytes or :group")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Key, E> {
+        Self::key(value, None)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Key, E> {
+        let invalid = || E::invalid_value(de::Unexpected::Str(value), &self);
+        let (number, suffix) = match value.split_once(':') {
+            Some((number, suffix)) => (number, Some(Suffix::parse(suffix).ok_or_else(invalid)?)),
+            None => (value, None),
+        };
+        Self::key(number.parse().map_err(|_| invalid())?, suffix)
+    }
+}
+
+/// Where a value is found, which decides what an array means
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    /// the value of a map entry, where an array holds consecutive occurrences
+    Map,
+    /// an element of such an array, which can't be an array itself
+    Array,
+    /// the value of a `[key, value]` entry, where an array is a message of entries
+    Entry,
+}
+
+/// Pushes the fields of one entry onto `message`, a single value or an array of them
+struct FieldSeed<'m> {
+    key: Key,
+    message: &'m mut Message,
+    position: Position,
+}
+
+impl FieldSeed<'_> {
+    fn push<E: de::Error>(self, object: MessageObject) -> Result<(), E> {
+        self.message.push(Field::new(self.key.field_id, object));
+        Ok(())
+    }
+
+    /// pushes `message` as a `Len` or a group, depending on the key
+    fn push_message<E: de::Error>(self, message: Message) -> Result<(), E> {
+        let object = match self.key.suffix {
+            Some(Suffix::Group) => {
+                let mut group = Group::new(self.key.field_id);
+                for field in message.into_try_iter() {
+                    group.push(field.map_err(E::custom)?);
+                }
+                MessageObject::Group(group)
+            }
+            _ => MessageObject::Len(Len::new_message(message)),
+        };
+        self.push(object)
+    }
+
+    fn holds_message(&self) -> bool {
+        matches!(self.key.suffix, None | Some(Suffix::Group))
+    }
+
+    fn unexpected<E: de::Error>(&self, unexpected: de::Unexpected<'_>) -> E {
+        E::invalid_type(unexpected, self)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for FieldSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FieldSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let expected = match self.key.suffix {
+            None => "a number, a string or a map",
+            Some(Suffix::I32 | Suffix::I64) => "a number",
+            Some(Suffix::Bytes) => "a base64 string",
+            Some(Suffix::Group) => "a map",
+        };
+        match self.position {
+            Position::Map => write!(f, "{expected}, or an array of them"),
+            Position::Entry if self.holds_message() => {
+                write!(f, "{expected}, or an array of entries")
+            }
+            Position::Array | Position::Entry => f.write_str(expected),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<(), E> {
+        let out_of_range = || E::invalid_value(de::Unexpected::Unsigned(value), &self);
+        let object = match self.key.suffix {
+            None => MessageObject::Varint(Varint::new(value)),
+            // fixed width values are written signed, but unsigned ones are read back too
+            Some(Suffix::I32) => match u32::try_from(value) {
+                Ok(value) => MessageObject::I32(I32::new(value as i32)),
+                Err(_) => return Err(out_of_range()),
+            },
+            Some(Suffix::I64) => MessageObject::I64(I64::new(value as i64)),
+            _ => return Err(self.unexpected(de::Unexpected::Unsigned(value))),
+        };
+        self.push(object)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<(), E> {
+        if value >= 0 {
+            return self.visit_u64(value as u64);
+        }
+        let object = match self.key.suffix {
+            None => MessageObject::Varint(Varint::new_proto_int64(value)),
+            Some(Suffix::I32) => match i32::try_from(value) {
+                Ok(value) => MessageObject::I32(I32::new(value)),
+                Err(_) => return Err(E::invalid_value(de::Unexpected::Signed(value), &self)),
+            },
+            Some(Suffix::I64) => MessageObject::I64(I64::new(value)),
+            _ => return Err(self.unexpected(de::Unexpected::Signed(value))),
+        };
+        self.push(object)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<(), E> {
+        let object = match self.key.suffix {
+            None => MessageObject::Len(Len::new_string(value)),
+            Some(Suffix::Bytes) => {
+                let data = base64::decode(value)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))?;
+                let mut len = Len::new();
+                len.set_bytes(&data);
+                MessageObject::Len(len)
+            }
+            _ => return Err(self.unexpected(de::Unexpected::Str(value))),
+        };
+        self.push(object)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        if !self.holds_message() {
+            return Err(self.unexpected(de::Unexpected::Map));
+        }
+        let message = MessageVisitor.visit_map(map)?;
+        self.push_message(message)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        match self.position {
+            Position::Map => {}
+            Position::Array => return Err(self.unexpected(de::Unexpected::Seq)),
+            Position::Entry if self.holds_message() => {
+                let message = MessageVisitor.visit_seq(seq)?;
+                return self.push_message(message);
+            }
+            Position::Entry => return Err(self.unexpected(de::Unexpected::Seq)),
+        }
+        loop {
+            let seed = FieldSeed {
+                key: self.key,
+                message: &mut *self.message,
+                position: Position::Array,
+            };
+            if seq.next_element_seed(seed)?.is_none() {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,920 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::borrowed::{FieldRef, MessageObjectRef};
+use crate::descriptor::{FieldDescriptor, Kind, MessageDescriptor};
+use crate::dynamic_message::{DynamicError, DynamicMessage, MapKey, Value};
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::message::Message;
+use crate::wire_data::WireData;
+
+/// Why protobuf text format could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextFormatErrorKind {
+    /// a character which can't start any token
+    UnexpectedChar(char),
+    /// the text ended, or held something else, where the given token was expected
+    Expected(&'static str),
+    /// a string literal without its closing quote
+    UnterminatedString,
+    /// a backslash escape in a string which isn't supported
+    InvalidEscape,
+    /// the message type has no field of this name
+    UnknownField(String),
+    /// no extension of the message type has this fully qualified name
+    UnknownExtension(String),
+    /// an `Any` names a message type the pool doesn't have
+    UnknownType(String),
+    /// a value which doesn't fit the type of the field, such as a number out of range or
+    /// an enum value the enum doesn't declare
+    InvalidValue(String),
+    /// a field which isn't repeated appears more than once, or more than one field of a
+    /// oneof is set
+    DuplicateField(String),
+    /// messages were nested more than 100 deep
+    DepthLimitExceeded,
+    /// the parsed values could not be encoded
+    Dynamic(DynamicError),
+}
+
+/// An error raised while parsing protobuf text format, with the position it was found at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFormatError {
+    pub kind: TextFormatErrorKind,
+    /// 1-based line of the error
+    pub line: usize,
+    /// 1-based column, in characters, of the error
+    pub column: usize,
+}
+
+impl core::fmt::Display for TextFormatErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedChar(c) => write!(f, "unexpected character {c:?}"),
+            Self::Expected(token) => write!(f, "expected {token}"),
+            Self::UnterminatedString => write!(f, "unterminated string"),
+            Self::InvalidEscape => write!(f, "invalid escape sequence"),
+            Self::UnknownField(name) => write!(f, "no field named {name}"),
+            Self::UnknownExtension(name) => write!(f, "no extension named {name}"),
+            Self::UnknownType(url) => write!(f, "no message type for {url}"),
+            Self::InvalidValue(name) => write!(f, "invalid value for {name}"),
+            Self::DuplicateField(name) => write!(f, "field {name} is set more than once"),
+            Self::DepthLimitExceeded => write!(f, "messages nested too deeply"),
+            Self::Dynamic(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl core::fmt::Display for TextFormatError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.kind, self.line, self.column
+        )
+    }
+}
+
+impl core::error::Error for TextFormatError {}
+
+/// How deeply messages may be nested, bounding recursion in both directions
+const MAX_DEPTH: usize = 100;
+
+impl<'a> DynamicMessage<'a> {
+    /// the message in protobuf text format, one field per line
+    ///
+    /// Fields and extensions are printed in field number order, an `Any` whose type is in
+    /// the pool is expanded as `[type.googleapis.com/...] { ... }`, and fields the schema
+    /// doesn't declare are printed by number, as protoc does, which can't be parsed back.
+    pub fn to_text_format(&self) -> Result<String, DynamicError> {
+        let mut out = String::new();
+        print_message(self, 0, &mut out)?;
+        Ok(out)
+    }
+
+    /// parses protobuf text format into a message of type `descriptor`
+    ///
+    /// Both `{}` and `<>` delimit messages, repeated fields may be given one value at a
+    /// time or as a `[...]` list, and fields may be separated by `,` or `;`.
+    pub fn from_text_format(
+        descriptor: MessageDescriptor<'a>,
+        text: &str,
+    ) -> Result<Self, TextFormatError> {
+        let mut parser = Parser { text, position: 0 };
+        let message = parser.message(descriptor, None, 0)?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(message),
+            Some(c) => Err(parser.error(TextFormatErrorKind::UnexpectedChar(c))),
+        }
+    }
+}
+
+impl Message {
+    /// the message in protobuf text format, read as type `descriptor`
+    pub fn to_text_format(
+        &self,
+        descriptor: MessageDescriptor<'_>,
+    ) -> Result<String, DynamicError> {
+        DynamicMessage::from(descriptor, Message(self.0.clone())).to_text_format()
+    }
+
+    pub fn from_text_format(
+        descriptor: MessageDescriptor<'_>,
+        text: &str,
+    ) -> Result<Self, TextFormatError> {
+        DynamicMessage::from_text_format(descriptor, text).map(DynamicMessage::into_message)
+    }
+}
+
+fn indent(depth: usize, out: &mut String) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+fn print_message(
+    message: &DynamicMessage<'_>,
+    depth: usize,
+    out: &mut String,
+) -> Result<(), DynamicError> {
+    if depth > MAX_DEPTH {
+        let kind = DecodeErrorKind::DepthLimitExceeded { limit: MAX_DEPTH };
+        return Err(DynamicError::Decode(DecodeError::new(kind, 0)));
+    }
+    let descriptor = message.descriptor();
+    if descriptor.full_name() == "google.protobuf.Any" && print_any(message, depth, out)? {
+        return Ok(());
+    }
+
+    let mut fields: Vec<_> = descriptor.fields().chain(descriptor.extensions()).collect();
+    fields.sort_by_key(|field| field.number());
+    for field in &fields {
+        let Some(value) = message.get_present_field(*field)? else {
+            continue;
+        };
+        let name = match field.kind() {
+            _ if field.is_extension() => format!("[{}]", field.full_name()),
+            // groups are named by their type
+            Kind::Group(group) => group.name().to_string(),
+            _ => field.name().to_string(),
+        };
+        match value {
+            Value::List(values) => {
+                for value in values {
+                    print_field(&name, field.kind(), value, depth, out)?;
+                }
+            }
+            Value::Map(map) => {
+                let Kind::Message(entry) = field.kind() else {
+                    continue;
+                };
+                let (Some(key_field), Some(value_field)) = (entry.get_field(1), entry.get_field(2))
+                else {
+                    continue;
+                };
+                for (key, value) in map {
+                    indent(depth, out);
+                    out.push_str(&name);
+                    out.push_str(" {\n");
+                    print_field("key", key_field.kind(), key.into(), depth + 1, out)?;
+                    print_field("value", value_field.kind(), value, depth + 1, out)?;
+                    indent(depth, out);
+                    out.push_str("}\n");
+                }
+            }
+            value => print_field(&name, field.kind(), value, depth, out)?,
+        }
+    }
+
+    let known = |number: u64| fields.iter().any(|field| field.number() as u64 == number);
+    for field in message.message().view().into_try_iter() {
+        let field = field?;
+        if !known(field.get_field_id()) {
+            print_unknown(&field, depth, out);
+        }
+    }
+    Ok(())
+}
+
+/// prints an `Any` as its expanded contents, returning false if its type isn't in the pool
+/// or the contents don't decode
+fn print_any(
+    message: &DynamicMessage<'_>,
+    depth: usize,
+    out: &mut String,
+) -> Result<bool, DynamicError> {
+    let (Ok(Value::String(type_url)), Ok(Value::Bytes(value))) =
+        (message.get("type_url"), message.get("value"))
+    else {
+        return Ok(false);
+    };
+    let name = type_url.rsplit_once('/').map_or("", |(_, name)| name);
+    let Some(inner) = message.descriptor().parent_pool().get_message_by_name(name) else {
+        return Ok(false);
+    };
+    let inner = DynamicMessage::from(inner, Message(WireData::new(bytes::Bytes::from(value))));
+    let mut contents = String::new();
+    if print_message(&inner, depth + 1, &mut contents).is_err() {
+        return Ok(false);
+    }
+    indent(depth, out);
+    let _ = writeln!(out, "[{type_url}] {{");
+    out.push_str(&contents);
+    indent(depth, out);
+    out.push_str("}\n");
+    Ok(true)
+}
+
+/// prints a single value of a field
+fn print_field(
+    name: &str,
+    kind: Kind<'_>,
+    value: Value<'_>,
+    depth: usize,
+    out: &mut String,
+) -> Result<(), DynamicError> {
+    indent(depth, out);
+    out.push_str(name);
+    match value {
+        Value::Message(message) => {
+            out.push_str(" {\n");
+            print_message(&message, depth + 1, out)?;
+            indent(depth, out);
+            out.push_str("}\n");
+            return Ok(());
+        }
+        Value::Bool(value) => {
+            let _ = write!(out, ": {value}");
+        }
+        Value::I32(value) => {
+            let _ = write!(out, ": {value}");
+        }
+        Value::I64(value) => {
+            let _ = write!(out, ": {value}");
+        }
+        Value::U32(value) => {
+            let _ = write!(out, ": {value}");
+        }
+        Value::U64(value) => {
+            let _ = write!(out, ": {value}");
+        }
+        Value::F32(value) => {
+            let text = float_text(value.is_nan(), value.is_infinite(), value < 0.0, || {
+                format!("{value:?}")
+            });
+            let _ = write!(out, ": {text}");
+        }
+        Value::F64(value) => {
+            let text = float_text(value.is_nan(), value.is_infinite(), value < 0.0, || {
+                format!("{value:?}")
+            });
+            let _ = write!(out, ": {text}");
+        }
+        Value::String(value) => {
+            out.push_str(": ");
+            quote(value.as_bytes(), true, out);
+        }
+        Value::Bytes(value) => {
+            out.push_str(": ");
+            quote(&value, false, out);
+        }
+        Value::Enum(number) => match kind {
+            Kind::Enum(e) => match e.get_value(number) {
+                Some(value) => {
+                    let _ = write!(out, ": {}", value.name());
+                }
+                None => {
+                    let _ = write!(out, ": {number}");
+                }
+            },
+            _ => {
+                let _ = write!(out, ": {number}");
+            }
+        },
+        // lists and maps are printed one element at a time by `print_message`
+        Value::List(_) | Value::Map(_) => {}
+    }
+    out.push('\n');
+    Ok(())
+}
+
+/// a float as text format writes it, `inf`, `-inf` and `nan` included
+fn float_text(nan: bool, infinite: bool, negative: bool, text: impl Fn() -> String) -> String {
+    match (nan, infinite, negative) {
+        (true, _, _) => "nan".into(),
+        (_, true, false) => "inf".into(),
+        (_, true, true) => "-inf".into(),
+        _ => {
+            let text = text();
+            match text.strip_suffix(".0") {
+                Some(text) => text.to_string(),
+                None => text,
+            }
+        }
+    }
+}
+
+/// a double quoted string, escaping quotes, backslashes and unprintable bytes. Text keeps
+/// its UTF-8 characters while bytes outside ASCII are escaped
+fn quote(data: &[u8], utf8: bool, out: &mut String) {
+    out.push('"');
+    let text = match utf8 {
+        true => core::str::from_utf8(data).ok(),
+        false => None,
+    };
+    let escape = |byte: u8, out: &mut String| match byte {
+        b'\n' => out.push_str("\\n"),
+        b'\r' => out.push_str("\\r"),
+        b'\t' => out.push_str("\\t"),
+        b'"' => out.push_str("\\\""),
+        b'\'' => out.push_str("\\'"),
+        b'\\' => out.push_str("\\\\"),
+        0x20..0x7f => out.push(byte as char),
+        byte => {
+            let _ = write!(out, "\\{byte:03o}");
+        }
+    };
+    match text {
+        Some(text) => {
+            for c in text.chars() {
+                match c.is_ascii() {
+                    true => escape(c as u8, out),
+                    false => out.push(c),
+                }
+            }
+        }
+        None => {
+            for byte in data {
+                escape(*byte, out);
+            }
+        }
+    }
+    out.push('"');
+}
+
+/// prints a field the schema doesn't declare by its number and wire type alone
+fn print_unknown(field: &FieldRef<'_>, depth: usize, out: &mut String) {
+    indent(depth, out);
+    let number = field.get_field_id();
+    match &field.data {
+        MessageObjectRef::Varint(value) => {
+            let _ = writeln!(out, "{number}: {}", value.get());
+        }
+        MessageObjectRef::I32(value) => {
+            let _ = writeln!(out, "{number}: 0x{:08x}", value.get() as u32);
+        }
+        MessageObjectRef::I64(value) => {
+            let _ = writeln!(out, "{number}: 0x{:016x}", value.get() as u64);
+        }
+        MessageObjectRef::Len(len) => {
+            let _ = write!(out, "{number}: ");
+            quote(len.inner, false, out);
+            out.push('\n');
+        }
+        MessageObjectRef::Group(group) => {
+            let _ = writeln!(out, "{number} {{");
+            for field in group.get_fields() {
+                print_unknown(field, depth + 1, out);
+            }
+            indent(depth, out);
+            out.push_str("}\n");
+        }
+        MessageObjectRef::EGroup => {
+            let _ = writeln!(out, "{number}: EGROUP");
+        }
+    }
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    position: usize,
+}
+
+/// A number as written, before it is read as the type of a field
+struct Number<'t> {
+    negative: bool,
+    text: &'t str,
+}
+
+impl<'t> Parser<'t> {
+    fn error(&self, kind: TextFormatErrorKind) -> TextFormatError {
+        self.error_at(self.position, kind)
+    }
+
+    fn error_at(&self, position: usize, kind: TextFormatErrorKind) -> TextFormatError {
+        let before = &self.text[..position];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        TextFormatError { kind, line, column }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    /// skips whitespace and `#` comments
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.text[self.position..];
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if !trimmed.starts_with('#') {
+                return;
+            }
+            self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    /// consumes `c` if it is the next token
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        match self.peek() == Some(c) {
+            true => {
+                self.position += c.len_utf8();
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn expect(&mut self, c: char, token: &'static str) -> Result<(), TextFormatError> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.error(TextFormatErrorKind::Expected(token))),
+        }
+    }
+
+    /// the characters from the current position while `accept` holds
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &'t str {
+        let rest = &self.text[self.position..];
+        let end = rest.find(|c| !accept(c)).unwrap_or(rest.len());
+        self.position += end;
+        &rest[..end]
+    }
+
+    fn identifier(&mut self) -> Option<&'t str> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                Some(self.take_while(|c| c.is_ascii_alphanumeric() || c == '_'))
+            }
+            _ => None,
+        }
+    }
+
+    /// the fields of a message up to `close`, or the end of the text at the top level
+    fn message<'a>(
+        &mut self,
+        descriptor: MessageDescriptor<'a>,
+        close: Option<char>,
+        depth: usize,
+    ) -> Result<DynamicMessage<'a>, TextFormatError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error(TextFormatErrorKind::DepthLimitExceeded));
+        }
+        let mut message = DynamicMessage::new(descriptor);
+        let mut values: Vec<(FieldDescriptor<'a>, Value<'a>)> = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match (self.peek(), close) {
+                (None, None) => break,
+                (None, Some(_)) => {
+                    let token = if close == Some('>') { "'>'" } else { "'}'" };
+                    return Err(self.error(TextFormatErrorKind::Expected(token)));
+                }
+                (Some(c), Some(close)) if c == close => {
+                    self.position += 1;
+                    break;
+                }
+                _ => {}
+            }
+
+            let start = self.position;
+            if self.eat('[') {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || "_./".contains(c));
+                self.expect(']', "']'")?;
+                if name.contains('/') {
+                    if descriptor.full_name() != "google.protobuf.Any" {
+                        return Err(
+                            self.error_at(start, TextFormatErrorKind::UnknownField(name.into()))
+                        );
+                    }
+                    self.any(&mut message, name, start, depth)?;
+                    self.separator();
+                    continue;
+                }
+                let extension = descriptor
+                    .parent_pool()
+                    .get_extension_by_name(name)
+                    .filter(|field| field.containing_message() == descriptor);
+                let Some(field) = extension else {
+                    return Err(
+                        self.error_at(start, TextFormatErrorKind::UnknownExtension(name.into()))
+                    );
+                };
+                self.field(field, &mut values, start, depth)?;
+            } else {
+                let Some(name) = self.identifier() else {
+                    let c = self.peek().unwrap_or(' ');
+                    return Err(self.error(TextFormatErrorKind::UnexpectedChar(c)));
+                };
+                // a group may be named by its type as well as its field
+                let field = descriptor.get_field_by_name(name).or_else(|| {
+                    descriptor.fields().find(
+                        |field| matches!(field.kind(), Kind::Group(group) if group.name() == name),
+                    )
+                });
+                let Some(field) = field else {
+                    return Err(
+                        self.error_at(start, TextFormatErrorKind::UnknownField(name.into()))
+                    );
+                };
+                self.field(field, &mut values, start, depth)?;
+            }
+            self.separator();
+        }
+
+        for (field, value) in values {
+            message
+                .set_present_field(field, value)
+                .map_err(|e| self.error(TextFormatErrorKind::Dynamic(e)))?;
+        }
+        Ok(message)
+    }
+
+    /// skips the optional `,` or `;` after a field
+    fn separator(&mut self) {
+        if !self.eat(',') {
+            self.eat(';');
+        }
+    }
+
+    /// the value of `field`, added to the values collected for the message so far
+    fn field<'a>(
+        &mut self,
+        field: FieldDescriptor<'a>,
+        values: &mut Vec<(FieldDescriptor<'a>, Value<'a>)>,
+        start: usize,
+        depth: usize,
+    ) -> Result<(), TextFormatError> {
+        let is_message = matches!(field.kind(), Kind::Message(_) | Kind::Group(_));
+        // the colon is optional before a message or a list of messages
+        if !self.eat(':') && !is_message {
+            return Err(self.error(TextFormatErrorKind::Expected("':'")));
+        }
+
+        let mut parsed = Vec::new();
+        if field.label() == crate::descriptor::Label::Repeated && self.eat('[') {
+            if !self.eat(']') {
+                loop {
+                    parsed.push(self.value(field, depth)?);
+                    if self.eat(']') {
+                        break;
+                    }
+                    self.expect(',', "','")?;
+                }
+            }
+        } else {
+            parsed.push(self.value(field, depth)?);
+        }
+
+        let existing = values.iter().position(|(other, _)| *other == field);
+        if field.is_map() {
+            let mut map = match existing {
+                Some(index) => match values.remove(index).1 {
+                    Value::Map(map) => map,
+                    _ => BTreeMap::new(),
+                },
+                None => BTreeMap::new(),
+            };
+            for entry in parsed {
+                let (key, value) = self.entry(field, entry, start)?;
+                map.insert(key, value);
+            }
+            values.push((field, Value::Map(map)));
+        } else if field.is_list() {
+            match existing.map(|index| &mut values[index].1) {
+                Some(Value::List(list)) => list.extend(parsed),
+                _ => values.push((field, Value::List(parsed))),
+            }
+        } else {
+            let oneof = field.containing_oneof();
+            let duplicate = values.iter().find_map(|(other, _)| match other {
+                other if *other == field => Some(field.name()),
+                other if oneof.is_some() && other.containing_oneof() == oneof => {
+                    oneof.map(|oneof| oneof.name())
+                }
+                _ => None,
+            });
+            if let Some(name) = duplicate {
+                return Err(self.error_at(start, TextFormatErrorKind::DuplicateField(name.into())));
+            }
+            values.extend(parsed.into_iter().map(|value| (field, value)));
+        }
+        Ok(())
+    }
+
+    /// the key and value of a parsed map entry
+    fn entry<'a>(
+        &self,
+        field: FieldDescriptor<'a>,
+        entry: Value<'a>,
+        start: usize,
+    ) -> Result<(MapKey, Value<'a>), TextFormatError> {
+        let Value::Message(entry) = entry else {
+            unreachable!("map entries are messages")
+        };
+        let descriptor = entry.descriptor();
+        let (Some(key), Some(value)) = (descriptor.get_field(1), descriptor.get_field(2)) else {
+            return Err(self.error_at(
+                start,
+                TextFormatErrorKind::InvalidValue(field.name().into()),
+            ));
+        };
+        let error = |e| self.error_at(start, TextFormatErrorKind::Dynamic(e));
+        let key = match entry.get_field(key).map_err(error)? {
+            Value::Bool(key) => MapKey::Bool(key),
+            Value::I32(key) => MapKey::I32(key),
+            Value::I64(key) => MapKey::I64(key),
+            Value::U32(key) => MapKey::U32(key),
+            Value::U64(key) => MapKey::U64(key),
+            Value::String(key) => MapKey::String(key),
+            _ => {
+                return Err(self.error_at(
+                    start,
+                    TextFormatErrorKind::InvalidValue(field.name().into()),
+                ))
+            }
+        };
+        Ok((key, entry.get_field(value).map_err(error)?))
+    }
+
+    /// the expanded contents of an `Any`, `[type.googleapis.com/...] { ... }`
+    fn any<'a>(
+        &mut self,
+        message: &mut DynamicMessage<'a>,
+        type_url: &str,
+        start: usize,
+        depth: usize,
+    ) -> Result<(), TextFormatError> {
+        let descriptor = message.descriptor();
+        let name = type_url.rsplit_once('/').map_or("", |(_, name)| name);
+        let Some(inner) = descriptor.parent_pool().get_message_by_name(name) else {
+            return Err(self.error_at(start, TextFormatErrorKind::UnknownType(type_url.into())));
+        };
+        if message.has("type_url").unwrap_or(false) {
+            return Err(self.error_at(start, TextFormatErrorKind::DuplicateField(type_url.into())));
+        }
+        self.eat(':');
+        let close = self.open()?;
+        let inner = self.message(inner, Some(close), depth + 1)?;
+        let value = inner.into_message().0.as_ref().to_vec();
+        message
+            .set("type_url", Value::String(type_url.into()))
+            .and_then(|()| message.set("value", Value::Bytes(value)))
+            .map_err(|e| self.error(TextFormatErrorKind::Dynamic(e)))
+    }
+
+    /// consumes the `{` or `<` opening a message, returning the character closing it
+    fn open(&mut self) -> Result<char, TextFormatError> {
+        match (self.eat('{'), self.eat('<')) {
+            (true, _) => Ok('}'),
+            (_, true) => Ok('>'),
+            _ => Err(self.error(TextFormatErrorKind::Expected("'{'"))),
+        }
+    }
+
+    /// a single value of `field`
+    fn value<'a>(
+        &mut self,
+        field: FieldDescriptor<'a>,
+        depth: usize,
+    ) -> Result<Value<'a>, TextFormatError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let invalid = |parser: &mut Self| {
+            parser.error_at(
+                start,
+                TextFormatErrorKind::InvalidValue(field.name().into()),
+            )
+        };
+
+        Ok(match field.kind() {
+            Kind::Message(descriptor) | Kind::Group(descriptor) => {
+                let close = self.open()?;
+                Value::Message(self.message(descriptor, Some(close), depth + 1)?)
+            }
+            Kind::String => match String::from_utf8(self.string()?) {
+                Ok(value) => Value::String(value),
+                Err(_) => return Err(invalid(self)),
+            },
+            Kind::Bytes => Value::Bytes(self.string()?),
+            Kind::Bool => match self.identifier() {
+                Some("true" | "True" | "t") => Value::Bool(true),
+                Some("false" | "False" | "f") => Value::Bool(false),
+                Some(_) => return Err(invalid(self)),
+                None => match self.number()? {
+                    Number {
+                        negative: false,
+                        text: "1",
+                    } => Value::Bool(true),
+                    Number {
+                        negative: false,
+                        text: "0",
+                    } => Value::Bool(false),
+                    _ => return Err(invalid(self)),
+                },
+            },
+            Kind::Enum(e) => match self.identifier() {
+                Some(name) => match e.get_value_by_name(name) {
+                    Some(value) => Value::Enum(value.number()),
+                    None => return Err(invalid(self)),
+                },
+                None => {
+                    let number = self.number()?.integer().ok_or_else(|| invalid(self))?;
+                    Value::Enum(i32::try_from(number).map_err(|_| invalid(self))?)
+                }
+            },
+            Kind::Float | Kind::Double => {
+                let negative = self.eat('-');
+                let value = match self.identifier() {
+                    Some(name) => match name.to_ascii_lowercase().as_str() {
+                        "inf" | "infinity" => f64::INFINITY,
+                        "nan" => f64::NAN,
+                        _ => return Err(invalid(self)),
+                    },
+                    None => self.number()?.float().ok_or_else(|| invalid(self))?,
+                };
+                let value = if negative { -value } else { value };
+                match field.kind() {
+                    Kind::Float => Value::F32(value as f32),
+                    _ => Value::F64(value),
+                }
+            }
+            kind => {
+                let number = self.number()?.integer().ok_or_else(|| invalid(self))?;
+                let value = match kind {
+                    Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                        i32::try_from(number).ok().map(Value::I32)
+                    }
+                    Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                        i64::try_from(number).ok().map(Value::I64)
+                    }
+                    Kind::Uint32 | Kind::Fixed32 => u32::try_from(number).ok().map(Value::U32),
+                    _ => u64::try_from(number).ok().map(Value::U64),
+                };
+                value.ok_or_else(|| invalid(self))?
+            }
+        })
+    }
+
+    /// a number with an optional minus sign
+    fn number(&mut self) -> Result<Number<'t>, TextFormatError> {
+        let negative = self.eat('-');
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {}
+            _ => return Err(self.error(TextFormatErrorKind::Expected("a number"))),
+        }
+        let rest = &self.text[self.position..];
+        let mut end = 0;
+        let mut previous = ' ';
+        let hex = rest.starts_with("0x") || rest.starts_with("0X");
+        for c in rest.chars() {
+            let exponent_sign = (c == '+' || c == '-') && "eE".contains(previous) && !hex;
+            if !(c.is_ascii_alphanumeric() || c == '.' || exponent_sign) {
+                break;
+            }
+            end += 1;
+            previous = c;
+        }
+        self.position += end;
+        Ok(Number {
+            negative,
+            text: &rest[..end],
+        })
+    }
+
+    /// one or more adjacent string literals, concatenated
+    fn string(&mut self) -> Result<Vec<u8>, TextFormatError> {
+        let mut value = Vec::new();
+        self.skip_whitespace();
+        if !matches!(self.peek(), Some('"' | '\'')) {
+            return Err(self.error(TextFormatErrorKind::Expected("a string")));
+        }
+        while let Some(quote @ ('"' | '\'')) = self.peek() {
+            let start = self.position;
+            self.position += 1;
+            loop {
+                let Some(c) = self.peek() else {
+                    return Err(self.error_at(start, TextFormatErrorKind::UnterminatedString));
+                };
+                if c == '\n' {
+                    return Err(self.error_at(start, TextFormatErrorKind::UnterminatedString));
+                }
+                self.position += c.len_utf8();
+                match c {
+                    c if c == quote => break,
+                    '\\' => self.escape(&mut value)?,
+                    c => {
+                        let mut buf = [0; 4];
+                        value.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                    }
+                }
+            }
+            self.skip_whitespace();
+        }
+        Ok(value)
+    }
+
+    /// the escape after a backslash, which may stand for a byte or a character
+    fn escape(&mut self, value: &mut Vec<u8>) -> Result<(), TextFormatError> {
+        let start = self.position - 1;
+        let invalid =
+            |parser: &mut Self| parser.error_at(start, TextFormatErrorKind::InvalidEscape);
+        let Some(c) = self.peek() else {
+            return Err(invalid(self));
+        };
+        self.position += c.len_utf8();
+        let byte = match c {
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            '?' | '"' | '\'' | '\\' => c as u8,
+            '0'..='7' => {
+                self.position -= 1;
+                let digits = self.take_while(|c| c.is_digit(8));
+                let digits = &digits[..digits.len().min(3)];
+                self.position = start + 1 + digits.len();
+                u8::try_from(u32::from_str_radix(digits, 8).unwrap_or(0))
+                    .map_err(|_| invalid(self))?
+            }
+            'x' | 'X' => {
+                let digits = self.take_while(|c| c.is_ascii_hexdigit());
+                let digits = &digits[..digits.len().min(2)];
+                if digits.is_empty() {
+                    return Err(invalid(self));
+                }
+                self.position = start + 2 + digits.len();
+                u8::from_str_radix(digits, 16).map_err(|_| invalid(self))?
+            }
+            'u' | 'U' => {
+                let width = if c == 'u' { 4 } else { 8 };
+                let digits = self
+                    .text
+                    .get(self.position..self.position + width)
+                    .ok_or_else(|| invalid(self))?;
+                let code = u32::from_str_radix(digits, 16).map_err(|_| invalid(self))?;
+                let c = char::from_u32(code).ok_or_else(|| invalid(self))?;
+                self.position += width;
+                let mut buf = [0; 4];
+                value.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                return Ok(());
+            }
+            _ => return Err(invalid(self)),
+        };
+        value.push(byte);
+        Ok(())
+    }
+}
+
+impl Number<'_> {
+    /// the number as an integer: decimal, `0x` hex or `0` prefixed octal
+    fn integer(&self) -> Option<i128> {
+        let text = self.text;
+        let magnitude = if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+            u64::from_str_radix(hex, 16).ok()?
+        } else if text.len() > 1 && text.starts_with('0') {
+            u64::from_str_radix(&text[1..], 8).ok()?
+        } else if text.bytes().all(|b| b.is_ascii_digit()) {
+            text.parse().ok()?
+        } else {
+            return None;
+        };
+        Some(match self.negative {
+            true => -(magnitude as i128),
+            false => magnitude as i128,
+        })
+    }
+
+    /// the number as a float, which may have an `f` suffix
+    fn float(&self) -> Option<f64> {
+        let text = self.text.trim_end_matches(['f', 'F']);
+        if text.starts_with("0x") || text.starts_with("0X") {
+            return None;
+        }
+        let value: f64 = text.parse().ok()?;
+        Some(match self.negative {
+            true => -value,
+            false => value,
+        })
+    }
+}
@@ -0,0 +1,245 @@
+//! Typed accessors following protobuf's decoding rules, as used by generated code
+//!
+//! Singular fields take their last occurrence, skipping occurrences with a wire type the
+//! field can't have, like protobuf parsers treat them as unknown fields. Repeated scalars
+//! accept both packed and unpacked occurrences, mixed in any order. Nested messages are
+//! shared with the outer buffer where possible rather than copied.
+
+use alloc::vec::Vec;
+
+use crate::borrowed::{
+    range_of, FieldRef, I32Ref, I64Ref, LenRef, MessageRef, PackedRepeatedI32Ref,
+    PackedRepeatedI64Ref, PackedRepeatedVarintRef, VarintRef,
+};
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::message::Message;
+use crate::wire_data::WireData;
+
+impl<'a> MessageRef<'a> {
+    /// the last occurrence of `field_id` for which `f` returns a value
+    fn last<T>(
+        &self,
+        field_id: u64,
+        f: impl Fn(FieldRef<'a>) -> Option<T>,
+    ) -> Result<Option<T>, DecodeError> {
+        let mut last = None;
+        for field in self.get_all(field_id) {
+            if let Some(value) = f(field?) {
+                last = Some(value);
+            }
+        }
+        Ok(last)
+    }
+
+    /// the last occurrence of `field_id` encoded as a varint
+    pub fn get_varint(&self, field_id: u64) -> Result<Option<VarintRef<'a>>, DecodeError> {
+        self.last(field_id, FieldRef::into_varint)
+    }
+
+    /// the last occurrence of `field_id` encoded as an `I32`
+    pub fn get_i32(&self, field_id: u64) -> Result<Option<I32Ref<'a>>, DecodeError> {
+        self.last(field_id, FieldRef::into_i32)
+    }
+
+    /// the last occurrence of `field_id` encoded as an `I64`
+    pub fn get_i64(&self, field_id: u64) -> Result<Option<I64Ref<'a>>, DecodeError> {
+        self.last(field_id, FieldRef::into_i64)
+    }
+
+    /// the last occurrence of `field_id` encoded as a `Len`
+    pub fn get_len(&self, field_id: u64) -> Result<Option<LenRef<'a>>, DecodeError> {
+        self.last(field_id, FieldRef::into_len)
+    }
+
+    /// the last occurrence of `field_id` encoded as a `Len`, as a string, failing with
+    /// `DecodeErrorKind::InvalidUtf8` if it isn't valid UTF-8
+    pub fn get_str(&self, field_id: u64) -> Result<Option<&'a str>, DecodeError> {
+        self.get_len(field_id)?
+            .map(|len| self.len_str(field_id, len))
+            .transpose()
+    }
+
+    fn len_str(&self, field_id: u64, len: LenRef<'a>) -> Result<&'a str, DecodeError> {
+        len.as_str().map_err(|_| {
+            let offset = range_of(self.0, len.inner).start;
+            DecodeError::new(DecodeErrorKind::InvalidUtf8, offset).within(field_id)
+        })
+    }
+
+    /// every occurrence of `field_id` encoded as a `Len`
+    pub fn get_all_lens(&self, field_id: u64) -> Result<Vec<LenRef<'a>>, DecodeError> {
+        let mut values = Vec::new();
+        for field in self.get_all(field_id) {
+            values.extend(field?.into_len());
+        }
+        Ok(values)
+    }
+
+    /// every occurrence of `field_id` encoded as a `Len`, as strings, see `get_str`
+    pub fn get_all_strs(&self, field_id: u64) -> Result<Vec<&'a str>, DecodeError> {
+        self.get_all_lens(field_id)?
+            .into_iter()
+            .map(|len| self.len_str(field_id, len))
+            .collect()
+    }
+
+    /// the values of every varint occurrence of `field_id`, unpacked or packed
+    pub fn get_all_varints(&self, field_id: u64) -> Result<Vec<u64>, DecodeError> {
+        self.get_all_scalars(
+            field_id,
+            |field| field.as_varint().map(VarintRef::get),
+            |data| {
+                PackedRepeatedVarintRef(data)
+                    .into_try_iter()
+                    .map(|value| value.map(|value| value.get()))
+                    .collect()
+            },
+        )
+    }
+
+    /// the values of every `I32` occurrence of `field_id`, unpacked or packed
+    pub fn get_all_i32s(&self, field_id: u64) -> Result<Vec<i32>, DecodeError> {
+        self.get_all_scalars(
+            field_id,
+            |field| field.as_i32().map(I32Ref::get),
+            |data| {
+                PackedRepeatedI32Ref(data)
+                    .into_try_iter()
+                    .map(|value| value.map(|value| value.get()))
+                    .collect()
+            },
+        )
+    }
+
+    /// the values of every `I64` occurrence of `field_id`, unpacked or packed
+    pub fn get_all_i64s(&self, field_id: u64) -> Result<Vec<i64>, DecodeError> {
+        self.get_all_scalars(
+            field_id,
+            |field| field.as_i64().map(I64Ref::get),
+            |data| {
+                PackedRepeatedI64Ref(data)
+                    .into_try_iter()
+                    .map(|value| value.map(|value| value.get()))
+                    .collect()
+            },
+        )
+    }
+
+    /// the values of every occurrence of `field_id`, read by `unpacked` or, for `Len`
+    /// occurrences, by `packed` from the payload
+    fn get_all_scalars<T>(
+        &self,
+        field_id: u64,
+        unpacked: impl Fn(&FieldRef<'a>) -> Option<T>,
+        packed: impl Fn(&'a [u8]) -> Result<Vec<T>, DecodeError>,
+    ) -> Result<Vec<T>, DecodeError> {
+        let mut values = Vec::new();
+        for field in self.get_all(field_id) {
+            let field = field?;
+            if let Some(value) = unpacked(&field) {
+                values.push(value);
+            } else if let Some(len) = field.as_len() {
+                let offset = range_of(self.0, len.inner).start;
+                values.extend(packed(len.inner).map_err(|e| e.shift(offset).within(field_id))?);
+            }
+        }
+        Ok(values)
+    }
+}
+
+impl Message {
+    /// see `MessageRef::get_varint`
+    pub fn get_varint(&self, field_id: u64) -> Result<Option<VarintRef<'_>>, DecodeError> {
+        self.view().get_varint(field_id)
+    }
+
+    /// see `MessageRef::get_i32`
+    pub fn get_i32(&self, field_id: u64) -> Result<Option<I32Ref<'_>>, DecodeError> {
+        self.view().get_i32(field_id)
+    }
+
+    /// see `MessageRef::get_i64`
+    pub fn get_i64(&self, field_id: u64) -> Result<Option<I64Ref<'_>>, DecodeError> {
+        self.view().get_i64(field_id)
+    }
+
+    /// see `MessageRef::get_len`
+    pub fn get_len(&self, field_id: u64) -> Result<Option<LenRef<'_>>, DecodeError> {
+        self.view().get_len(field_id)
+    }
+
+    /// see `MessageRef::get_str`
+    pub fn get_str(&self, field_id: u64) -> Result<Option<&str>, DecodeError> {
+        self.view().get_str(field_id)
+    }
+
+    /// see `MessageRef::get_all_lens`
+    pub fn get_all_lens(&self, field_id: u64) -> Result<Vec<LenRef<'_>>, DecodeError> {
+        self.view().get_all_lens(field_id)
+    }
+
+    /// see `MessageRef::get_all_strs`
+    pub fn get_all_strs(&self, field_id: u64) -> Result<Vec<&str>, DecodeError> {
+        self.view().get_all_strs(field_id)
+    }
+
+    /// see `MessageRef::get_all_varints`
+    pub fn get_all_varints(&self, field_id: u64) -> Result<Vec<u64>, DecodeError> {
+        self.view().get_all_varints(field_id)
+    }
+
+    /// see `MessageRef::get_all_i32s`
+    pub fn get_all_i32s(&self, field_id: u64) -> Result<Vec<i32>, DecodeError> {
+        self.view().get_all_i32s(field_id)
+    }
+
+    /// see `MessageRef::get_all_i64s`
+    pub fn get_all_i64s(&self, field_id: u64) -> Result<Vec<i64>, DecodeError> {
+        self.view().get_all_i64s(field_id)
+    }
+
+    /// the message held by `field_id`, either as a `Len` or as a group, merging every
+    /// occurrence the way protobuf does by concatenating them
+    ///
+    /// A single occurrence shares the buffer of this message when it is immutable, so no
+    /// bytes are copied.
+    pub fn get_message(&self, field_id: u64) -> Result<Option<Message>, DecodeError> {
+        let mut payloads = self.message_payloads(field_id)?;
+        Ok(match payloads.len() {
+            0 => None,
+            1 => Some(self.share(payloads.remove(0))),
+            _ => Some(Message(WireData::new(payloads.concat()))),
+        })
+    }
+
+    /// every message held by `field_id`, as a `Len` or as a group, sharing the buffer of
+    /// this message when it is immutable
+    pub fn get_all_messages(&self, field_id: u64) -> Result<Vec<Message>, DecodeError> {
+        Ok(self
+            .message_payloads(field_id)?
+            .into_iter()
+            .map(|payload| self.share(payload))
+            .collect())
+    }
+
+    fn message_payloads(&self, field_id: u64) -> Result<Vec<&[u8]>, DecodeError> {
+        let mut payloads = Vec::new();
+        for field in self.get_all(field_id) {
+            let field = field?;
+            if let Some(len) = field.as_len() {
+                payloads.push(len.inner);
+            } else if let Some(payload) = field.group_payload() {
+                payloads.push(payload);
+            }
+        }
+        Ok(payloads)
+    }
+
+    /// `data`, which must borrow from this message, as a message of its own
+    fn share(&self, data: &[u8]) -> Message {
+        match &self.0 {
+            WireData::Const(bytes) => Message(WireData::Const(bytes.slice_ref(data))),
+            WireData::Mut(_) => Message(WireData::Const(bytes::Bytes::copy_from_slice(data))),
+        }
+    }
+}
@@ -1,10 +1,10 @@
+use crate::decode_options::DecodeOptions;
+use crate::error::{DecodeError, DecodeErrorKind};
 use crate::wire_data::WireData;
 
-use anyhow::{anyhow, Result};
-
 pub struct Varint(pub(crate) WireData);
 
-impl std::default::Default for Varint {
+impl core::default::Default for Varint {
     fn default() -> Self {
         Self::new(u64::default())
     }
@@ -56,29 +56,52 @@ impl Varint {
         result
     }
 
-    pub fn from(mut data: WireData) -> Result<(Self, WireData)> {
+    pub fn from(data: WireData) -> Result<(Self, WireData), DecodeError> {
+        Self::from_with_options(data, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        mut data: WireData,
+        options: &DecodeOptions,
+    ) -> Result<(Self, WireData), DecodeError> {
+        let len = Self::scan(data.as_ref(), options)?;
+        let remainder = data.split_off(len);
+
+        Ok((Self(data), remainder))
+    }
+
+    /// validates the varint at the start of `data`, returning how many bytes it occupies
+    pub(crate) fn scan(data: &[u8], options: &DecodeOptions) -> Result<usize, DecodeError> {
         let mut len = 0;
         let mut valid = false;
-        for byte in data.iter() {
+        // a u64 never needs more than 10 bytes, so there is no point scanning further
+        for byte in data.iter().take(10) {
             len += 1;
             if byte & 0b1000_0000 == 0 {
                 valid = true;
                 break;
             }
         }
-        if !valid {
-            Err(anyhow!("Varint message has no terminating byte"))
-        } else if len > 10 {
-            Err(anyhow!("Varint message is too long"))
+        if !valid && data.len() > 10 {
+            Err(DecodeError::new(DecodeErrorKind::OverlongVarint, 0))
+        } else if !valid {
+            Err(DecodeError::new(DecodeErrorKind::TruncatedVarint, 0))
+        } else if options.strict && len > 1 && data[len - 1] == 0 {
+            // a zero final byte only contributes leading zeros to the value
+            Err(DecodeError::new(DecodeErrorKind::NonCanonicalVarint, 0))
+        } else if options.strict && len == 10 && data[9] > 1 {
+            // the 10th byte holds bit 63 alone, anything more would be discarded
+            Err(DecodeError::new(DecodeErrorKind::VarintOverflow, 0))
         } else {
-            let remainder = data.split_off(len);
-
-            Ok((Self(data), remainder))
+            Ok(len)
         }
     }
 
     pub fn get(&self) -> u64 {
-        let buf = self.0.as_ref();
+        Self::decode(self.0.as_ref())
+    }
+
+    pub(crate) fn decode(buf: &[u8]) -> u64 {
         let mut result = 0u64;
 
         for (index, byte) in buf.iter().enumerate() {
@@ -113,7 +136,11 @@ impl Varint {
     }
 
     pub fn as_proto_sint32(&self) -> i32 {
-        let value = self.get() as i32;
+        Self::decode_sint32(self.get())
+    }
+
+    pub(crate) fn decode_sint32(value: u64) -> i32 {
+        let value = value as i32;
         // conversion from 2s complement to zigzag for N: (N << 1) ^ (N >> 31)
         // so inverse of that conversion for N: (N >> 1) ^ (-(N & 1))
         (value >> 1) ^ (-(value & 1))
@@ -125,7 +152,11 @@ impl Varint {
     }
 
     pub fn as_proto_sint64(&self) -> i64 {
-        let value = self.get() as i64;
+        Self::decode_sint64(self.get())
+    }
+
+    pub(crate) fn decode_sint64(value: u64) -> i64 {
+        let value = value as i64;
         // conversion from 2s complement to zigzag for N: (N << 1) ^ (N >> 64)
         // so inverse of that conversion for N: (N >> 1) ^ (-(N & 1))
         (value >> 1) ^ (-(value & 1))
@@ -161,6 +192,17 @@ impl Varint {
         bytes.extend_from_slice(&varint_bytes[0..len]);
     }
 
+    /// how many bytes the canonical encoding of `value` takes
+    pub(crate) fn encoded_len(value: u64) -> usize {
+        (64 - (value | 1).leading_zeros() as usize).div_ceil(7)
+    }
+
+    /// appends the canonical encoding of `value` to `dest`
+    pub(crate) fn encode_into(value: u64, dest: &mut bytes::BytesMut) {
+        let bytes = Self::encode(value);
+        dest.extend_from_slice(&bytes[..Self::encoded_len(value)]);
+    }
+
     pub fn encode(value: u64) -> [u8; 10] {
         // consts for the highest value storable for a certain number of 7-bit bytes
         const ONE: u64 = 0b111_1111;
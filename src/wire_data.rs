@@ -4,7 +4,7 @@ pub enum WireData {
     Mut(bytes::BytesMut),
 }
 
-impl std::convert::AsRef<[u8]> for WireData {
+impl core::convert::AsRef<[u8]> for WireData {
     fn as_ref(&self) -> &[u8] {
         match self {
             Self::Const(buf) => buf.as_ref(),
@@ -71,7 +71,7 @@ impl WireData {
     pub fn get_mut(&mut self) -> &mut bytes::BytesMut {
         match self {
             Self::Const(buf) => {
-                let buf = std::mem::replace(buf, bytes::Bytes::new());
+                let buf = core::mem::replace(buf, bytes::Bytes::new());
                 let mut_copy = match buf.try_into_mut() {
                     Ok(buf) => buf,
                     Err(buf) => {
@@ -111,4 +111,35 @@ impl WireData {
             Self::Mut(buf) => Self::Mut(buf),
         }
     }
+
+    pub fn into_const(self) -> Self {
+        match self {
+            Self::Const(buf) => Self::Const(buf),
+            Self::Mut(buf) => Self::Const(buf.freeze()),
+        }
+    }
+
+    /// the data as reference counted `Bytes`, freezing it if it is mutable
+    pub fn into_bytes(self) -> bytes::Bytes {
+        match self {
+            Self::Const(buf) => buf,
+            Self::Mut(buf) => buf.freeze(),
+        }
+    }
+
+    /// replaces the bytes in `range` with `replacement`, moving everything after the range
+    /// to make room (or close the gap) without reallocating unless the data grows
+    pub fn splice(&mut self, range: core::ops::Range<usize>, replacement: &[u8]) {
+        let buf = self.get_mut();
+        let len = buf.len();
+        let end = range.start + replacement.len();
+        if end > range.end {
+            buf.resize(len + (end - range.end), 0);
+            buf.copy_within(range.end..len, end);
+        } else if end < range.end {
+            buf.copy_within(range.end..len, end);
+            buf.truncate(len - (range.end - end));
+        }
+        buf[range.start..end].copy_from_slice(replacement);
+    }
 }